@@ -0,0 +1,205 @@
+// Sandboxed execution of `$(command)` substitutions.
+//
+// Commands are never run through a shell. The command line is split into
+// words honoring single and double quotes and backslash escapes, and anything
+// a shell would interpret (pipes, redirections, globs, nested substitutions
+// etc.) is rejected. The program has to be on the allow-list, gets a cleared
+// environment, no stdin, and is killed when it exceeds the timeout.
+use std::{ffi::{OsStr, OsString}, io::Read, path::PathBuf, process::{Command, ExitStatus, Stdio}, time::{Duration, Instant}};
+
+#[derive(Debug)]
+pub(crate) enum CommandError {
+    Syntax(&'static str),
+    Empty,
+    NotAllowed(String),
+    Spawn(std::io::Error),
+    Timeout(Duration),
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax(msg)        => write!(f, "{msg}"),
+            Self::Empty              => write!(f, "empty command"),
+            Self::NotAllowed(name)   => write!(f, "program {name:?} is not allowed"),
+            Self::Spawn(err)         => write!(f, "{err}"),
+            Self::Timeout(timeout)   => write!(f, "timed out after {}s", timeout.as_secs_f64()),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Spawn(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    #[inline]
+    fn from(value: std::io::Error) -> Self {
+        Self::Spawn(value)
+    }
+}
+
+#[inline]
+fn is_shell_meta(ch: char) -> bool {
+    matches!(ch, '|' | '&' | ';' | '<' | '>' | '(' | ')' | '$' | '`' | '*' | '?' | '[' | ']' | '{' | '}' | '~' | '!' | '#')
+}
+
+pub(crate) fn split_command(src: &str) -> Result<Vec<String>, CommandError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = src.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            ' ' | '\t' | '\n' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        None => return Err(CommandError::Syntax("unterminated single quoted string")),
+                        Some('\'') => break,
+                        Some(ch) => word.push(ch),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        None => return Err(CommandError::Syntax("unterminated double quoted string")),
+                        Some('"') => break,
+                        Some('\\') => {
+                            match chars.next() {
+                                None => return Err(CommandError::Syntax("unterminated double quoted string")),
+                                Some(ch @ ('"' | '\\' | '$' | '`')) => word.push(ch),
+                                Some('\n') => {},
+                                Some(ch) => {
+                                    word.push('\\');
+                                    word.push(ch);
+                                }
+                            }
+                        }
+                        Some('$' | '`') => return Err(CommandError::Syntax("substitutions are not supported")),
+                        Some(ch) => word.push(ch),
+                    }
+                }
+            }
+            '\\' => {
+                match chars.next() {
+                    None => return Err(CommandError::Syntax("backslash at end of command")),
+                    Some('\n') => {},
+                    Some(ch) => {
+                        in_word = true;
+                        word.push(ch);
+                    }
+                }
+            }
+            ch if is_shell_meta(ch) => {
+                return Err(CommandError::Syntax("shell syntax is not supported"));
+            }
+            ch => {
+                in_word = true;
+                word.push(ch);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Look up a program name in `search_path`, because the command itself is run
+/// with an empty environment. This is never the current `PATH` of the process,
+/// which might have been set by the very `.env` file that is being loaded.
+fn resolve_program(name: &str, search_path: Option<&OsStr>) -> Option<PathBuf> {
+    if name.contains(std::path::is_separator) {
+        return Some(name.into());
+    }
+
+    std::env::split_paths(search_path?).
+        map(|dir| dir.join(name)).
+        find(|program| program.is_file())
+}
+
+/// Run `args` and return its standard output with one trailing line break
+/// removed, like Ruby's `String#chomp`.
+pub(crate) fn run_command(args: &[String], allow: &[OsString], search_path: Option<&OsStr>, timeout: Duration) -> Result<(String, ExitStatus), CommandError> {
+    let Some((program, args)) = args.split_first() else {
+        return Err(CommandError::Empty);
+    };
+
+    if !allow.iter().any(|allowed| allowed == program.as_str()) {
+        return Err(CommandError::NotAllowed(program.clone()));
+    }
+
+    let Some(program) = resolve_program(program, search_path) else {
+        return Err(CommandError::Spawn(std::io::ErrorKind::NotFound.into()));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let Some(mut stdout) = child.stdout.take() else {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(CommandError::Spawn(std::io::ErrorKind::BrokenPipe.into()));
+    };
+
+    // read in a thread so a chatty command can't block on a full pipe
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            // The reader thread is not joined, because a grand child might
+            // still hold on to the pipe.
+            return Err(CommandError::Timeout(timeout));
+        }
+
+        std::thread::sleep((deadline - now).min(Duration::from_millis(5)));
+    };
+
+    let output = match reader.join() {
+        Ok(output) => output?,
+        Err(_) => return Err(CommandError::Spawn(std::io::ErrorKind::Other.into())),
+    };
+
+    let mut output = String::from_utf8_lossy(&output).into_owned();
+    if output.ends_with("\r\n") {
+        output.truncate(output.len() - 2);
+    } else if output.ends_with(['\n', '\r']) {
+        output.truncate(output.len() - 1);
+    }
+
+    Ok((output, status))
+}
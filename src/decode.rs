@@ -0,0 +1,186 @@
+// Decoding of prefixed values after parsing, independent of the dialect:
+// `KEY=base64:...`, `KEY=hex:...`, and `KEY=@path/to/file`.
+use std::{ffi::{OsStr, OsString}, path::Path};
+
+use crate::{env::parse_comma_list, file_secrets::base_dir, options::{IllegalOption, OptionType}, Env, Error, ErrorKind, Options, Result, DEBUG_PREFIX};
+
+/// The enabled value decoders. None are enabled per default.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Decoders {
+    /// Decode values starting with `base64:`. Both the standard and the URL
+    /// safe alphabet are accepted, padding is optional and white space is
+    /// ignored.
+    pub base64: bool,
+    /// Decode values starting with `hex:`. White space is ignored.
+    pub hex: bool,
+    /// Replace values starting with `@` with the content of the named file.
+    /// Relative paths are relative to the directory of the `.env` file.
+    pub file: bool,
+}
+
+impl Decoders {
+    pub const ALL: Decoders = Decoders { base64: true, hex: true, file: true };
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.base64 || self.hex || self.file
+    }
+}
+
+impl std::fmt::Display for Decoders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = [(self.base64, "base64"), (self.hex, "hex"), (self.file, "file")];
+        let mut first = true;
+        for (_, name) in names.iter().filter(|(enabled, _)| *enabled) {
+            if !first {
+                ",".fmt(f)?;
+            }
+            name.fmt(f)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&OsStr> for Decoders {
+    type Error = IllegalOption;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, IllegalOption> {
+        let illegal = || IllegalOption::new("decoders".into(), value.into(), OptionType::Decoders);
+        let mut decoders = Decoders::default();
+        for name in parse_comma_list(value).ok_or_else(illegal)? {
+            if name.eq_ignore_ascii_case("base64") {
+                decoders.base64 = true;
+            } else if name.eq_ignore_ascii_case("hex") {
+                decoders.hex = true;
+            } else if name.eq_ignore_ascii_case("file") {
+                decoders.file = true;
+            } else if name.eq_ignore_ascii_case("all") {
+                decoders = Decoders::ALL;
+            } else {
+                return Err(illegal());
+            }
+        }
+        Ok(decoders)
+    }
+}
+
+fn base64_digit(byte: u8) -> Option<u32> {
+    match byte {
+        b'A'..=b'Z' => Some((byte - b'A') as u32),
+        b'a'..=b'z' => Some((byte - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((byte - b'0') as u32 + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    }
+}
+
+pub fn decode_base64(value: &str) -> std::result::Result<Vec<u8>, String> {
+    let digits: Vec<u8> = value.bytes().filter(|byte| !byte.is_ascii_whitespace()).collect();
+    let data = digits.strip_suffix(b"==").or_else(|| digits.strip_suffix(b"=")).unwrap_or(&digits);
+
+    if (data.len() != digits.len() && !digits.len().is_multiple_of(4)) || data.len() % 4 == 1 {
+        return Err("illegal base64 length".to_owned());
+    }
+
+    let mut decoded = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut bits = 0;
+        for (index, &byte) in chunk.iter().enumerate() {
+            let Some(digit) = base64_digit(byte) else {
+                return Err(format!("illegal base64 character {:?}", byte as char));
+            };
+            bits |= digit << (18 - 6 * index);
+        }
+        let bytes = bits.to_be_bytes();
+        decoded.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+
+    Ok(decoded)
+}
+
+pub fn decode_hex(value: &str) -> std::result::Result<Vec<u8>, String> {
+    let digits: Vec<u8> = value.bytes().filter(|byte| !byte.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".to_owned());
+    }
+
+    if let Some(&byte) = digits.iter().find(|byte| !byte.is_ascii_hexdigit()) {
+        return Err(format!("illegal hex character {:?}", byte as char));
+    }
+
+    Ok(digits.chunks(2).map(|pair| {
+        let digit = |byte: u8| (byte as char).to_digit(16).unwrap_or(0) as u8;
+        digit(pair[0]) << 4 | digit(pair[1])
+    }).collect())
+}
+
+/// Environment variables can't contain NUL bytes and on Windows they have to
+/// be valid Unicode.
+fn into_os_string(bytes: Vec<u8>) -> std::result::Result<OsString, String> {
+    if let Some(index) = bytes.iter().position(|&byte| byte == 0) {
+        return Err(format!("decoded value contains a NUL byte at offset {index}"));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Ok(OsString::from_vec(bytes))
+    }
+
+    #[cfg(not(unix))]
+    {
+        String::from_utf8(bytes)
+            .map(OsString::from)
+            .map_err(|err| format!("decoded value is not valid UTF-8: {err}"))
+    }
+}
+
+fn decode_value(value: &OsStr, decoders: Decoders, dir: &Path) -> Option<std::result::Result<OsString, (ErrorKind, String)>> {
+    let value = value.to_str()?;
+    let decoded = if let Some(data) = value.strip_prefix("base64:").filter(|_| decoders.base64) {
+        decode_base64(data).map_err(|err| (ErrorKind::DecodeError, err))
+    } else if let Some(data) = value.strip_prefix("hex:").filter(|_| decoders.hex) {
+        decode_hex(data).map_err(|err| (ErrorKind::DecodeError, err))
+    } else if let Some(file) = value.strip_prefix('@').filter(|_| decoders.file) {
+        let path = dir.join(file);
+        std::fs::read(&path).map_err(|err| (ErrorKind::IOError, format!("{}: {err}", path.to_string_lossy())))
+    } else {
+        return None;
+    };
+
+    Some(decoded.and_then(|bytes| into_os_string(bytes).map_err(|err| (ErrorKind::DecodeError, err))))
+}
+
+/// Decode the values of `keys` in place. A value that can't be decoded is
+/// kept as it is if not in strict mode.
+pub(crate) fn decode_values(env: &mut dyn Env, keys: &[OsString], options: &Options<&Path>) -> Result<()> {
+    let dir = base_dir(options);
+
+    for key in keys {
+        // might have been removed again
+        let Some(value) = env.get(key) else {
+            continue;
+        };
+
+        let (kind, message) = match decode_value(&value, options.decoders, dir) {
+            None => continue,
+            Some(Ok(decoded)) => {
+                env.set(key, &decoded);
+                continue;
+            }
+            Some(Err((kind, err))) => (kind, format!("{}: {err}", key.to_string_lossy())),
+        };
+
+        if options.debug {
+            eprintln!("{DEBUG_PREFIX}{}: {message}", options.path.to_string_lossy());
+        }
+
+        if options.strict {
+            return Err(Error::with_cause(kind, message));
+        }
+    }
+
+    Ok(())
+}
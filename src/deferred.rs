@@ -0,0 +1,353 @@
+// Deferred variable substitution, i.e. references to variables that are only
+// defined further down in the same file.
+//
+// Instead of teaching every dialect a second way to resolve variables, the
+// file is parsed multiple times by the normal dialect parser. It writes into
+// a scratch layer over the actual environment, which records the variables
+// read for each definition. A reference that would be undefined, but is
+// defined later in the file, gets the value of that (last) definition from the
+// previous pass. This is repeated until no value changes anymore, which takes
+// as many passes as the longest chain of forward references. A definition
+// referencing its own key (`PATH=$PATH:/opt/bin`) still sees the previous
+// value.
+//
+// Command substitutions and resolvers must not run once per pass, though.
+// Their results are cached by source location for the whole load.
+use std::{borrow::Cow, cell::{Cell, RefCell}, collections::HashMap, ffi::{OsStr, OsString}, io::BufRead, path::Path};
+
+use crate::{env::GetEnv, error::SourceLocation, options::UndefinedVars, DialectParser, Env, Error, ErrorKind, Options, Result};
+
+type SubstitutionKey = (String, usize, usize, String);
+
+thread_local! {
+    /// Results of command substitutions and resolvers of the deferred load
+    /// currently running on this thread, if any.
+    static SUBSTITUTIONS: RefCell<Option<HashMap<SubstitutionKey, std::result::Result<String, String>>>> = const { RefCell::new(None) };
+
+    /// Location of the key that is defined next, see [`key_location()`].
+    static KEY_LOCATION: Cell<Option<SourceLocation>> = const { Cell::new(None) };
+
+    /// See [`is_scratch_pass()`].
+    static SCRATCH_PASS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Is this one of the passes before the final one? Forward references might
+/// still be undefined then, so errors for required variables (`${VAR:?}`)
+/// have to wait for the final pass.
+#[inline]
+pub(crate) fn is_scratch_pass() -> bool {
+    SCRATCH_PASS.with(Cell::get)
+}
+
+/// Called by [`Options::check_key()`] for every key before it is defined, so
+/// the definition knows where it comes from.
+#[inline]
+pub(crate) fn key_location(location: SourceLocation) {
+    KEY_LOCATION.with(|key_location| key_location.set(Some(location)));
+}
+
+/// Enables the substitution cache until dropped.
+struct SubstitutionCache(Option<HashMap<SubstitutionKey, std::result::Result<String, String>>>);
+
+impl SubstitutionCache {
+    fn enable() -> Self {
+        Self(SUBSTITUTIONS.with(|cache| cache.replace(Some(HashMap::new()))))
+    }
+}
+
+impl Drop for SubstitutionCache {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SUBSTITUTIONS.with(|cache| *cache.borrow_mut() = previous);
+    }
+}
+
+/// Runs `substitute` only once per source location and `text` during a
+/// deferred load, later passes get the cached result (including errors).
+/// Outside of a deferred load it is always run.
+pub(crate) fn cached_substitution(path: &str, location: SourceLocation, text: &str, substitute: impl FnOnce() -> std::result::Result<String, String>) -> std::result::Result<String, String> {
+    let enabled = SUBSTITUTIONS.with(|cache| cache.borrow().is_some());
+    if !enabled {
+        return substitute();
+    }
+
+    let key = (path.to_owned(), location.lineno(), location.column(), text.to_owned());
+    if let Some(result) = SUBSTITUTIONS.with(|cache| cache.borrow().as_ref().and_then(|cache| cache.get(&key).cloned())) {
+        return result;
+    }
+
+    let result = substitute();
+    SUBSTITUTIONS.with(|cache| {
+        if let Some(cache) = cache.borrow_mut().as_mut() {
+            cache.insert(key, result.clone());
+        }
+    });
+
+    result
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Definition {
+    key: OsString,
+    /// `None` for removed variables.
+    value: Option<OsString>,
+    /// Variables read while parsing this definition and the index of the
+    /// definition that provided the value, if any.
+    reads: Vec<(OsString, Option<usize>)>,
+    /// Location of the key.
+    location: Option<SourceLocation>,
+}
+
+struct DeferredEnv<'a> {
+    base: &'a dyn Env,
+    layer: HashMap<OsString, Option<OsString>>,
+    definitions: Vec<Definition>,
+    reads: RefCell<Vec<(OsString, Option<usize>)>>,
+    previous: &'a [Definition],
+}
+
+impl<'a> DeferredEnv<'a> {
+    fn define(&mut self, key: &OsStr, value: Option<&OsStr>) {
+        self.layer.insert(key.to_owned(), value.map(ToOwned::to_owned));
+        self.definitions.push(Definition {
+            key: key.to_owned(),
+            value: value.map(ToOwned::to_owned),
+            reads: std::mem::take(self.reads.get_mut()),
+            location: KEY_LOCATION.with(Cell::take),
+        });
+    }
+
+    fn lookup(&self, key: &OsStr) -> (Option<Cow<'_, OsStr>>, Option<usize>) {
+        if let Some(value) = self.layer.get(key) {
+            let provider = self.definitions.iter().rposition(|definition| definition.key == key);
+            return (value.as_deref().map(Cow::Borrowed), provider);
+        }
+
+        if let Some(value) = self.base.get(key) {
+            return (Some(value), None);
+        }
+
+        // forward reference, but not to the variable that is currently defined
+        let index = self.definitions.len();
+        if self.previous.get(index).is_some_and(|definition| definition.key == key) {
+            return (None, None);
+        }
+
+        match self.previous.iter().rposition(|definition| definition.key == key) {
+            Some(provider) if provider > index => {
+                let value = self.previous[provider].value.clone().map(Cow::Owned);
+                (value, Some(provider))
+            }
+            _ => (None, None),
+        }
+    }
+}
+
+impl<'a> GetEnv for DeferredEnv<'a> {
+    fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
+        let (value, provider) = self.lookup(key);
+        self.reads.borrow_mut().push((key.to_owned(), provider));
+        value
+    }
+
+    /// Forward references aren't visited, only what is defined so far.
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.base.for_each(&mut |key, value| {
+            if !self.layer.contains_key(key) {
+                f(key, value);
+            }
+        });
+
+        for (key, value) in &self.layer {
+            if let Some(value) = value {
+                f(key, value);
+            }
+        }
+    }
+}
+
+impl<'a> Env for DeferredEnv<'a> {
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        self.define(key, Some(value));
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        self.define(key, None);
+    }
+
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+}
+
+fn parse_pass(src: &[u8], env: &dyn Env, parent: &dyn GetEnv, options: &Options<&Path>, previous: &[Definition], scratch: bool) -> Result<Vec<Definition>> {
+    let mut deferred_env = DeferredEnv {
+        base: env,
+        layer: HashMap::new(),
+        definitions: Vec::new(),
+        reads: RefCell::new(Vec::new()),
+        previous,
+    };
+
+    let outer = SCRATCH_PASS.with(|scratch_pass| scratch_pass.replace(scratch));
+    let result = options.dialect.parse(&mut &src[..], &mut deferred_env, parent, options);
+    SCRATCH_PASS.with(|scratch_pass| scratch_pass.set(outer));
+    result?;
+
+    Ok(deferred_env.definitions)
+}
+
+/// Any unresolved reads that are defined later in the file?
+fn has_forward_references(definitions: &[Definition]) -> bool {
+    definitions.iter().enumerate().any(|(index, definition)| {
+        definition.reads.iter().any(|(key, provider)| {
+            provider.is_none() && *key != definition.key &&
+            definitions[index + 1..].iter().any(|later| later.key == *key)
+        })
+    })
+}
+
+#[inline]
+fn same_values(definitions: &[Definition], previous: &[Definition]) -> bool {
+    definitions.len() == previous.len() &&
+    definitions.iter().zip(previous).all(|(definition, previous)| {
+        definition.key == previous.key && definition.value == previous.value
+    })
+}
+
+/// Returns the indices of the definitions forming a cycle, if there is one.
+fn find_cycle(definitions: &[Definition]) -> Option<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State { New, Active, Done }
+
+    fn visit(index: usize, definitions: &[Definition], state: &mut [State], stack: &mut Vec<usize>) -> Option<Vec<usize>> {
+        state[index] = State::Active;
+        stack.push(index);
+
+        for &(_, provider) in &definitions[index].reads {
+            let Some(provider) = provider else { continue };
+            if provider == index {
+                continue;
+            }
+            match state[provider] {
+                State::Active => {
+                    let start = stack.iter().position(|&item| item == provider).unwrap_or(0);
+                    return Some(stack[start..].to_vec());
+                }
+                State::New => {
+                    if let Some(cycle) = visit(provider, definitions, state, stack) {
+                        return Some(cycle);
+                    }
+                }
+                State::Done => {}
+            }
+        }
+
+        stack.pop();
+        state[index] = State::Done;
+        None
+    }
+
+    let mut state = vec![State::New; definitions.len()];
+    let mut stack = Vec::new();
+    for index in 0..definitions.len() {
+        if state[index] == State::New {
+            if let Some(cycle) = visit(index, definitions, &mut state, &mut stack) {
+                return Some(cycle);
+            }
+        }
+    }
+
+    None
+}
+
+fn cycle_error(options: &Options<&Path>, definitions: &[Definition], cycle: &[usize]) -> Error {
+    // start with the first definition in the file for a deterministic message
+    let mut cycle = cycle.to_vec();
+    if let Some(first) = cycle.iter().enumerate().min_by_key(|&(_, index)| index).map(|(pos, _)| pos) {
+        cycle.rotate_left(first);
+    }
+
+    let path = options.path.to_string_lossy();
+    let locations: Vec<_> = cycle.iter().map(|&index| definitions[index].location).collect();
+    let keys: Vec<_> = cycle.iter().zip(&locations).chain(cycle.iter().zip(&locations).take(1)).map(|(&index, location)| {
+        let key = definitions[index].key.to_string_lossy();
+        match location {
+            Some(location) => format!("{key} ({path}:{}:{})", location.lineno(), location.column()),
+            None => key.into_owned(),
+        }
+    }).collect();
+    let message = format!("cyclic variable references: {}", keys.join(" -> "));
+
+    match locations.first().copied().flatten() {
+        Some(location) => Error::new(ErrorKind::SubstitutionError, message, location),
+        None => Error::with_cause(ErrorKind::SubstitutionError, message),
+    }
+}
+
+pub(crate) fn config_deferred(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut src = Vec::new();
+    reader.read_to_end(&mut src)?;
+
+    let _cache = SubstitutionCache::enable();
+
+    // Don't repeat debug messages for every pass. Forward references are
+    // undefined in the first passes, so errors like `${VAR:?}` are only
+    // raised by the final pass.
+    let quiet_options = Options {
+        debug: false,
+        strict: false,
+        deferred_substitution: false,
+        undefined_vars: UndefinedVars::Ignore,
+        ..options.clone()
+    };
+
+    let mut definitions = parse_pass(&src, env, parent, &quiet_options, &[], true)?;
+    if has_forward_references(&definitions) {
+        let mut passes = 1;
+        loop {
+            let next = parse_pass(&src, env, parent, &quiet_options, &definitions, true)?;
+            let done = same_values(&next, &definitions);
+            definitions = next;
+            passes += 1;
+
+            if done {
+                break;
+            }
+
+            // Without a cycle every pass resolves at least one more level of
+            // forward references.
+            if passes > definitions.len() + 1 {
+                break;
+            }
+        }
+    }
+
+    if let Some(cycle) = find_cycle(&definitions) {
+        let error = cycle_error(options, &definitions, &cycle);
+        if options.debug {
+            eprintln!("{}{error}", crate::DEBUG_PREFIX);
+        }
+        return Err(error);
+    }
+
+    if options.debug || options.strict || options.undefined_vars != UndefinedVars::Ignore {
+        let debug_options = Options {
+            deferred_substitution: false,
+            ..options.clone()
+        };
+        definitions = parse_pass(&src, env, parent, &debug_options, &definitions, false)?;
+    }
+
+    for definition in &definitions {
+        match &definition.value {
+            Some(value) => env.set(&definition.key, value),
+            None => env.remove(&definition.key),
+        }
+    }
+
+    Ok(())
+}
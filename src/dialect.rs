@@ -1,17 +1,45 @@
 use std::ffi::OsStr;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+use crate::dialects::binary::config_binary;
+use crate::dialects::composego::config_composego;
+use crate::dialects::dotnet_env::config_dotnet_env;
+use crate::dialects::elixir_dotenvy::config_elixir_dotenvy;
+use crate::dialects::go_dotenv::config_go_dotenv;
+use crate::dialects::java_dotenv::config_java_dotenv;
+use crate::dialects::javascript_dotenv::config_javascript_dotenv;
+use crate::dialects::nodejs::{config_nodejs, config_nodejs20};
+use crate::dialects::punktum::config_punktum;
+use crate::dialects::python_dotenv::config_python_dotenv;
+use crate::dialects::python_dotenv_cli::config_python_dotenv_cli;
+use crate::dialects::ruby_dotenv::{config_ruby_dotenv, config_ruby_dotenv2};
+use crate::env::GetEnv;
+use crate::{Env, Error, ErrorKind, Options};
+
+#[derive(Debug, Clone, Copy)]
 pub enum Dialect {
     Punktum,
+    /// NodeJS 21.7 and newer (and 20.12 and newer).
     NodeJS,
+    /// NodeJS 20.6 to 20.11 and 21.0 to 21.6.
+    NodeJS20,
     JavaScriptDotenv,
     PythonDotenv,
     PythonDotenvCLI,
     ComposeGo,
     GoDotenv,
+    /// dotenv gem 2.8 and newer.
     RubyDotenv,
+    /// dotenv gem before 2.8.
+    RubyDotenv2,
     JavaDotenv,
+    DotNetEnv,
+    ElixirDotenvy,
     Binary,
+    /// A dialect registered with [`register_dialect()`].
+    Custom(&'static str),
 }
 
 impl Default for Dialect {
@@ -21,10 +49,24 @@ impl Default for Dialect {
     }
 }
 
+/// Names of custom dialects are compared case-insensitively, the same way
+/// they are looked up in the registry.
+impl PartialEq for Dialect {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Dialect::Custom(name), Dialect::Custom(other)) => name.eq_ignore_ascii_case(other),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
 impl std::fmt::Display for Dialect {
     #[inline]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        match self {
+            Dialect::Custom(name) => name.fmt(f),
+            _ => std::fmt::Debug::fmt(&self, f),
+        }
     }
 }
 
@@ -32,11 +74,20 @@ impl TryFrom<&OsStr> for Dialect {
     type Error = IllegalDialect;
 
     fn try_from(value: &OsStr) -> Result<Self, Self::Error> {
+        if let Some((name, version)) = value.to_str().and_then(|value| value.split_once('@')) {
+            let Some(version) = Version::parse(version) else {
+                return Err(IllegalDialect());
+            };
+            return Dialect::try_from(OsStr::new(name))?.with_version(version);
+        }
+
         if value.is_empty() ||
            value.eq_ignore_ascii_case("punktum") {
             Ok(Dialect::Punktum)
         } else if value.eq_ignore_ascii_case("nodejs") {
             Ok(Dialect::NodeJS)
+        } else if value.eq_ignore_ascii_case("nodejs20") {
+            Ok(Dialect::NodeJS20)
         } else if value.eq_ignore_ascii_case("javascriptdotenv") ||
                   value.eq_ignore_ascii_case("jsdotenv") ||
                   value.eq_ignore_ascii_case("javascript-dotenv") ||
@@ -61,17 +112,213 @@ impl TryFrom<&OsStr> for Dialect {
         } else if value.eq_ignore_ascii_case("rubydotenv") ||
                   value.eq_ignore_ascii_case("ruby-dotenv") {
             Ok(Dialect::RubyDotenv)
+        } else if value.eq_ignore_ascii_case("rubydotenv2") ||
+                  value.eq_ignore_ascii_case("ruby-dotenv2") {
+            Ok(Dialect::RubyDotenv2)
         } else if value.eq_ignore_ascii_case("javadotenv") ||
                   value.eq_ignore_ascii_case("java-dotenv") {
             Ok(Dialect::JavaDotenv)
+        } else if value.eq_ignore_ascii_case("dotnetenv") ||
+                  value.eq_ignore_ascii_case("dotnet-env") ||
+                  value.eq_ignore_ascii_case("dotnet") {
+            Ok(Dialect::DotNetEnv)
+        } else if value.eq_ignore_ascii_case("elixirdotenvy") ||
+                  value.eq_ignore_ascii_case("elixir-dotenvy") ||
+                  value.eq_ignore_ascii_case("dotenvy-ex") ||
+                  value.eq_ignore_ascii_case("elixir") {
+            Ok(Dialect::ElixirDotenvy)
         } else if value.eq_ignore_ascii_case("binary") {
             Ok(Dialect::Binary)
+        } else if let Some(name) = value.to_str().and_then(find_registered_name) {
+            Ok(Dialect::Custom(name))
         } else {
             Err(IllegalDialect())
         }
     }
 }
 
+impl Dialect {
+    /// Select the variant of the dialect that emulates the given upstream version.
+    /// Only the NodeJS and RubyDotenv dialects can have a version.
+    pub fn with_version(self, version: Version) -> Result<Self, IllegalDialect> {
+        match self {
+            Dialect::NodeJS | Dialect::NodeJS20 => {
+                match (version.major, version.minor) {
+                    // --env-file was introduced in NodeJS 20.6
+                    (major, _) if major < 20 => Err(IllegalDialect()),
+                    (20, None) => Ok(Dialect::NodeJS20),
+                    (20, Some(minor)) if minor < 6  => Err(IllegalDialect()),
+                    (20, Some(minor)) if minor < 12 => Ok(Dialect::NodeJS20),
+                    (21, minor) if minor.unwrap_or(0) < 7 => Ok(Dialect::NodeJS20),
+                    _ => Ok(Dialect::NodeJS),
+                }
+            }
+            Dialect::RubyDotenv | Dialect::RubyDotenv2 => {
+                match (version.major, version.minor) {
+                    (2, minor) if minor.unwrap_or(0) < 8 => Ok(Dialect::RubyDotenv2),
+                    (2, _) | (3, _) => Ok(Dialect::RubyDotenv),
+                    _ => Err(IllegalDialect()),
+                }
+            }
+            _ => Err(IllegalDialect()),
+        }
+    }
+}
+
+/// A parser (and optionally a writer) for a `.env` file format.
+///
+/// All built-in dialects implement this via [`Dialect`]. Applications can add
+/// their own formats with [`register_dialect()`].
+pub trait DialectParser: Send + Sync {
+    /// Parse `reader` and set the found variables in `env`. Variable substitutions
+    /// may read from `parent` for variables not defined in `env`.
+    fn parse(&self, reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> crate::Result<()>;
+
+    /// Write a single variable in a syntax that can be read back by [`DialectParser::parse()`].
+    ///
+    /// Returns an error of the kind [`std::io::ErrorKind::Unsupported`] per default.
+    #[inline]
+    fn serialize(&self, _writer: &mut dyn Write, _key: &str, _value: &str) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+impl DialectParser for Dialect {
+    fn parse(&self, reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> crate::Result<()> {
+        match *self {
+            Dialect::Punktum          => config_punktum(          reader, env, parent, options),
+            Dialect::JavaScriptDotenv => config_javascript_dotenv(reader, env, options),
+            Dialect::NodeJS           => config_nodejs(           reader, env, options),
+            Dialect::NodeJS20         => config_nodejs20(         reader, env, options),
+            Dialect::PythonDotenv     => config_python_dotenv(    reader, env, options),
+            Dialect::PythonDotenvCLI  => config_python_dotenv_cli(reader, env, options),
+            Dialect::ComposeGo        => config_composego(        reader, env, parent, options),
+            Dialect::GoDotenv         => config_go_dotenv(        reader, env, options),
+            Dialect::RubyDotenv       => config_ruby_dotenv(      reader, env, parent, options),
+            Dialect::RubyDotenv2      => config_ruby_dotenv2(     reader, env, parent, options),
+            Dialect::JavaDotenv       => config_java_dotenv(      reader, env, options),
+            Dialect::DotNetEnv        => config_dotnet_env(       reader, env, parent, options),
+            Dialect::ElixirDotenvy    => config_elixir_dotenvy(   reader, env, parent, options),
+            Dialect::Binary           => config_binary(           reader, env, options),
+            Dialect::Custom(name) => {
+                let Some(parser) = get_dialect_parser(name) else {
+                    return Err(Error::with_cause(ErrorKind::OptionsParseError, IllegalDialect()));
+                };
+                parser.parse(reader, env, parent, options)
+            }
+        }
+    }
+
+    fn serialize(&self, writer: &mut dyn Write, key: &str, value: &str) -> std::io::Result<()> {
+        match *self {
+            Dialect::Punktum => crate::write_var(writer, key, value),
+            Dialect::Binary  => crate::write_var_binary(writer, key, value),
+            Dialect::Custom(name) => {
+                let Some(parser) = get_dialect_parser(name) else {
+                    return Err(std::io::ErrorKind::Unsupported.into());
+                };
+                parser.serialize(writer, key, value)
+            }
+            _ => Err(std::io::ErrorKind::Unsupported.into()),
+        }
+    }
+}
+
+static DIALECT_REGISTRY: RwLock<Vec<(&'static str, Arc<dyn DialectParser>)>> = RwLock::new(Vec::new());
+
+/// Register a parser for a custom dialect. The name is matched case-insensitively
+/// and can then be used with `DOTENV_CONFIG_DIALECT` and `--dialect`.
+///
+/// Returns an error if the name is empty, contains `@`, or is already used by
+/// a built-in dialect. Registering a name a second time replaces the parser.
+pub fn register_dialect(name: &'static str, parser: impl DialectParser + 'static) -> Result<Dialect, IllegalDialect> {
+    if name.is_empty() || name.contains('@') {
+        return Err(IllegalDialect());
+    }
+
+    if let Ok(dialect) = Dialect::try_from(OsStr::new(name)) {
+        let Dialect::Custom(_) = dialect else {
+            return Err(IllegalDialect());
+        };
+    }
+
+    let mut registry = DIALECT_REGISTRY.write().unwrap_or_else(|err| err.into_inner());
+    let parser: Arc<dyn DialectParser> = Arc::new(parser);
+    if let Some(entry) = registry.iter_mut().find(|(other, _)| other.eq_ignore_ascii_case(name)) {
+        *entry = (name, parser);
+    } else {
+        registry.push((name, parser));
+    }
+
+    Ok(Dialect::Custom(name))
+}
+
+/// Remove a custom dialect. Returns `true` if it was registered.
+pub fn unregister_dialect(name: &str) -> bool {
+    let mut registry = DIALECT_REGISTRY.write().unwrap_or_else(|err| err.into_inner());
+    let len = registry.len();
+    registry.retain(|(other, _)| !other.eq_ignore_ascii_case(name));
+    registry.len() != len
+}
+
+/// Get the parser of a custom dialect.
+pub fn get_dialect_parser(name: &str) -> Option<Arc<dyn DialectParser>> {
+    let registry = DIALECT_REGISTRY.read().unwrap_or_else(|err| err.into_inner());
+    registry.iter().
+        find(|(other, _)| other.eq_ignore_ascii_case(name)).
+        map(|(_, parser)| parser.clone())
+}
+
+fn find_registered_name(name: &str) -> Option<&'static str> {
+    let registry = DIALECT_REGISTRY.read().unwrap_or_else(|err| err.into_inner());
+    registry.iter().
+        find(|(other, _)| other.eq_ignore_ascii_case(name)).
+        map(|(other, _)| *other)
+}
+
+/// Upstream version of a dialect, as in `nodejs@20` or `ruby-dotenv@2.7`.
+/// A patch level may be given, but is ignored.
+///
+/// `minor` is `None` if only the major version was given, which means the
+/// release line in general and not specifically its `.0` release.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Version {
+    pub major: u32,
+    pub minor: Option<u32>,
+}
+
+impl Version {
+    #[inline]
+    pub fn new(major: u32, minor: Option<u32>) -> Self {
+        Self { major, minor }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.strip_prefix(['v', 'V']).unwrap_or(value);
+        let mut parts = value.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = if let Some(minor) = parts.next() { Some(minor.parse().ok()?) } else { None };
+        if let Some(patch) = parts.next() {
+            patch.parse::<u32>().ok()?;
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self { major, minor })
+    }
+}
+
+impl std::fmt::Display for Version {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(minor) = self.minor {
+            write!(f, "{}.{minor}", self.major)
+        } else {
+            write!(f, "{}", self.major)
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct IllegalDialect();
 
@@ -87,6 +87,10 @@ pub fn config_binary(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
             continue;
         }
 
+        if !options.check_key(&key, lineno, 1)? {
+            continue;
+        }
+
         options.set_var(env, key.as_ref(), value.as_ref());
     }
 
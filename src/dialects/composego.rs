@@ -1,6 +1,6 @@
-use std::{borrow::Cow, io::BufRead, num::NonZeroU8, path::Path};
+use std::{borrow::Cow, cell::RefCell, io::BufRead, num::NonZeroU8, path::Path};
 
-use crate::{env::GetEnv, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{deferred::is_scratch_pass, dialects::{nth_reference, report_undefined_var}, env::GetEnv, error::SourceLocation, options::UndefinedVars, Env, Error, ErrorKind, Options, Result, DEBUG_PREFIX};
 
 // trying to be compatible to: https://github.com/compose-spec/compose-go/blob/main/dotenv/parser.go
 pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
@@ -9,13 +9,23 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
     let mut src = String::new();
     options.encoding.read_to_string(reader, &mut src)?;
 
+    // In compat mode every error compose-go reports aborts loading, no matter
+    // if strict mode is on or not.
+    let compat_errors = options.dialect_options.composego.compat_errors;
+    let strict = options.strict || compat_errors;
+
     let src = src.replace("\r\n", "\n");
     let mut cutset = &src[..];
     let mut parser = Parser {
         lineno: 1,
         path: path_str,
         debug: options.debug,
-        strict: options.strict,
+        strict,
+        strict_substitution: options.dialect_options.composego.strict_substitution || compat_errors,
+        compat_errors,
+        unresolved: None,
+        undefined: if options.undefined_vars == UndefinedVars::Ignore { None } else { Some(RefCell::new(Vec::new())) },
+        undefined_refs: Vec::new(),
     };
 
     loop {
@@ -29,7 +39,7 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
 
         let (key, left, inherited) = match parser.locate_key_name(cutset) {
             Err(err) => {
-                if options.strict {
+                if strict {
                     return Err(err);
                 }
                 cutset = skip_to_line_end(cutset);
@@ -42,8 +52,8 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
             if options.debug {
                 eprintln!("{DEBUG_PREFIX}{}:{}: key cannot contain a space: {key:?}", &parser.path, parser.lineno);
             }
-            if options.strict {
-                return Err(Error::syntax_error(parser.lineno, 1));
+            if strict {
+                return Err(parser.syntax_error(format!("line {}: key cannot contain a space", parser.lineno)));
             }
             cutset = skip_to_line_end(cutset);
             continue;
@@ -60,7 +70,12 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
             }
         }
 
+        let key_lineno = parser.lineno;
         if inherited {
+            if !options.check_key(raw_key, key_lineno, 1)? {
+                cutset = left;
+                continue;
+            }
             if let Some(value) = parent.get(key.as_ref()) {
                 options.set_var(env, key.as_ref(), &value);
             }
@@ -69,13 +84,20 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
         }
         let (value, left) = match parser.extract_var_value(left, env.as_get_env()) {
             Err(err) => {
-                if options.strict {
+                if strict {
                     return Err(err);
                 }
+                parser.clear_undefined();
                 cutset = skip_to_line_end(cutset);
                 continue;
             },
-            Ok(res) => res
+            Ok((value, rest)) => {
+                let range = src.len() - left.len()..src.len() - rest.len();
+                for (name, nth) in std::mem::take(&mut parser.undefined_refs) {
+                    report_undefined_var(&src, range.clone(), &name, nth, options)?;
+                }
+                (value, rest)
+            }
         };
         let raw_value = &value;
         let value = value.split('\0').next().unwrap();
@@ -88,22 +110,268 @@ pub fn config_composego(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dy
             }
         }
 
-        options.set_var(env, key.as_ref(), value.as_ref());
-
         cutset = left;
+
+        if !options.check_key(raw_key, key_lineno, 1)? {
+            continue;
+        }
+
+        options.set_var(env, key.as_ref(), value.as_ref());
     }
 
     Ok(())
 }
 
+/// Substitute variables in `text` the same way compose-go does it in
+/// `compose.yaml` files, i.e. with the same engine that is used for the values
+/// of the ComposeGo dialect.
+///
+/// `$$` is an escaped `$`, `${VAR:?message}` and `${VAR?message}` are errors
+/// if `VAR` is empty/unset and defaults can contain further substitutions.
+/// Malformed substitutions are errors (`Invalid template`).
+///
+/// The text isn't parsed as YAML, but like compose-go only values are
+/// substituted. Keys, comments, and the quotes around a value are copied as is.
+#[inline]
+pub fn interpolate(text: &str, env: &dyn GetEnv) -> Result<String> {
+    let (text, _) = interpolate_with_unresolved(text, env)?;
+    Ok(text)
+}
+
+/// Same as [`interpolate()`], but also returns the names of all referenced
+/// variables that are not set and didn't have a default value, together with
+/// the line number they are referenced at. compose-go replaces these with an
+/// empty string and prints a warning.
+pub fn interpolate_with_unresolved(text: &str, env: &dyn GetEnv) -> Result<(String, Vec<(String, usize)>)> {
+    expand_lines(text, env, true)
+}
+
+/// Same as [`interpolate()`], but substitutes whole lines, including keys and
+/// comments.
+#[inline]
+pub(crate) fn expand_text(text: &str, env: &dyn GetEnv) -> Result<String> {
+    let (text, _) = expand_lines(text, env, false)?;
+    Ok(text)
+}
+
+fn expand_lines(text: &str, env: &dyn GetEnv, yaml: bool) -> Result<(String, Vec<(String, usize)>)> {
+    let mut parser = Parser {
+        lineno: 1,
+        path: Cow::Borrowed(""),
+        debug: false,
+        strict: true,
+        strict_substitution: true,
+        compat_errors: true,
+        unresolved: Some(RefCell::new(Vec::new())),
+        undefined: None,
+        undefined_refs: Vec::new(),
+    };
+
+    let mut buf = String::with_capacity(text.len());
+    // indentation of the line that started a block scalar (`key: |`)
+    let mut block_indent = None;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        parser.lineno = index + 1;
+
+        let content = line.trim_end_matches(['\r', '\n']);
+        let (prefix, value, suffix) = if !yaml {
+            ("", content, "")
+        } else {
+            let indent = content.len() - content.trim_start_matches([' ', '\t']).len();
+            match block_indent {
+                Some(block_indent) if indent > block_indent || content.trim().is_empty() => {
+                    let (indent, value) = content.split_at(indent);
+                    (indent, value, "")
+                }
+                _ => {
+                    let parts = split_yaml_line(content);
+                    block_indent = is_block_scalar_header(parts.1).then_some(indent);
+                    parts
+                }
+            }
+        };
+
+        buf.push_str(prefix);
+        buf.push_str(&parser.expand_variables(value, 0, env)?);
+        buf.push_str(suffix);
+        buf.push_str(&line[content.len()..]);
+    }
+
+    let unresolved = parser.unresolved.map(RefCell::into_inner).unwrap_or_default();
+
+    Ok((buf, unresolved))
+}
+
+/// Split a line of a YAML file into everything before the value (indentation,
+/// `- `, and `key: ` including an opening quote), the value, and everything
+/// after it (a closing quote and a comment).
+fn split_yaml_line(line: &str) -> (&str, &str, &str) {
+    let mut start = line.len() - line.trim_start_matches([' ', '\t']).len();
+
+    while let Some(rest) = line[start..].strip_prefix('-') {
+        if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+            break;
+        }
+        start = line.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    if let Some(key_len) = yaml_key_len(&line[start..]) {
+        let rest = &line[start + key_len..];
+        start = line.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    let rest = &line[start..];
+    if rest.starts_with(['"', '\'']) {
+        return match yaml_quoted_len(rest) {
+            Some(len) => (&line[..start + 1], &rest[1..len - 1], &rest[len - 1..]),
+            // a multi-line quoted value
+            None => (&line[..start + 1], &rest[1..], ""),
+        };
+    }
+
+    let end = yaml_comment_start(rest).unwrap_or(rest.len());
+    let value = rest[..end].trim_end_matches([' ', '\t']);
+    (&line[..start], value, &rest[value.len()..])
+}
+
+/// Length of a mapping key including the `:` at the start of `src`.
+fn yaml_key_len(src: &str) -> Option<usize> {
+    let key_len = if src.starts_with(['"', '\'']) {
+        yaml_quoted_len(src)?
+    } else if src.starts_with(['#', '[', '{']) {
+        return None;
+    } else {
+        let end = yaml_comment_start(src).unwrap_or(src.len());
+        src[..end].match_indices(':').
+            map(|(index, _)| index).
+            find(|&index| src[index + 1..].is_empty() || src[index + 1..].starts_with([' ', '\t']))?
+    };
+
+    let rest = &src[key_len..];
+    if !rest.starts_with(':') || !(rest.len() == 1 || rest[1..].starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    Some(key_len + 1)
+}
+
+/// Length of the quoted string at the start of `src` including the quotes, or
+/// `None` if it doesn't end on this line.
+fn yaml_quoted_len(src: &str) -> Option<usize> {
+    let quote = src.as_bytes()[0];
+    let mut index = 1;
+    while index < src.len() {
+        let byte = src.as_bytes()[index];
+        if quote == b'"' && byte == b'\\' {
+            index += 2;
+            continue;
+        }
+        if byte == quote {
+            // '' is an escaped ' in single quoted strings
+            if quote == b'\'' && src.as_bytes().get(index + 1) == Some(&b'\'') {
+                index += 2;
+                continue;
+            }
+            return Some(index + 1);
+        }
+        index += 1;
+    }
+    None
+}
+
+/// A `#` starts a comment at the start of a plain value or after white space.
+fn yaml_comment_start(src: &str) -> Option<usize> {
+    src.match_indices('#').
+        map(|(index, _)| index).
+        find(|&index| index == 0 || src[..index].ends_with([' ', '\t']))
+}
+
+/// `|` and `>` with optional chomping and indentation indicators.
+fn is_block_scalar_header(value: &str) -> bool {
+    value.starts_with(['|', '>']) && value[1..].chars().all(|ch| ch == '+' || ch == '-' || ch.is_ascii_digit())
+}
+
 struct Parser<'a> {
     lineno: usize,
     path: Cow<'a, str>,
     debug: bool,
     strict: bool,
+    strict_substitution: bool,
+    /// Report substitution errors as `SubstitutionError` instead of `SyntaxError`.
+    compat_errors: bool,
+    unresolved: Option<RefCell<Vec<(String, usize)>>>,
+    /// Undefined variables and the byte offset of their name in the expanded
+    /// text, only recorded if they are reported.
+    undefined: Option<RefCell<Vec<(String, usize)>>>,
+    /// Undefined variables of the current value and the index of the reference
+    /// among all references to the same variable.
+    undefined_refs: Vec<(String, usize)>,
 }
 
 impl<'a> Parser<'a> {
+    // The error messages are the ones compose-go uses, so they can be compared.
+    #[inline]
+    fn syntax_error(&self, message: String) -> Error {
+        Error::new(ErrorKind::SyntaxError, message, SourceLocation::new(self.lineno, 1))
+    }
+
+    #[inline]
+    fn substitution_error(&self, message: String) -> Error {
+        let kind = if self.compat_errors { ErrorKind::SubstitutionError } else { ErrorKind::SyntaxError };
+        Error::new(kind, message, SourceLocation::new(self.lineno, 1))
+    }
+
+    #[inline]
+    fn invalid_template(&self, template: &str) -> Error {
+        self.substitution_error(format!("Invalid template: {template:?}"))
+    }
+
+    #[inline]
+    fn report_unresolved(&self, name: &str, offset: usize) {
+        if let Some(unresolved) = &self.unresolved {
+            unresolved.borrow_mut().push((name.to_owned(), self.lineno));
+        }
+        if let Some(undefined) = &self.undefined {
+            undefined.borrow_mut().push((name.to_owned(), offset));
+        }
+    }
+
+    #[inline]
+    fn clear_undefined(&mut self) {
+        if let Some(undefined) = &mut self.undefined {
+            undefined.get_mut().clear();
+        }
+        self.undefined_refs.clear();
+    }
+
+    /// Move the undefined variables recorded while expanding `text` to
+    /// `undefined_refs`.
+    fn take_undefined(&mut self, text: &str) {
+        let Some(undefined) = &mut self.undefined else {
+            return;
+        };
+
+        for (name, offset) in undefined.get_mut().drain(..) {
+            let nth = nth_reference(text, &name, offset);
+            self.undefined_refs.push((name, nth));
+        }
+    }
+
+    fn missing_required(&self, name: &str, message: &str, offset: usize, env: &dyn GetEnv) -> Error {
+        let message = match self.expand_variables(message, offset, env) {
+            Ok(message) => message,
+            Err(err) => return err,
+        };
+
+        let message = if message.is_empty() {
+            format!("required variable {name} is missing a value")
+        } else {
+            format!("required variable {name} is missing a value: {message}")
+        };
+
+        self.substitution_error(message)
+    }
+
     fn get_statement_start<'b>(&mut self, mut src: &'b str) -> Option<&'b str> {
         loop {
             let pos = self.index_of_non_space_char(src)?;
@@ -170,14 +438,15 @@ impl<'a> Parser<'a> {
                         continue;
                     }
 
+                    let newline = src.find('\n').unwrap_or(src.len());
                     if self.debug {
-                        let newline = src.find('\n').unwrap_or(src.len());
                         eprintln!("{DEBUG_PREFIX}{}:{}: unexpected character {:?} in variable name {:?}",
                             &self.path, self.lineno, rune, &src[..newline]
                         );
                     }
-    
-                    return Err(Error::syntax_error(self.lineno, 1));
+
+                    return Err(self.syntax_error(format!("line {}: unexpected character {:?} in variable name {:?}",
+                        self.lineno, rune.to_string(), &src[..newline])));
                 }
             }
         }
@@ -189,7 +458,7 @@ impl<'a> Parser<'a> {
                 );
             }
 
-            return Err(Error::syntax_error(self.lineno, 1));
+            return Err(self.syntax_error("zero length string".into()));
         }
 
         key = key.trim_end();
@@ -206,24 +475,27 @@ impl<'a> Parser<'a> {
         let Some(quote) = has_quote_prefix(src) else {
             let index = src.find('\n').unwrap_or(src.len());
             let mut value = &src[..index];
+
+            if let Some(index) = value.find(" #") {
+                value = &value[..index];
+            }
+            value = value.trim_end();
+            let res = self.expand_variables(value, 0, env)?;
+            self.take_undefined(value);
+
             let rest = if index < src.len() {
                 self.lineno += 1;
                 &src[index + 1..]
             } else {
                 &src[index..]
             };
-
-            if let Some(index) = value.find(" #") {
-                value = &value[..index];
-            }
-            value = value.trim_end();
-            let res = self.expand_variables(value, env)?;
             return Ok((res, rest));
         };
 
         let mut prev_char_is_esc = false;
 
         let quote = quote.get() as char;
+        let start_lineno = self.lineno;
         let mut value = String::new();
         let quoted_start = src;
         let src = &src[1..];
@@ -251,7 +523,9 @@ impl<'a> Parser<'a> {
             }
 
             if quote == '"' {
-                let res = self.expand_variables(&expand_escapes(&value), env)?;
+                let value = expand_escapes(&value);
+                let res = self.expand_variables(&value, 0, env)?;
+                self.take_undefined(&value);
                 return Ok((res, &src[index + ch.len_utf8()..]));
             }
 
@@ -264,11 +538,18 @@ impl<'a> Parser<'a> {
             );
         }
 
-        Err(Error::syntax_error(self.lineno, 1))
+        let newline = quoted_start.find('\n').unwrap_or(quoted_start.len());
+        Err(Error::new(
+            ErrorKind::SyntaxError,
+            format!("line {start_lineno}: unterminated quoted value {}", &quoted_start[..newline]),
+            SourceLocation::new(start_lineno, 1)))
     }
 
+    /// `offset` is the byte offset of `src` in the expanded text, it is added
+    /// to the location of undefined variables.
     // see: https://github.com/compose-spec/compose-go/blob/e1496cd905b20b799fa3acecefed8056338961a2/template/template.go
-    fn expand_variables(&self, mut src: &str, env: &dyn GetEnv) -> Result<String> {
+    fn expand_variables(&self, mut src: &str, offset: usize, env: &dyn GetEnv) -> Result<String> {
+        let template = src;
         let mut buf = String::new();
 
         while !src.is_empty() {
@@ -305,15 +586,18 @@ impl<'a> Parser<'a> {
                                 &self.path, self.lineno, &subst_start[..2]
                             );
                         }
-                        if self.strict {
-                            return Err(Error::syntax_error(self.lineno, 1));
+                        if self.strict || self.strict_substitution {
+                            return Err(self.invalid_template(template));
                         }
                         buf.push_str("${");
                         continue;
                     }
 
                     let name = &src[..index].split('\0').next().unwrap();
+                    let name_offset = offset + template.len() - src.len();
                     src = &src[index..];
+                    // offset of the operator (`:-`, `?` etc.)
+                    let op_offset = offset + template.len() - src.len();
                     let value = env.get(name.as_ref());
                     let var_end_index = if src.starts_with(|ch| ch == ':' || ch == '?' ||ch == '+' || ch == '-') {
                         let var_end_index = find_braced_subst_end(src);
@@ -323,8 +607,8 @@ impl<'a> Parser<'a> {
                                     &self.path, self.lineno
                                 );
                             }
-                            if self.strict {
-                                return Err(Error::syntax_error(self.lineno, 1));
+                            if self.strict || self.strict_substitution {
+                                return Err(self.invalid_template(template));
                             }
                             buf.push_str("${");
                             buf.push_str(name);
@@ -339,8 +623,8 @@ impl<'a> Parser<'a> {
                                 &self.path, self.lineno
                             );
                         }
-                        if self.strict {
-                            return Err(Error::syntax_error(self.lineno, 1));
+                        if self.strict || self.strict_substitution {
+                            return Err(self.invalid_template(template));
                         }
                         buf.push_str("${");
                         buf.push_str(name);
@@ -354,7 +638,7 @@ impl<'a> Parser<'a> {
                         if let Some(value) = value {
                             if value.is_empty() {
                                 if self.debug {
-                                    let message = self.expand_variables(message, env)?;
+                                    let message = self.expand_variables(message, op_offset + 2, env)?;
                                     if message.is_empty() {
                                         eprintln!("{DEBUG_PREFIX}{}:{}: variable ${} may not be empty",
                                             &self.path, self.lineno, name
@@ -365,12 +649,14 @@ impl<'a> Parser<'a> {
                                         );
                                     }
                                 }
-                                return Err(Error::syntax_error(self.lineno, 1));
+                                if !is_scratch_pass() {
+                                    return Err(self.missing_required(name, message, op_offset + 2, env));
+                                }
                             }
                             buf.push_str(value.to_string_lossy().as_ref());
                         } else {
                             if self.debug {
-                                let message = self.expand_variables(message, env)?;
+                                let message = self.expand_variables(message, op_offset + 2, env)?;
                                 if message.is_empty() {
                                     eprintln!("{DEBUG_PREFIX}{}:{}: variable ${} may not be unset",
                                         &self.path, self.lineno, name
@@ -381,7 +667,9 @@ impl<'a> Parser<'a> {
                                     );
                                 }
                             }
-                            return Err(Error::syntax_error(self.lineno, 1));
+                            if !is_scratch_pass() {
+                                return Err(self.missing_required(name, message, op_offset + 2, env));
+                            }
                         }
                     } else if src.starts_with('?') {
                         // required error when unset
@@ -391,7 +679,7 @@ impl<'a> Parser<'a> {
                             buf.push_str(value.to_string_lossy().as_ref());
                         } else {
                             if self.debug {
-                                let message = self.expand_variables(message, env)?;
+                                let message = self.expand_variables(message, op_offset + 1, env)?;
                                 if message.is_empty() {
                                     eprintln!("{DEBUG_PREFIX}{}:{}: variable ${} may not be unset",
                                         &self.path, self.lineno, name
@@ -402,7 +690,9 @@ impl<'a> Parser<'a> {
                                     );
                                 }
                             }
-                            return Err(Error::syntax_error(self.lineno, 1));
+                            if !is_scratch_pass() {
+                                return Err(self.missing_required(name, message, op_offset + 1, env));
+                            }
                         }
                     } else if src.starts_with(":-") {
                         // default when empty or unset
@@ -410,13 +700,13 @@ impl<'a> Parser<'a> {
                         src = &src[var_end_index..];
                         if let Some(value) = value {
                             if value.is_empty() {
-                                let default = self.expand_variables(default, env)?;
+                                let default = self.expand_variables(default, op_offset + 2, env)?;
                                 buf.push_str(&default);
                             } else {
                                 buf.push_str(value.to_string_lossy().as_ref());
                             }
                         } else {
-                            let default = self.expand_variables(default, env)?;
+                            let default = self.expand_variables(default, op_offset + 2, env)?;
                             buf.push_str(&default);
                         }
                     } else if src.starts_with('-') {
@@ -426,7 +716,7 @@ impl<'a> Parser<'a> {
                         if let Some(value) = value {
                             buf.push_str(value.to_string_lossy().as_ref());
                         } else {
-                            let default = self.expand_variables(default, env)?;
+                            let default = self.expand_variables(default, op_offset + 1, env)?;
                             buf.push_str(&default);
                         }
                     } else if src.starts_with(":+") {
@@ -435,7 +725,7 @@ impl<'a> Parser<'a> {
                         src = &src[var_end_index..];
                         if let Some(value) = value {
                             if !value.is_empty() {
-                                let default = self.expand_variables(default, env)?;
+                                let default = self.expand_variables(default, op_offset + 2, env)?;
                                 buf.push_str(&default);
                             }
                         }
@@ -444,11 +734,13 @@ impl<'a> Parser<'a> {
                         let default = &src[1..var_end_index];
                         src = &src[var_end_index..];
                         if value.is_some() {
-                            let default = self.expand_variables(default, env)?;
+                            let default = self.expand_variables(default, op_offset + 1, env)?;
                             buf.push_str(&default);
                         }
                     } else if let Some(value) = value {
                         buf.push_str(value.to_string_lossy().as_ref());
+                    } else {
+                        self.report_unresolved(name, name_offset);
                     }
 
                     if !src.starts_with('}') {
@@ -457,8 +749,8 @@ impl<'a> Parser<'a> {
                                 &self.path, self.lineno
                             );
                         }
-                        if self.strict {
-                            return Err(Error::syntax_error(self.lineno, 1));
+                        if self.strict || self.strict_substitution {
+                            return Err(self.invalid_template(template));
                         }
                     } else {
                         src = &src[1..];
@@ -481,16 +773,19 @@ impl<'a> Parser<'a> {
                                     &self.path, self.lineno, &subst_start[..1 + ch.len_utf8()]
                                 );
                             }
-                            if self.strict {
-                                return Err(Error::syntax_error(self.lineno, 1));
+                            if self.strict || self.strict_substitution {
+                                return Err(self.invalid_template(template));
                             }
                             buf.push('$');
                         } else {
                             let name = &src[..index].split('\0').next().unwrap();
+                            let name_offset = offset + template.len() - src.len();
                             src = &src[index..];
 
                             if let Some(value) = env.get(name.as_ref()) {
                                 buf.push_str(value.to_string_lossy().as_ref());
+                            } else {
+                                self.report_unresolved(name, name_offset);
                             }
                         }
                     }
@@ -0,0 +1,401 @@
+use std::{borrow::Cow, io::BufRead, path::Path};
+
+use crate::{env::GetEnv, error::SourceLocation, Env, Error, Options, Result, DEBUG_PREFIX};
+
+// trying to be compatible to: https://github.com/tonerdo/dotnet-env/blob/v3.1.1/src/DotNetEnv/Parsers.cs
+pub fn config_dotnet_env(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut src = String::new();
+    options.encoding.read_to_string(reader, &mut src)?;
+
+    let src = src.replace("\r\n", "\n");
+    let mut parser = Parser {
+        src: &src,
+        index: 0,
+        lineno: 1,
+        line_start: 0,
+        path: options.path.to_string_lossy(),
+        debug: options.debug,
+        parent,
+    };
+
+    loop {
+        parser.skip_ws();
+        if parser.index >= src.len() {
+            break;
+        }
+
+        match parser.parse_assignment(env.as_get_env()) {
+            Ok(None) => {}
+            Ok(Some((key, value, location))) => {
+                if options.check_key(key, location.lineno(), location.column())? {
+                    options.set_var_cut_null(env, key, &value);
+                }
+            }
+            Err(error) => {
+                if options.strict {
+                    return Err(error);
+                }
+                parser.skip_line();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    index: usize,
+    lineno: usize,
+    line_start: usize,
+    path: Cow<'a, str>,
+    debug: bool,
+    /// Variables not defined by the file itself are looked up here.
+    parent: &'a dyn GetEnv,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.src[self.index..].chars().next()
+    }
+
+    #[inline]
+    fn column(&self) -> usize {
+        self.src[self.line_start..self.index].chars().count() + 1
+    }
+
+    #[inline]
+    fn advance(&mut self, ch: char) {
+        self.index += ch.len_utf8();
+        if ch == '\n' {
+            self.lineno += 1;
+            self.line_start = self.index;
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            self.advance(ch);
+        }
+    }
+
+    fn skip_inline_ws(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !is_inline_ws(ch) {
+                break;
+            }
+            self.advance(ch);
+        }
+    }
+
+    fn skip_line(&mut self) {
+        if let Some(index) = self.src[self.index..].find('\n') {
+            self.index += index + 1;
+            self.lineno += 1;
+            self.line_start = self.index;
+        } else {
+            self.index = self.src.len();
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let column = self.column();
+        if self.debug {
+            eprintln!("{DEBUG_PREFIX}{}:{}:{}: {message}", self.path, self.lineno, column);
+        }
+        Error::syntax_error(self.lineno, column)
+    }
+
+    fn parse_assignment(&mut self, env: &dyn GetEnv) -> Result<Option<(&'a str, String, SourceLocation)>> {
+        if self.peek() == Some('#') {
+            self.skip_line();
+            return Ok(None);
+        }
+
+        let rest = &self.src[self.index..];
+        if let Some(tail) = rest.strip_prefix("export") {
+            if tail.starts_with(is_inline_ws) {
+                self.index += "export".len();
+                self.skip_inline_ws();
+            }
+        }
+
+        let location = SourceLocation::new(self.lineno, self.column());
+        let Some(key) = self.parse_identifier() else {
+            return Err(self.error("expected variable name"));
+        };
+
+        self.skip_inline_ws();
+        if self.peek() != Some('=') {
+            return Err(self.error("expected \"=\""));
+        }
+        self.advance('=');
+        self.skip_inline_ws();
+
+        let value = match self.peek() {
+            Some('\'') => self.parse_single_quoted()?,
+            Some('"')  => self.parse_double_quoted(env)?,
+            _          => self.parse_unquoted(env)?,
+        };
+
+        self.skip_inline_ws();
+        match self.peek() {
+            None => {}
+            Some('\n') => self.advance('\n'),
+            Some('#') => self.skip_line(),
+            Some(_) => {
+                return Err(self.error("unexpected characters after value"));
+            }
+        }
+
+        Ok(Some((key, value, location)))
+    }
+
+    fn parse_identifier(&mut self) -> Option<&'a str> {
+        let src = &self.src[self.index..];
+        let mut iter = src.char_indices();
+        let (_, head) = iter.next()?;
+        if !head.is_alphabetic() && head != '_' {
+            return None;
+        }
+
+        let end = iter.find(|(_, ch)| !is_identifier(*ch)).map(|(index, _)| index).unwrap_or(src.len());
+        self.index += end;
+
+        Some(&src[..end])
+    }
+
+    fn parse_single_quoted(&mut self) -> Result<String> {
+        let start_lineno = self.lineno;
+        let start_column = self.column();
+        self.advance('\'');
+
+        let src = &self.src[self.index..];
+        let Some(end) = src.find('\'') else {
+            if self.debug {
+                eprintln!("{DEBUG_PREFIX}{}:{}:{}: unterminated single quoted value", self.path, start_lineno, start_column);
+            }
+            return Err(Error::syntax_error(start_lineno, start_column));
+        };
+
+        let value = &src[..end];
+        for ch in value.chars() {
+            self.advance(ch);
+        }
+        self.advance('\'');
+
+        Ok(value.to_owned())
+    }
+
+    fn parse_double_quoted(&mut self, env: &dyn GetEnv) -> Result<String> {
+        let start_lineno = self.lineno;
+        let start_column = self.column();
+        self.advance('"');
+
+        let mut value = String::new();
+        let mut bytes = Vec::new();
+
+        loop {
+            let Some(ch) = self.peek() else {
+                if self.debug {
+                    eprintln!("{DEBUG_PREFIX}{}:{}:{}: unterminated double quoted value", self.path, start_lineno, start_column);
+                }
+                return Err(Error::syntax_error(start_lineno, start_column));
+            };
+
+            if ch != '\\' {
+                flush_bytes(&mut bytes, &mut value);
+            }
+
+            match ch {
+                '"' => {
+                    self.advance(ch);
+                    break;
+                }
+                '\\' => {
+                    self.advance(ch);
+                    self.parse_escape(&mut value, &mut bytes)?;
+                }
+                '$' => {
+                    self.parse_interpolation(&mut value, env)?;
+                }
+                _ => {
+                    self.advance(ch);
+                    value.push(ch);
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn parse_escape(&mut self, value: &mut String, bytes: &mut Vec<u8>) -> Result<()> {
+        let Some(ch) = self.peek() else {
+            value.push('\\');
+            return Ok(());
+        };
+
+        if ch >= '0' && ch <= '7' {
+            let src = &self.src[self.index..];
+            let end = src.find(|ch: char| !(ch >= '0' && ch <= '7')).unwrap_or(src.len()).min(3);
+            let byte = u32::from_str_radix(&src[..end], 8).unwrap_or(0);
+            if byte > 0xFF {
+                return Err(self.error("illegal octal escape sequence"));
+            }
+            self.index += end;
+            bytes.push(byte as u8);
+            return Ok(());
+        }
+
+        if ch == 'x' {
+            let src = &self.src[self.index + 1..];
+            let Some(byte) = src.get(..2).and_then(|hex| u8::from_str_radix(hex, 16).ok()) else {
+                return Err(self.error("illegal hex escape sequence"));
+            };
+            self.index += 3;
+            bytes.push(byte);
+            return Ok(());
+        }
+
+        flush_bytes(bytes, value);
+
+        match ch {
+            'u' | 'U' => {
+                let len = if ch == 'u' { 4 } else { 8 };
+                let src = &self.src[self.index + 1..];
+                let Some(mut codepoint) = src.get(..len).and_then(|hex| u32::from_str_radix(hex, 16).ok()) else {
+                    return Err(self.error("illegal unicode escape sequence"));
+                };
+                self.index += len + 1;
+
+                if ch == 'u' && codepoint >= 0xD800 && codepoint <= 0xDBFF {
+                    // surrogate pair
+                    let src = &self.src[self.index..];
+                    let low = src.strip_prefix("\\u").
+                        and_then(|src| src.get(..4)).
+                        and_then(|hex| u32::from_str_radix(hex, 16).ok()).
+                        filter(|low| *low >= 0xDC00 && *low <= 0xDFFF);
+
+                    let Some(low) = low else {
+                        return Err(self.error("illegal unicode escape sequence"));
+                    };
+                    self.index += 6;
+                    codepoint = 0x10000 + ((codepoint - 0xD800) << 10) + (low - 0xDC00);
+                }
+
+                let Some(decoded) = char::from_u32(codepoint) else {
+                    return Err(self.error("illegal unicode escape sequence"));
+                };
+                value.push(decoded);
+            }
+            _ => {
+                self.advance(ch);
+                match ch {
+                    'a'  => value.push('\x07'),
+                    'b'  => value.push('\x08'),
+                    'f'  => value.push('\x0C'),
+                    'n'  => value.push('\n'),
+                    'r'  => value.push('\r'),
+                    't'  => value.push('\t'),
+                    'v'  => value.push('\x0B'),
+                    '\\' => value.push('\\'),
+                    '\'' => value.push('\''),
+                    '"'  => value.push('"'),
+                    '$'  => value.push('$'),
+                    _ => {
+                        value.push('\\');
+                        value.push(ch);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_unquoted(&mut self, env: &dyn GetEnv) -> Result<String> {
+        let mut value = String::new();
+
+        while let Some(ch) = self.peek() {
+            match ch {
+                '\n' => break,
+                '$' => {
+                    self.parse_interpolation(&mut value, env)?;
+                }
+                '"' | '\'' => {
+                    return Err(self.error("unexpected quote in unquoted value"));
+                }
+                _ if is_inline_ws(ch) => {
+                    let src = &self.src[self.index..];
+                    let end = src.find(|ch: char| !is_inline_ws(ch)).unwrap_or(src.len());
+                    let tail = &src[end..];
+                    if tail.is_empty() || tail.starts_with('\n') || tail.starts_with('#') {
+                        // trailing whitespace or a comment
+                        break;
+                    }
+                    value.push_str(&src[..end]);
+                    self.index += end;
+                }
+                _ if ch.is_control() => {
+                    return Err(self.error("unexpected control character in unquoted value"));
+                }
+                _ => {
+                    self.advance(ch);
+                    value.push(ch);
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn parse_interpolation(&mut self, value: &mut String, env: &dyn GetEnv) -> Result<()> {
+        self.advance('$');
+
+        let braced = self.peek() == Some('{');
+        if braced {
+            self.advance('{');
+        }
+
+        let Some(name) = self.parse_identifier() else {
+            return Err(self.error("expected variable name after \"$\""));
+        };
+
+        if braced {
+            if self.peek() != Some('}') {
+                return Err(self.error("expected \"}\""));
+            }
+            self.advance('}');
+        }
+
+        if let Some(var_value) = env.get(name.as_ref()).or_else(|| self.parent.get(name.as_ref())) {
+            value.push_str(&var_value.to_string_lossy());
+        } else if self.debug {
+            eprintln!("{DEBUG_PREFIX}{}:{}:{}: variable ${name} is not set", self.path, self.lineno, self.column());
+        }
+
+        Ok(())
+    }
+}
+
+#[inline]
+fn is_inline_ws(ch: char) -> bool {
+    ch == ' ' || ch == '\t'
+}
+
+#[inline]
+fn is_identifier(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '.'
+}
+
+#[inline]
+fn flush_bytes(bytes: &mut Vec<u8>, value: &mut String) {
+    if !bytes.is_empty() {
+        value.push_str(&String::from_utf8_lossy(bytes));
+        bytes.clear();
+    }
+}
@@ -0,0 +1,378 @@
+use std::{borrow::Cow, io::BufRead, path::Path};
+
+use crate::{env::GetEnv, error::SourceLocation, Env, Error, Options, Result, DEBUG_PREFIX};
+
+// trying to be compatible to: https://github.com/fireproofsocks/dotenvy/blob/v0.8.0/lib/dotenvy/parser.ex
+//
+// Command substitution ($(command)) is deliberately not supported, just like with RubyDotenv.
+pub fn config_elixir_dotenvy(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    let mut src = String::new();
+    options.encoding.read_to_string(reader, &mut src)?;
+
+    let src = src.replace("\r\n", "\n");
+    let mut parser = Parser {
+        src: &src,
+        index: 0,
+        lineno: 1,
+        line_start: 0,
+        path: options.path.to_string_lossy(),
+        debug: options.debug,
+        parent,
+    };
+
+    loop {
+        parser.skip_ws();
+        if parser.index >= src.len() {
+            break;
+        }
+
+        match parser.parse_assignment(env.as_get_env()) {
+            Ok(None) => {}
+            Ok(Some((key, value, location))) => {
+                if options.check_key(key, location.lineno(), location.column())? {
+                    options.set_var_cut_null(env, key, &value);
+                }
+            }
+            Err(error) => {
+                if options.strict {
+                    return Err(error);
+                }
+                parser.skip_line();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    index: usize,
+    lineno: usize,
+    line_start: usize,
+    path: Cow<'a, str>,
+    debug: bool,
+    /// Variables not defined by the file itself are looked up here.
+    parent: &'a dyn GetEnv,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.src[self.index..].chars().next()
+    }
+
+    #[inline]
+    fn column(&self) -> usize {
+        self.src[self.line_start..self.index].chars().count() + 1
+    }
+
+    #[inline]
+    fn advance(&mut self, ch: char) {
+        self.index += ch.len_utf8();
+        if ch == '\n' {
+            self.lineno += 1;
+            self.line_start = self.index;
+        }
+    }
+
+    fn advance_str(&mut self, src: &str) {
+        for ch in src.chars() {
+            self.advance(ch);
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            self.advance(ch);
+        }
+    }
+
+    fn skip_inline_ws(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !is_inline_ws(ch) {
+                break;
+            }
+            self.advance(ch);
+        }
+    }
+
+    fn skip_line(&mut self) {
+        if let Some(index) = self.src[self.index..].find('\n') {
+            self.index += index + 1;
+            self.lineno += 1;
+            self.line_start = self.index;
+        } else {
+            self.index = self.src.len();
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let column = self.column();
+        if self.debug {
+            eprintln!("{DEBUG_PREFIX}{}:{}:{}: {message}", self.path, self.lineno, column);
+        }
+        Error::syntax_error(self.lineno, column)
+    }
+
+    fn parse_assignment(&mut self, env: &dyn GetEnv) -> Result<Option<(&'a str, String, SourceLocation)>> {
+        if self.peek() == Some('#') {
+            self.skip_line();
+            return Ok(None);
+        }
+
+        let rest = &self.src[self.index..];
+        if let Some(tail) = rest.strip_prefix("export") {
+            if tail.starts_with(is_inline_ws) {
+                self.index += "export".len();
+                self.skip_inline_ws();
+            }
+        }
+
+        let location = SourceLocation::new(self.lineno, self.column());
+        let Some(key) = self.parse_identifier() else {
+            return Err(self.error("expected variable name"));
+        };
+
+        self.skip_inline_ws();
+        if self.peek() != Some('=') {
+            return Err(self.error("expected \"=\""));
+        }
+        self.advance('=');
+        self.skip_inline_ws();
+
+        let rest = &self.src[self.index..];
+        let value = if rest.starts_with("\"\"\"") {
+            self.parse_heredoc("\"\"\"", env)?
+        } else if rest.starts_with("'''") {
+            self.parse_heredoc("'''", env)?
+        } else if rest.starts_with('"') {
+            self.parse_double_quoted(env)?
+        } else if rest.starts_with('\'') {
+            self.parse_single_quoted()?
+        } else {
+            self.parse_unquoted(env)?
+        };
+
+        self.skip_inline_ws();
+        match self.peek() {
+            None => {}
+            Some('\n') => self.advance('\n'),
+            Some('#') => self.skip_line(),
+            Some(_) => {
+                return Err(self.error("unexpected characters after value"));
+            }
+        }
+
+        Ok(Some((key, value, location)))
+    }
+
+    fn parse_identifier(&mut self) -> Option<&'a str> {
+        let src = &self.src[self.index..];
+        let mut iter = src.char_indices();
+        let (_, head) = iter.next()?;
+        if !head.is_ascii_alphabetic() && head != '_' {
+            return None;
+        }
+
+        let end = iter.find(|(_, ch)| !is_identifier(*ch)).map(|(index, _)| index).unwrap_or(src.len());
+        self.index += end;
+
+        Some(&src[..end])
+    }
+
+    fn parse_heredoc(&mut self, quotes: &str, env: &dyn GetEnv) -> Result<String> {
+        let start_lineno = self.lineno;
+        let start_column = self.column();
+        self.advance_str(quotes);
+
+        // like in Elixir, the newline right after the opening quotes is not part of the value
+        if self.peek() == Some('\n') {
+            self.advance('\n');
+        }
+
+        let src = &self.src[self.index..];
+        let Some(end) = src.find(quotes) else {
+            if self.debug {
+                eprintln!("{DEBUG_PREFIX}{}:{}:{}: unterminated heredoc", self.path, start_lineno, start_column);
+            }
+            return Err(Error::syntax_error(start_lineno, start_column));
+        };
+
+        let raw = &src[..end];
+        let value = if quotes.starts_with('"') {
+            let mut value = String::new();
+            let mut chars = raw.chars();
+            while let Some(ch) = chars.next() {
+                self.advance(ch);
+                match ch {
+                    '\\' => {
+                        let Some(ch) = chars.next() else {
+                            value.push('\\');
+                            break;
+                        };
+                        self.advance(ch);
+                        push_escape(&mut value, ch);
+                    }
+                    '$' if chars.as_str().starts_with('{') => {
+                        let name_len = self.interpolate(chars.as_str(), &mut value, env)?;
+                        self.advance_str(&chars.as_str()[..name_len]);
+                        chars = chars.as_str()[name_len..].chars();
+                    }
+                    _ => value.push(ch),
+                }
+            }
+            value
+        } else {
+            self.advance_str(raw);
+            raw.to_owned()
+        };
+
+        self.advance_str(quotes);
+
+        Ok(value)
+    }
+
+    fn parse_single_quoted(&mut self) -> Result<String> {
+        let start_lineno = self.lineno;
+        let start_column = self.column();
+        self.advance('\'');
+
+        let src = &self.src[self.index..];
+        let Some(end) = src.find('\'') else {
+            if self.debug {
+                eprintln!("{DEBUG_PREFIX}{}:{}:{}: unterminated single quoted value", self.path, start_lineno, start_column);
+            }
+            return Err(Error::syntax_error(start_lineno, start_column));
+        };
+
+        let value = &src[..end];
+        self.advance_str(value);
+        self.advance('\'');
+
+        Ok(value.to_owned())
+    }
+
+    fn parse_double_quoted(&mut self, env: &dyn GetEnv) -> Result<String> {
+        let start_lineno = self.lineno;
+        let start_column = self.column();
+        self.advance('"');
+
+        let mut value = String::new();
+
+        loop {
+            let Some(ch) = self.peek() else {
+                if self.debug {
+                    eprintln!("{DEBUG_PREFIX}{}:{}:{}: unterminated double quoted value", self.path, start_lineno, start_column);
+                }
+                return Err(Error::syntax_error(start_lineno, start_column));
+            };
+            self.advance(ch);
+
+            match ch {
+                '"' => break,
+                '\\' => {
+                    let Some(ch) = self.peek() else {
+                        continue;
+                    };
+                    self.advance(ch);
+                    push_escape(&mut value, ch);
+                }
+                '$' if self.peek() == Some('{') => {
+                    let len = self.interpolate(&self.src[self.index..], &mut value, env)?;
+                    self.index += len;
+                }
+                _ => value.push(ch),
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn parse_unquoted(&mut self, env: &dyn GetEnv) -> Result<String> {
+        let mut value = String::new();
+
+        while let Some(ch) = self.peek() {
+            match ch {
+                '\n' => break,
+                '$' if self.src[self.index + 1..].starts_with('{') => {
+                    self.advance(ch);
+                    let len = self.interpolate(&self.src[self.index..], &mut value, env)?;
+                    self.index += len;
+                }
+                _ if is_inline_ws(ch) => {
+                    let src = &self.src[self.index..];
+                    let end = src.find(|ch: char| !is_inline_ws(ch)).unwrap_or(src.len());
+                    let tail = &src[end..];
+                    if tail.is_empty() || tail.starts_with('\n') || tail.starts_with('#') {
+                        // trailing whitespace or a comment
+                        break;
+                    }
+                    value.push_str(&src[..end]);
+                    self.index += end;
+                }
+                _ => {
+                    self.advance(ch);
+                    value.push(ch);
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// `src` starts at the `{` of `${NAME}`. Returns the number of bytes consumed.
+    fn interpolate(&self, src: &str, value: &mut String, env: &dyn GetEnv) -> Result<usize> {
+        let Some(end) = src.find(['}', '\n']) else {
+            return Err(self.error("unterminated variable reference"));
+        };
+
+        if !src[end..].starts_with('}') {
+            return Err(self.error("unterminated variable reference"));
+        }
+
+        let name = &src[1..end];
+        if name.is_empty() || !name.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_') || !name.chars().all(is_identifier) {
+            return Err(self.error("illegal variable name"));
+        }
+
+        if let Some(var_value) = env.get(name.as_ref()).or_else(|| self.parent.get(name.as_ref())) {
+            value.push_str(&var_value.to_string_lossy());
+        } else if self.debug {
+            eprintln!("{DEBUG_PREFIX}{}:{}:{}: variable ${{{name}}} is not set", self.path, self.lineno, self.column());
+        }
+
+        Ok(end + 1)
+    }
+}
+
+fn push_escape(value: &mut String, ch: char) {
+    match ch {
+        'n'  => value.push('\n'),
+        'r'  => value.push('\r'),
+        't'  => value.push('\t'),
+        'f'  => value.push('\x0C'),
+        'b'  => value.push('\x08'),
+        '"'  => value.push('"'),
+        '\'' => value.push('\''),
+        '\\' => value.push('\\'),
+        '$'  => value.push('$'),
+        _ => {
+            value.push('\\');
+            value.push(ch);
+        }
+    }
+}
+
+#[inline]
+fn is_inline_ws(ch: char) -> bool {
+    ch == ' ' || ch == '\t'
+}
+
+#[inline]
+fn is_identifier(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '.'
+}
@@ -40,6 +40,7 @@ pub fn config_go_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &O
             }
         }
 
+        let key_lineno = parser.lineno;
         let (value, left) = parser.extract_var_value(left, env.as_get_env())?;
         let raw_value = &value;
         let value = value.split('\0').next().unwrap();
@@ -52,9 +53,13 @@ pub fn config_go_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &O
             }
         }
 
-        options.set_var(env, key.as_ref(), value.as_ref());
-
         cutset = left;
+
+        if !options.check_key(raw_key, key_lineno, 1)? {
+            continue;
+        }
+
+        options.set_var(env, key.as_ref(), value.as_ref());
     }
 
     Ok(())
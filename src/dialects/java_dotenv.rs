@@ -120,6 +120,9 @@ pub fn config_java_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options:
             }
             continue;
         }
+        if !options.check_key(key, lineno, key_start + 1)? {
+            continue;
+        }
         let value = normalize_value(value);
         options.set_var_cut_null(env, key, value);
     }
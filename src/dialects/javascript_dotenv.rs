@@ -1,9 +1,21 @@
 // trying to emulate: https://github.com/motdotla/dotenv/blob/8ab33066f90a20445d3c41e4fafba6c929c5e1a5/lib/main.js
 use std::{io::BufRead, path::Path};
 
-use crate::{Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{error::SourceLocation, Env, Error, Options, Result, DEBUG_PREFIX};
 
 pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
+    parse_javascript_dotenv(reader, env, options, Variant::Dotenv)
+}
+
+/// NodeJS 20 used the regular expression of the dotenv npm package,
+/// but post-processed the values slightly differently.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Variant {
+    Dotenv,
+    NodeJS20,
+}
+
+pub(crate) fn parse_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>, variant: Variant) -> Result<()> {
     let path_str = options.path.to_string_lossy();
     let mut buf = String::new();
     options.encoding.read_to_string(reader, &mut buf)?;
@@ -15,6 +27,8 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         line_start: 0,
         index: 0,
         lineno: 1,
+        // NodeJS has no such option
+        multiline: variant == Variant::NodeJS20 || options.dialect_options.javascript.multiline,
     };
 
     while parser.index < parser.buf.len() {
@@ -31,6 +45,7 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
 
         let mut key_start = parser.index;
         let mut key_end = find_vardef_end(&parser.buf, parser.index);
+        let mut key_location = SourceLocation::new(parser.lineno, key_start - parser.line_start + 1);
 
         parser.index = key_end;
         parser.skip_ws();
@@ -38,6 +53,7 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         if parser.buf[parser.index..].starts_with(is_vardef) && &parser.buf[key_start..key_end] == "export" {
             key_start = parser.index;
             key_end = find_vardef_end(&parser.buf, parser.index);
+            key_location = SourceLocation::new(parser.lineno, key_start - parser.line_start + 1);
             parser.index = key_end;
             parser.skip_ws();
         }
@@ -156,7 +172,16 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         let quote = value_slice.chars().next().unwrap_or('\0');
 
         let mut value;
-        if value_slice.len() > 1 && matches!(quote, '"' | '\'' | '`') && value_slice.ends_with(quote) {
+        if variant == Variant::NodeJS20 {
+            // NodeJS 20 only trims spaces and tabs and then removes any quotes
+            // from the start and end, even if they are not the same.
+            let trimmed = value_slice.trim_end_matches(['\t', ' ']);
+            if trimmed.len() > 1 && trimmed.starts_with(is_quote) && trimmed.ends_with(is_quote) {
+                value = trimmed[1..trimmed.len() - 1].to_owned();
+            } else {
+                value = trimmed.to_owned();
+            }
+        } else if value_slice.len() > 1 && matches!(quote, '"' | '\'' | '`') && value_slice.ends_with(quote) {
             value = parser.buf[value_start + 1..value_end - 1].to_owned();
         } else {
             value = value_slice.trim_end_matches(|ch| matches!(ch, '\t' | '\x0B' | '\x0C' | ' ')).to_owned();
@@ -193,12 +218,21 @@ pub fn config_javascript_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, opt
             }
         }
 
+        if !options.check_key(&parser.buf[key_start..key_end], key_location.lineno(), key_location.column())? {
+            continue;
+        }
+
         options.set_var_cut_null(env, parser.buf[key_start..key_end].as_ref(), value.as_ref());
     }
 
     Ok(())
 }
 
+#[inline]
+fn is_quote(ch: char) -> bool {
+    matches!(ch, '"' | '\'' | '`')
+}
+
 #[inline]
 fn unescape_double_quoted(value: &str) -> String {
     value.replace("\\n", "\n").replace("\\r", "\r")
@@ -209,6 +243,7 @@ struct Parser {
     line_start: usize,
     index: usize,
     lineno: usize,
+    multiline: bool,
 }
 
 impl Parser {
@@ -252,8 +287,13 @@ impl Parser {
             end_index += pos + quote_len;
         }
 
-        // Count newlines in the parsed string and set the line_start offset.
         let mut slice = &slice[..end_index];
+        if !self.multiline && slice.contains('\n') {
+            // dotenv before version 15 parsed line by line
+            return false;
+        }
+
+        // Count newlines in the parsed string and set the line_start offset.
         loop {
             let Some(index) = slice.find('\n') else {
                 break;
@@ -8,3 +8,40 @@ pub mod go_dotenv;
 pub mod binary;
 pub mod ruby_dotenv;
 pub mod java_dotenv;
+pub mod dotnet_env;
+pub mod elixir_dotenvy;
+
+use std::{ops::Range, path::Path};
+
+use crate::{error::SourceLocation, Options, Result};
+
+/// Byte offsets of the references to `name` (`$name` or `${name`) in `src`.
+/// Escaped references are included, they are the same in the source text and
+/// the unescaped value.
+fn references<'a>(src: &'a str, name: &'a str) -> impl Iterator<Item=usize> + 'a {
+    src.match_indices('$').map(|(index, _)| index).filter(move |&index| {
+        let tail = &src[index + 1..];
+        let tail = tail.strip_prefix('{').unwrap_or(tail);
+        tail.starts_with(name) && !tail[name.len()..].starts_with(|ch: char| ch.is_ascii_alphanumeric() || ch == '_')
+    })
+}
+
+/// Index of the reference to `name` at `offset` in `value` among all the
+/// references to `name` in `value`.
+#[inline]
+pub(crate) fn nth_reference(value: &str, name: &str, offset: usize) -> usize {
+    references(&value[..offset], name).count()
+}
+
+/// Report the `nth` reference to the undefined variable `name` according to
+/// `options.undefined_vars`. Substitution happens on the unescaped value, so
+/// the location is the same reference in `src[range]`, the source text of the
+/// value.
+pub(crate) fn report_undefined_var(src: &str, range: Range<usize>, name: &str, nth: usize, options: &Options<&Path>) -> Result<()> {
+    let index = references(&src[range.clone()], name).nth(nth).map(|index| range.start + index).unwrap_or(range.start);
+    let line_start = src[..index].rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+    let lineno = src[..index].matches('\n').count() + 1;
+    let location = SourceLocation::new(lineno, index - line_start + 1);
+
+    options.undefined_vars.report(&options.path.to_string_lossy(), name, location, options.strict, options.debug)
+}
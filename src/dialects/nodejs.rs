@@ -2,6 +2,17 @@ use std::{io::BufRead, path::Path};
 
 use crate::{Env, Options, Result};
 
+use super::javascript_dotenv::{parse_javascript_dotenv, Variant};
+
+// Trying to emulate: https://github.com/nodejs/node/blob/v20.6.0/src/node_dotenv.cc
+// NodeJS 20.6 to 20.11 and 21.0 to 21.6 used the same regular expression as the
+// dotenv npm package (JavaScriptDotenv), only the post-processing of the values
+// is a bit different.
+#[inline]
+pub fn config_nodejs20(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
+    parse_javascript_dotenv(reader, env, options, Variant::NodeJS20)
+}
+
 // Trying to emulate: https://github.com/nodejs/node/blob/v22.x/src/node_dotenv.cc
 pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
     let mut lines = String::new();
@@ -36,11 +47,16 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
             key = &key[7..];
         }
 
+        let (lineno, column) = location_of(&lines, key);
+        let valid = options.check_key(key, lineno, column)?;
+
         // SAFETY: Content is guaranteed to have at least one character
         if content.is_empty() {
             // In case the last line is a single key without value
             // Example: KEY= (without a newline at the EOF)
-            options.set_var(env, key.split('\0').next().unwrap().as_ref(), "".as_ref());
+            if valid {
+                options.set_var(env, key.split('\0').next().unwrap().as_ref(), "".as_ref());
+            }
             break;
         }
 
@@ -50,9 +66,11 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
             if let Some(closing_quote) = content[1..].find('"') {
                 let value = &content[1..closing_quote + 1];
                 let multi_line_value = value.replace("\\n", "\n");
-                options.set_var(env,
-                    key.split('\0').next().unwrap().as_ref(),
-                    multi_line_value.split('\0').next().unwrap().as_ref());
+                if valid {
+                    options.set_var(env,
+                        key.split('\0').next().unwrap().as_ref(),
+                        multi_line_value.split('\0').next().unwrap().as_ref());
+                }
                 content = &content[closing_quote + 1..];
                 let newline = content.find('\n').unwrap_or(content.len());
                 content = &content[newline..];
@@ -68,9 +86,11 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
             if let Some(closing_quote) = content[1..].find(front) {
                 // Example: KEY="value"
                 let value = &content[1..closing_quote + 1];
-                options.set_var(env,
-                    key.split('\0').next().unwrap().as_ref(),
-                    value.split('\0').next().unwrap().as_ref());
+                if valid {
+                    options.set_var(env,
+                        key.split('\0').next().unwrap().as_ref(),
+                        value.split('\0').next().unwrap().as_ref());
+                }
                 // Select the first newline after the closing quotation mark
                 // since there could be newline characters inside the value.
                 content = &content[closing_quote + 1..];
@@ -82,9 +102,11 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
                 // The value pair should be `"value`
                 if let Some(newline) = content.find('\n') {
                     let value = &content[..newline];
-                    options.set_var(env,
-                        key.split('\0').next().unwrap().as_ref(),
-                        value.split('\0').next().unwrap().as_ref());
+                    if valid {
+                        options.set_var(env,
+                            key.split('\0').next().unwrap().as_ref(),
+                            value.split('\0').next().unwrap().as_ref());
+                    }
                     content = &content[newline..];
                 }
             }
@@ -109,11 +131,21 @@ pub fn config_nodejs(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Opti
             }
 
             value = value.trim_matches(' ');
-            options.set_var(env,
-                key.split('\0').next().unwrap().as_ref(),
-                value.split('\0').next().unwrap().as_ref());
+            if valid {
+                options.set_var(env,
+                    key.split('\0').next().unwrap().as_ref(),
+                    value.split('\0').next().unwrap().as_ref());
+            }
         }
     }
 
     Ok(())
 }
+
+/// Line number and column of `slice`, which has to be a sub-slice of `src`.
+fn location_of(src: &str, slice: &str) -> (usize, usize) {
+    let offset = slice.as_ptr() as usize - src.as_ptr() as usize;
+    let head = &src[..offset];
+    let line_start = head.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    (head.matches('\n').count() + 1, head[line_start..].chars().count() + 1)
+}
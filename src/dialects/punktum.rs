@@ -1,6 +1,6 @@
-use std::{borrow::Cow, io::BufRead, path::Path};
+use std::{borrow::Cow, collections::VecDeque, ffi::OsStr, io::BufRead, path::Path, sync::Arc};
 
-use crate::{env::{EmptyEnv, GetEnv}, error::SourceLocation, Encoding, Env, Error, ErrorKind, Options, Result, DEBUG_PREFIX};
+use crate::{deferred::{cached_substitution, is_scratch_pass}, env::{EmptyEnv, GetEnv}, error::SourceLocation, glob::Glob, options::{AssignOp, UndefinedVars}, resolver::{get_resolver, ResolveContext, Resolver}, Encoding, Env, Error, ErrorKind, Options, Result, DEBUG_PREFIX};
 
 #[inline]
 fn is_word(ch: char) -> bool {
@@ -34,9 +34,51 @@ fn char_at(src: &str, index: usize) -> Option<char> {
     src.get(index..)?.chars().next()
 }
 
+#[inline]
+fn is_heredoc(src: &str, index: usize) -> bool {
+    src.get(index..).is_some_and(|tail| tail.starts_with("<<"))
+}
+
+/// The assignment operator at `index` and the index after it.
+fn assign_op(src: &str, index: usize) -> Option<(AssignOp, usize)> {
+    let tail = src.get(index..)?;
+    if tail.starts_with('=') {
+        Some((AssignOp::Assign, index + 1))
+    } else if tail.starts_with("+=") {
+        Some((AssignOp::Append, index + 2))
+    } else if tail.starts_with("^=") {
+        Some((AssignOp::Prepend, index + 2))
+    } else if tail.starts_with("?=") {
+        Some((AssignOp::Default, index + 2))
+    } else {
+        None
+    }
+}
+
+#[inline]
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
 pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
     let path_str = options.path.to_string_lossy();
 
+    let mut resolvers = Vec::with_capacity(options.resolvers.len());
+    for scheme in &options.resolvers {
+        let Some(resolver) = scheme.to_str().and_then(|scheme| Some((scheme.to_owned(), get_resolver(scheme)?))) else {
+            return Err(Error::with_cause(ErrorKind::OptionsParseError, format!("unknown resolver: {scheme:?}")));
+        };
+        resolvers.push(resolver);
+    }
+
+    // config_with_reader() already resolved Auto, but not if this is called
+    // directly
+    let encoding = if options.encoding == Encoding::Auto {
+        options.encoding.resolve(reader)?
+    } else {
+        options.encoding
+    };
+
     let mut key = String::new();
     let mut value = String::new();
     let mut parser = Parser {
@@ -44,15 +86,21 @@ pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn
         lineno: 0,
         debug: options.debug,
         strict: options.strict,
-        encoding: options.encoding,
+        encoding,
         linebuf: String::new(),
         reader,
+        assigned: Vec::new(),
+        resolvers,
+        file: options.path,
+        parent,
+        undefined_vars: options.undefined_vars,
+        pending: VecDeque::new(),
+        heredoc_line: false,
+        reads: 0,
     };
 
     loop {
-        parser.linebuf.clear();
-        parser.lineno += 1;
-        if let Err(err) = options.encoding.read_line(&mut parser.reader, &mut parser.linebuf) {
+        if let Err(err) = parser.read_line() {
             if options.debug {
                 eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", parser.path, parser.lineno);
             }
@@ -128,10 +176,14 @@ pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn
             continue;
         }
 
+        let mut key_column = prev_index + 1;
         index = skip_ws(&parser.linebuf, index);
 
         {
             let Some(next_ch) = char_at(&parser.linebuf, index) else {
+                if !options.check_key(&key, parser.lineno, key_column)? {
+                    continue;
+                }
                 if let Some(value) = parent.get(key.as_ref()) {
                     options.set_var(env, key.as_ref(), value.as_ref());
                 }
@@ -141,13 +193,52 @@ pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn
         }
 
         if ch == '#' {
+            if !options.check_key(&key, parser.lineno, key_column)? {
+                continue;
+            }
             if let Some(value) = parent.get(key.as_ref()) {
                 options.set_var(env, key.as_ref(), value.as_ref());
             }
             continue;
         }
 
-        if ch != '=' {
+        if key == "unset" && is_word(ch) {
+            // `unset KEY...`
+            let mut names = Vec::new();
+            let unexpected = loop {
+                let name_start = index;
+                index = find_word_end(&parser.linebuf, index);
+                names.push(name_start..index);
+
+                index = skip_ws(&parser.linebuf, index);
+                match char_at(&parser.linebuf, index) {
+                    None | Some('#') => break None,
+                    Some(ch) if is_word(ch) => {}
+                    Some(ch) => break Some(ch),
+                }
+            };
+
+            if let Some(ch) = unexpected {
+                let column = index + 1;
+                if options.debug {
+                    let line = parser.linebuf.trim_end_matches('\n');
+                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: unexpected {ch:?}, expected variable name: {line}", parser.path, parser.lineno);
+                }
+                if options.strict {
+                    return Err(Error::syntax_error(parser.lineno, column));
+                }
+                continue;
+            }
+
+            for name in names {
+                if options.check_key(&parser.linebuf[name.clone()], parser.lineno, name.start + 1)? {
+                    options.unset_var(env, parser.linebuf[name].as_ref());
+                }
+            }
+            continue;
+        }
+
+        if assign_op(&parser.linebuf, index).is_none() && !is_heredoc(&parser.linebuf, index) {
             if !options.strict && key.eq("export") && is_word(ch) {
                 // allow `export FOO=BAR`
                 key.clear();
@@ -156,10 +247,14 @@ pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn
                 index = find_word_end(&parser.linebuf, index);
 
                 key.push_str(&parser.linebuf[prev_index..index]);
+                key_column = prev_index + 1;
 
                 index = skip_ws(&parser.linebuf, index);
                 {
                     let Some(next_ch) = char_at(&parser.linebuf, index) else {
+                        if !options.check_key(&key, parser.lineno, key_column)? {
+                            continue;
+                        }
                         if let Some(value) = parent.get(key.as_ref()) {
                             options.set_var(env, key.as_ref(), value.as_ref());
                         }
@@ -169,13 +264,16 @@ pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn
                 }
 
                 if ch == '#' {
+                    if !options.check_key(&key, parser.lineno, key_column)? {
+                        continue;
+                    }
                     if let Some(value) = parent.get(key.as_ref()) {
                         options.set_var(env, key.as_ref(), value.as_ref());
                     }
                     continue;
                 }
 
-                if ch != '=' {
+                if assign_op(&parser.linebuf, index).is_none() && !is_heredoc(&parser.linebuf, index) {
                     let column = index + 1;
                     if options.debug {
                         let line = parser.linebuf.trim_end_matches('\n');
@@ -199,17 +297,94 @@ pub fn config_punktum(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn
             }
         }
 
-        index = skip_ws(&parser.linebuf, index + 1);
-
+        let key_lineno = parser.lineno;
         value.clear();
-        parser.parse_value(index, &mut value, env.as_get_env(), false)?;
+        let op = if let Some((op, next_index)) = assign_op(&parser.linebuf, index) {
+            index = skip_ws(&parser.linebuf, next_index);
+            parser.parse_value(index, &mut value, env.as_get_env(), false)?;
+            op
+        } else {
+            if !parser.parse_heredoc(index, &mut value, env.as_get_env())? {
+                continue;
+            }
+            AssignOp::Assign
+        };
+
+        // assignments by ${VAR:=DEFAULT}
+        for (assigned_key, assigned_value, location) in parser.assigned.drain(..) {
+            if options.check_key(&assigned_key, location.lineno(), location.column())? {
+                options.set_var(env, assigned_key.as_ref(), assigned_value.as_ref());
+            }
+        }
+
+        // checked after parsing the value, so a skipped definition doesn't
+        // leave the rest of a multi-line value behind
+        if !options.check_key(&key, key_lineno, key_column)? {
+            continue;
+        }
 
-        options.set_var(env, key.as_ref(), value.as_ref());
+        options.assign_var(env, parent, key.as_ref(), value.as_ref(), op);
     }
 
     Ok(())
 }
 
+/// Substitute variables in arbitrary text. References to variables for which
+/// `is_substituted()` returns `false` are kept as they are.
+pub(crate) fn render_punktum(text: &str, env: &dyn GetEnv, is_substituted: &dyn Fn(&str) -> bool) -> Result<String> {
+    let mut reader = text.as_bytes();
+    let mut parser = Parser {
+        path: Cow::Borrowed("-"),
+        lineno: 0,
+        debug: false,
+        strict: false,
+        encoding: Encoding::UTF8,
+        linebuf: String::new(),
+        reader: &mut reader,
+        assigned: Vec::new(),
+        resolvers: Vec::new(),
+        file: Path::new("-"),
+        parent: &EmptyEnv(),
+        undefined_vars: UndefinedVars::Ignore,
+        pending: VecDeque::new(),
+        heredoc_line: false,
+        reads: 0,
+    };
+
+    let mut buf = String::with_capacity(text.len());
+    loop {
+        parser.read_line()?;
+
+        if parser.linebuf.is_empty() {
+            break;
+        }
+
+        let mut index = 0;
+        while let Some(pos) = parser.linebuf[index..].find('$') {
+            let var_start_index = index + pos;
+            buf.push_str(&parser.linebuf[index..var_start_index]);
+
+            let mut key_index = var_start_index + 1;
+            if parser.linebuf[key_index..].starts_with('{') {
+                key_index += 1;
+            }
+            let end_index = find_word_end(&parser.linebuf, key_index);
+
+            if end_index > key_index && !is_substituted(&parser.linebuf[key_index..end_index]) {
+                buf.push('$');
+                index = var_start_index + 1;
+                continue;
+            }
+
+            // might read more lines for multiline default values
+            index = parser.parse_var(var_start_index + 1, &mut buf, env)?;
+        }
+        buf.push_str(&parser.linebuf[index..]);
+    }
+
+    Ok(buf)
+}
+
 struct Parser<'c> {
     path: Cow<'c, str>,
     lineno: usize,
@@ -218,11 +393,23 @@ struct Parser<'c> {
     encoding: Encoding,
     reader: &'c mut dyn BufRead,
     linebuf: String,
+    /// Variables assigned by `${VAR:=DEFAULT}` and where `VAR` is.
+    assigned: Vec<(String, String, SourceLocation)>,
+    resolvers: Vec<(String, Arc<dyn Resolver>)>,
+    file: &'c Path,
+    parent: &'c dyn GetEnv,
+    undefined_vars: UndefinedVars,
+    /// Lines of an unterminated heredoc, they are parsed again as normal lines.
+    pending: VecDeque<(usize, String)>,
+    /// Parsing a single line of a heredoc, there is no next line.
+    heredoc_line: bool,
+    /// Number of `read_line()` calls, i.e. how often `linebuf` was replaced.
+    reads: usize,
 }
 
 macro_rules! parse_var_error {
     ($self:expr, $index:expr, $buf:expr, $key:expr, $env:expr, $message:expr) => {
-        if $buf.is_skipped() {
+        if $buf.is_skipped() || is_scratch_pass() {
             $index = $self.parse_value($index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
         } else {
             let lineno = $self.lineno;
@@ -252,14 +439,19 @@ macro_rules! parse_var_error {
 }
 
 impl<'c> Parser<'c> {
-    fn parse_value(&mut self, mut index: usize, value: &mut dyn StringBuffer, env: &dyn GetEnv, nested: bool) -> Result<usize> {
+    #[inline]
+    fn parse_value(&mut self, index: usize, value: &mut dyn StringBuffer, env: &dyn GetEnv, nested: bool) -> Result<usize> {
+        self.parse_value_until(index, value, env, nested, '}')
+    }
+
+    /// Like `parse_value()`, but a nested value also ends at `end`. Used for
+    /// the parts of parameter expansions like `${VAR/PATTERN/REPLACEMENT}`.
+    fn parse_value_until(&mut self, mut index: usize, value: &mut dyn StringBuffer, env: &dyn GetEnv, nested: bool, end: char) -> Result<usize> {
         loop {
             if nested && index >= self.linebuf.len() {
                 index = 0;
 
-                self.linebuf.clear();
-                self.lineno += 1;
-                if let Err(err) = self.encoding.read_line(self.reader, &mut self.linebuf) {
+                if let Err(err) = self.read_line() {
                     if self.debug {
                         eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
                     }
@@ -490,9 +682,7 @@ impl<'c> Parser<'c> {
                                         index = 0;
                                         prev_index = index;
 
-                                        self.linebuf.clear();
-                                        self.lineno += 1;
-                                        if let Err(err) = self.encoding.read_line(&mut self.reader, &mut self.linebuf) {
+                                        if let Err(err) = self.read_line() {
                                             if self.debug {
                                                 eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
                                             }
@@ -555,9 +745,7 @@ impl<'c> Parser<'c> {
                             index = 0;
                             prev_index = index;
 
-                            self.linebuf.clear();
-                            self.lineno += 1;
-                            if let Err(err) = self.encoding.read_line(&mut self.reader, &mut self.linebuf) {
+                            if let Err(err) = self.read_line() {
                                 if self.debug {
                                     eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
                                 }
@@ -603,7 +791,7 @@ impl<'c> Parser<'c> {
                 }
             } else if ch == '#' && !nested {
                 break;
-            } else if ch == '}' && nested {
+            } else if (ch == '}' || ch == end) && nested {
                 break;
             } else if ch == '$' {
                 index = self.parse_var(index + 1, value, env)?;
@@ -627,10 +815,7 @@ impl<'c> Parser<'c> {
 
                         index = 0;
                         prev_index = index;
-                        self.linebuf.clear();
-                        self.lineno += 1;
-
-                        if let Err(err) = self.encoding.read_line(&mut self.reader, &mut self.linebuf) {
+                        if let Err(err) = self.read_line() {
                             if self.debug {
                                 eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
                             }
@@ -652,7 +837,7 @@ impl<'c> Parser<'c> {
                     };
 
                     index = next_index;
-                    if ch == '"' || ch == '\'' || ch == '\0' || ch == '$' || ch == '}' {
+                    if ch == '"' || ch == '\'' || ch == '\0' || ch == '$' || ch == '}' || ch == end {
                         break;
                     }
                     index += ch.len_utf8();
@@ -721,10 +906,17 @@ impl<'c> Parser<'c> {
 
     fn parse_var(&mut self, mut index: usize, buf: &mut dyn StringBuffer, env: &dyn GetEnv) -> Result<usize> {
         let var_start_index = index - 1;
+        let var_lineno = self.lineno;
+        let var_reads = self.reads;
         let brace = self.linebuf[index..].starts_with('{');
         if brace {
             index += 1;
         }
+        // ${#VAR}
+        let length = brace && self.linebuf[index..].starts_with('#') && char_at(&self.linebuf, index + 1).is_some_and(is_word);
+        if length {
+            index += 1;
+        }
         let end_index = find_word_end(&self.linebuf, index);
 
         if end_index == index {
@@ -754,12 +946,74 @@ impl<'c> Parser<'c> {
         }
 
         let key = &self.linebuf[index..end_index];
-        let value = env.get(key.as_ref());
+        let key_location = SourceLocation::new(self.lineno, index + 1);
         index = end_index;
+
+        if brace && !length && self.linebuf[index..].starts_with(':') && !self.linebuf[index + 1..].starts_with(['?', '-', '+', '=']) {
+            // a numeric argument is still a substring: ${file:1}
+            let resolver = if is_numeric_offset(&self.linebuf[index + 1..]) { None } else { self.get_resolver(key) };
+            if let Some(resolver) = resolver {
+                // ${scheme:argument}
+                let scheme = key.to_owned();
+                let lineno = self.lineno;
+                let argument;
+                (index, argument) = self.parse_word(index + 1, buf, env, '}')?;
+
+                if !buf.is_skipped() {
+                    let context = ResolveContext { env, parent: self.parent, path: self.file };
+                    let location = SourceLocation::new(lineno, var_start_index + 1);
+                    let result = cached_substitution(&self.path, location, &format!("{scheme}:{argument}"), || {
+                        resolver.resolve(&argument, &context).map_err(|err| err.to_string())
+                    });
+                    match result {
+                        Ok(value) => buf.push_str(&value),
+                        Err(err) => {
+                            let column = var_start_index + 1;
+                            if self.debug {
+                                eprintln!("{DEBUG_PREFIX}{}:{lineno}:{column}: substitution error: ${{{scheme}:...}}: {err}", self.path);
+                            }
+                            if self.strict {
+                                return Err(Error::new(ErrorKind::SubstitutionError, format!("{scheme}: {err}"), SourceLocation::new(lineno, column)));
+                            }
+                        }
+                    }
+                }
+
+                if self.linebuf[index..].starts_with('}') {
+                    index += 1;
+                } else {
+                    let column = index + 1;
+                    if self.debug {
+                        let line = self.linebuf.trim_end_matches('\n');
+                        eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: expected '}}': {line}", self.path, self.lineno);
+                    }
+                    if self.strict {
+                        return Err(Error::syntax_error(self.lineno, column));
+                    }
+                }
+
+                return Ok(index);
+            }
+        }
+
+        let value = self.get_var(env, key);
+        if value.is_none() && !buf.is_skipped() {
+            // forms with a default value or error message handle unset variables
+            let tail = &self.linebuf[index..];
+            let tail = tail.strip_prefix(':').unwrap_or(tail);
+            if !brace || length || !tail.starts_with(['-', '+', '=', '?']) {
+                let location = SourceLocation::new(self.lineno, var_start_index + 1);
+                self.undefined_vars.report(&self.path, key, location, self.strict, self.debug)?;
+            }
+        }
+
         if brace {
             let tail = &self.linebuf[index..];
 
-            if tail.starts_with(":?") {
+            if length {
+                let len = value.map(|value| value.to_string_lossy().chars().count()).unwrap_or(0);
+                buf.push_str(&len.to_string());
+            } else if tail.starts_with(":?") {
                 // required error when empty or unset
                 index += 2;
                 if let Some(value) = value {
@@ -823,6 +1077,90 @@ impl<'c> Parser<'c> {
                 } else {
                     index = self.parse_value(index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
                 }
+            } else if tail.starts_with(":=") || tail.starts_with('=') {
+                // assign default when (empty or) unset
+                let colon = tail.starts_with(':');
+                index += if colon { 2 } else { 1 };
+                match value {
+                    Some(value) if !colon || !value.is_empty() => {
+                        buf.push_str(value.to_string_lossy().as_ref());
+                        index = self.parse_value(index, &mut NullStringBuffer(), &EmptyEnv(), true)?;
+                    }
+                    _ => {
+                        let key = key.to_owned();
+                        let word;
+                        (index, word) = self.parse_word(index, buf, env, '}')?;
+                        if !buf.is_skipped() {
+                            buf.push_str(&word);
+                            self.assigned.push((key, word, key_location));
+                        }
+                    }
+                }
+            } else if tail.starts_with(':') {
+                // substring
+                let column = index + 2;
+                let lineno = self.lineno;
+                let offset;
+                (index, offset) = self.parse_word(index + 1, buf, env, ':')?;
+                let mut length = None;
+                if self.linebuf[index..].starts_with(':') {
+                    let word;
+                    (index, word) = self.parse_word(index + 1, buf, env, '}')?;
+                    length = Some(word);
+                }
+
+                if !buf.is_skipped() {
+                    let value = value.map(|value| value.to_string_lossy().into_owned()).unwrap_or_default();
+                    match substring(&value, &offset, length.as_deref()) {
+                        Ok(substr) => buf.push_str(&substr),
+                        Err(message) => {
+                            if self.debug {
+                                eprintln!("{DEBUG_PREFIX}{}:{lineno}:{column}: substitution error: {message}", self.path);
+                            }
+                            if self.strict {
+                                return Err(Error::new(ErrorKind::SubstitutionError, message, SourceLocation::new(lineno, column)));
+                            }
+                        }
+                    }
+                }
+            } else if tail.starts_with('#') || tail.starts_with('%') {
+                // remove shortest/longest matching prefix/suffix
+                let suffix = tail.starts_with('%');
+                let longest = tail[1..].starts_with(if suffix { '%' } else { '#' });
+                index += if longest { 2 } else { 1 };
+                let pattern;
+                (index, pattern) = self.parse_word(index, buf, env, '}')?;
+                if let Some(value) = value {
+                    buf.push_str(&strip_pattern(&value.to_string_lossy(), &pattern, suffix, longest));
+                }
+            } else if let Some(rest) = tail.strip_prefix('/') {
+                // pattern replacement
+                let mode = match rest.chars().next() {
+                    Some('/') => Replace::All,
+                    Some('#') => Replace::Prefix,
+                    Some('%') => Replace::Suffix,
+                    _ => Replace::First,
+                };
+                index += if mode == Replace::First { 1 } else { 2 };
+                let pattern;
+                (index, pattern) = self.parse_word(index, buf, env, '/')?;
+                let mut replacement = String::new();
+                if self.linebuf[index..].starts_with('/') {
+                    (index, replacement) = self.parse_word(index + 1, buf, env, '}')?;
+                }
+                if let Some(value) = value {
+                    buf.push_str(&replace_pattern(&value.to_string_lossy(), &pattern, &replacement, mode));
+                }
+            } else if tail.starts_with('^') || tail.starts_with(',') {
+                // case conversion of the first/all characters matching the pattern
+                let upper = tail.starts_with('^');
+                let all = tail[1..].starts_with(if upper { '^' } else { ',' });
+                index += if all { 2 } else { 1 };
+                let pattern;
+                (index, pattern) = self.parse_word(index, buf, env, '}')?;
+                if let Some(value) = value {
+                    buf.push_str(&convert_case(&value.to_string_lossy(), &pattern, upper, all));
+                }
             } else if let Some(value) = value {
                 buf.push_str(value.to_string_lossy().as_ref());
             }
@@ -833,14 +1171,22 @@ impl<'c> Parser<'c> {
             } else {
                 let column = end_index + 1;
                 if self.debug {
-                    let line = self.linebuf.trim_end_matches('\n');
-                    eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: expected '}}': {line}", self.path, self.lineno);
+                    if self.reads == var_reads {
+                        let line = self.linebuf.trim_end_matches('\n');
+                        eprintln!("{DEBUG_PREFIX}{}:{var_lineno}:{column}: syntax error: expected '}}': {line}", self.path);
+                    } else {
+                        eprintln!("{DEBUG_PREFIX}{}:{var_lineno}:{column}: syntax error: expected '}}'", self.path);
+                    }
                 }
                 if self.strict {
-                    return Err(Error::syntax_error(self.lineno, column));
+                    return Err(Error::syntax_error(var_lineno, column));
+                }
+                // if a quoted word ran into the following lines they are
+                // consumed, otherwise keep the reference as it is
+                if self.reads == var_reads {
+                    index = end_index;
+                    buf.push_str(&self.linebuf[var_start_index..index]);
                 }
-                index = end_index;
-                buf.push_str(&self.linebuf[var_start_index..index]);
             }
         } else if let Some(value) = value {
             // TODO: don't use lossy when strict?
@@ -849,6 +1195,382 @@ impl<'c> Parser<'c> {
 
         Ok(index)
     }
+
+    /// Read the next line into `linebuf`, the pending lines first. Reading past
+    /// a heredoc line doesn't advance the line number, so errors are reported
+    /// on that line.
+    fn read_line(&mut self) -> std::io::Result<usize> {
+        self.reads += 1;
+        self.linebuf.clear();
+        if let Some((lineno, line)) = self.pending.pop_front() {
+            self.lineno = lineno;
+            self.linebuf = line;
+            return Ok(self.linebuf.len());
+        }
+
+        if !self.heredoc_line {
+            self.lineno += 1;
+        }
+        self.encoding.read_line(self.reader, &mut self.linebuf)
+    }
+
+    /// Variables assigned by `${VAR:=DEFAULT}` in the current value aren't in
+    /// `env` yet.
+    fn get_var<'e>(&self, env: &'e dyn GetEnv, key: &str) -> Option<Cow<'e, OsStr>> {
+        if let Some((_, value, _)) = self.assigned.iter().rev().find(|(assigned_key, _, _)| assigned_key == key) {
+            return Some(Cow::Owned(value.into()));
+        }
+        env.get(key.as_ref())
+    }
+
+    /// Parse a heredoc (`KEY<<EOF`) starting at the `<<` at `index`. The value
+    /// are the following lines up to a line that only contains the delimiter,
+    /// without the newline before it. Returns `false` if the heredoc is
+    /// malformed or unterminated and not strict.
+    fn parse_heredoc(&mut self, mut index: usize, value: &mut dyn StringBuffer, env: &dyn GetEnv) -> Result<bool> {
+        let start_lineno = self.lineno;
+        let start_column = index + 1;
+
+        index += 2;
+        let strip_indent = self.linebuf[index..].starts_with('-');
+        if strip_indent {
+            index += 1;
+        }
+        index = skip_ws(&self.linebuf, index);
+
+        // a quoted delimiter means a raw heredoc
+        let quote = char_at(&self.linebuf, index).filter(|&ch| ch == '\'' || ch == '"');
+        if quote.is_some() {
+            index += 1;
+        }
+
+        let delimiter_index = index;
+        index = find_word_end(&self.linebuf, index);
+        let delimiter = self.linebuf[delimiter_index..index].to_owned();
+
+        let mut valid = !delimiter.is_empty();
+        if let Some(quote) = quote {
+            valid &= char_at(&self.linebuf, index) == Some(quote);
+            index += 1;
+        }
+        if valid {
+            index = skip_ws(&self.linebuf, index);
+            valid = matches!(char_at(&self.linebuf, index), None | Some('\n' | '#'));
+        }
+
+        if !valid {
+            let column = index + 1;
+            if self.debug {
+                let line = self.linebuf.trim_end_matches('\n');
+                eprintln!("{DEBUG_PREFIX}{}:{}:{column}: syntax error: illegal heredoc delimiter: {line}", self.path, self.lineno);
+            }
+            if self.strict {
+                return Err(Error::syntax_error(self.lineno, column));
+            }
+            return Ok(false);
+        }
+
+        let mut lines = Vec::new();
+        loop {
+            if let Err(err) = self.read_line() {
+                if self.debug {
+                    eprintln!("{DEBUG_PREFIX}{}:{}:1: {err}", self.path, self.lineno);
+                }
+                if self.strict {
+                    return Err(Error::new(ErrorKind::IOError, err, SourceLocation::new(self.lineno, 1)));
+                }
+                if err.kind() == std::io::ErrorKind::InvalidData {
+                    continue;
+                }
+                self.linebuf.clear();
+            }
+
+            if self.linebuf.is_empty() {
+                if self.debug {
+                    eprintln!("{DEBUG_PREFIX}{}:{start_lineno}:{start_column}: syntax error: unterminated heredoc, expected {delimiter}", self.path);
+                }
+                if self.strict {
+                    return Err(Error::new(ErrorKind::SyntaxError,
+                        format!("unterminated heredoc, expected {delimiter}"),
+                        SourceLocation::new(start_lineno, start_column)));
+                }
+                // only skip the line with the <<
+                self.pending.extend(lines);
+                return Ok(false);
+            }
+
+            if self.linebuf.ends_with("\r\n") {
+                // convert DOS line endings to Unix
+                self.linebuf.remove(self.linebuf.len() - 2);
+            }
+
+            let line = self.linebuf.trim_end_matches('\n');
+            let line = if strip_indent { &line[indentation(line)..] } else { line };
+            if line == delimiter {
+                break;
+            }
+
+            lines.push((self.lineno, std::mem::take(&mut self.linebuf)));
+        }
+
+        let end_lineno = self.lineno;
+        let indent = if strip_indent {
+            lines.iter()
+                .map(|(_, line)| line.trim_end_matches('\n'))
+                .filter(|line| !line.trim_start_matches([' ', '\t']).is_empty())
+                .map(indentation)
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        for (line_index, (lineno, line)) in lines.into_iter().enumerate() {
+            if line_index > 0 {
+                value.push('\n');
+            }
+
+            let start_index = indentation(&line).min(indent);
+            if quote.is_some() {
+                value.push_str(line[start_index..].trim_end_matches('\n'));
+            } else {
+                // A substitution must not read past its line, since the
+                // following lines belong to the heredoc.
+                let mut end: &[u8] = &[];
+                let mut parser = Parser {
+                    path: Cow::Borrowed(&self.path),
+                    lineno,
+                    debug: self.debug,
+                    strict: self.strict,
+                    encoding: self.encoding,
+                    reader: &mut end,
+                    linebuf: line,
+                    assigned: std::mem::take(&mut self.assigned),
+                    resolvers: self.resolvers.clone(),
+                    file: self.file,
+                    parent: self.parent,
+                    undefined_vars: self.undefined_vars,
+                    pending: VecDeque::new(),
+                    heredoc_line: true,
+                    reads: 0,
+                };
+                let result = parser.parse_heredoc_line(start_index, value, env);
+                self.assigned = parser.assigned;
+                result?;
+            }
+        }
+
+        self.lineno = end_lineno;
+
+        Ok(true)
+    }
+
+    /// Substitute variables in a line of an interpolating heredoc. Only `\$`
+    /// and `\\` are escape sequences.
+    fn parse_heredoc_line(&mut self, mut index: usize, value: &mut dyn StringBuffer, env: &dyn GetEnv) -> Result<()> {
+        let mut prev_index = index;
+        while let Some(pos) = self.linebuf.get(index..).and_then(|tail| tail.find(['$', '\\', '\n'])) {
+            index += pos;
+            value.push_str(&self.linebuf[prev_index..index]);
+
+            match self.linebuf.as_bytes()[index] {
+                b'\n' => return Ok(()),
+                b'$' => {
+                    index = self.parse_var(index + 1, value, env)?;
+                }
+                _ => {
+                    match char_at(&self.linebuf, index + 1) {
+                        Some(ch @ ('$' | '\\')) => {
+                            value.push(ch);
+                            index += 2;
+                        }
+                        _ => {
+                            value.push('\\');
+                            index += 1;
+                        }
+                    }
+                }
+            }
+            prev_index = index;
+        }
+
+        if let Some(tail) = self.linebuf.get(prev_index..) {
+            value.push_str(tail.trim_end_matches('\n'));
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn get_resolver(&self, scheme: &str) -> Option<Arc<dyn Resolver>> {
+        self.resolvers.iter()
+            .find(|(other, _)| other == scheme)
+            .map(|(_, resolver)| resolver.clone())
+    }
+
+    /// Parse a word of a parameter expansion (pattern, replacement, offset,
+    /// etc.). Returns an empty string if `buf` is skipped.
+    fn parse_word(&mut self, index: usize, buf: &dyn StringBuffer, env: &dyn GetEnv, end: char) -> Result<(usize, String)> {
+        let mut word = String::new();
+        let index = if buf.is_skipped() {
+            self.parse_value_until(index, &mut NullStringBuffer(), &EmptyEnv(), true, end)?
+        } else {
+            self.parse_value_until(index, &mut word, env, true, end)?
+        };
+        Ok((index, word))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Replace {
+    First,
+    All,
+    Prefix,
+    Suffix,
+}
+
+/// `${VAR#PATTERN}`, `${VAR##PATTERN}`, `${VAR%PATTERN}`, and `${VAR%%PATTERN}`
+fn strip_pattern(value: &str, pattern: &str, suffix: bool, longest: bool) -> String {
+    let glob = Glob::new(pattern);
+    let chars: Vec<char> = value.chars().collect();
+    let len = chars.len();
+
+    let found = if suffix {
+        if longest {
+            (0..=len).find(|&start| glob.matches(&chars[start..]))
+        } else {
+            (0..=len).rev().find(|&start| glob.matches(&chars[start..]))
+        }.map(|start| &chars[..start])
+    } else if longest {
+        (0..=len).rev().find(|&end| glob.matches(&chars[..end])).map(|end| &chars[end..])
+    } else {
+        (0..=len).find(|&end| glob.matches(&chars[..end])).map(|end| &chars[end..])
+    };
+
+    match found {
+        Some(rest) => rest.iter().collect(),
+        None => value.to_owned(),
+    }
+}
+
+/// `${VAR/PATTERN/REPLACEMENT}` and friends. Always replaces the longest match.
+fn replace_pattern(value: &str, pattern: &str, replacement: &str, mode: Replace) -> String {
+    let glob = Glob::new(pattern);
+    let chars: Vec<char> = value.chars().collect();
+    let len = chars.len();
+
+    match mode {
+        Replace::Prefix => {
+            let Some(end) = (0..=len).rev().find(|&end| glob.matches(&chars[..end])) else {
+                return value.to_owned();
+            };
+            let mut result = replacement.to_owned();
+            result.extend(&chars[end..]);
+            result
+        }
+        Replace::Suffix => {
+            let Some(start) = (0..=len).find(|&start| glob.matches(&chars[start..])) else {
+                return value.to_owned();
+            };
+            let mut result: String = chars[..start].iter().collect();
+            result.push_str(replacement);
+            result
+        }
+        Replace::First | Replace::All => {
+            if glob.is_empty() {
+                return value.to_owned();
+            }
+
+            let mut result = String::with_capacity(value.len());
+            let mut start = 0;
+            while start < len {
+                // empty matches are ignored
+                if let Some(end) = (start + 1..=len).rev().find(|&end| glob.matches(&chars[start..end])) {
+                    result.push_str(replacement);
+                    start = end;
+                    if mode == Replace::First {
+                        break;
+                    }
+                } else {
+                    result.push(chars[start]);
+                    start += 1;
+                }
+            }
+            result.extend(&chars[start..]);
+            result
+        }
+    }
+}
+
+/// Does `rest` start with a literal substring offset followed by `:` or `}`?
+fn is_numeric_offset(rest: &str) -> bool {
+    let rest = rest.trim_start_matches([' ', '\t']);
+    let rest = rest.strip_prefix(['-', '+']).unwrap_or(rest);
+    let digits = rest.len() - rest.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+    digits > 0 && rest[digits..].trim_start_matches([' ', '\t']).starts_with([':', '}'])
+}
+
+fn parse_offset(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Some(0);
+    }
+    value.parse().ok()
+}
+
+/// `${VAR:OFFSET}` and `${VAR:OFFSET:LENGTH}`. A negative `OFFSET` counts from
+/// the end of the value, a negative `LENGTH` is the end counted from the end.
+fn substring(value: &str, offset: &str, length: Option<&str>) -> std::result::Result<String, String> {
+    let Some(offset) = parse_offset(offset) else {
+        return Err(format!("illegal substring offset: {offset:?}"));
+    };
+
+    let chars: Vec<char> = value.chars().collect();
+    let len = chars.len() as i64;
+    let start = if offset < 0 { len + offset } else { offset };
+    if start < 0 || start > len {
+        return Ok(String::new());
+    }
+
+    let end = match length {
+        None => len,
+        Some(length) => {
+            let Some(length) = parse_offset(length) else {
+                return Err(format!("illegal substring length: {length:?}"));
+            };
+            if length < 0 {
+                let end = len + length;
+                if end < start {
+                    return Err(format!("substring expression < 0: {length}"));
+                }
+                end
+            } else {
+                len.min(start.saturating_add(length))
+            }
+        }
+    };
+
+    Ok(chars[start as usize..end as usize].iter().collect())
+}
+
+/// `${VAR^PATTERN}`, `${VAR^^PATTERN}`, `${VAR,PATTERN}`, and `${VAR,,PATTERN}`
+fn convert_case(value: &str, pattern: &str, upper: bool, all: bool) -> String {
+    let glob = Glob::new(pattern);
+    let mut result = String::with_capacity(value.len());
+
+    for (index, ch) in value.chars().enumerate() {
+        if (all || index == 0) && glob.matches_char(ch) {
+            if upper {
+                result.extend(ch.to_uppercase());
+            } else {
+                result.extend(ch.to_lowercase());
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
 }
 
 trait StringBuffer {
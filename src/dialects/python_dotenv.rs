@@ -1,7 +1,7 @@
 // trying to emulate: https://github.com/theskumar/python-dotenv/blob/main/src/dotenv/parser.py
-use std::{borrow::Cow, io::BufRead, path::Path};
+use std::{borrow::Cow, collections::HashMap, ffi::OsStr, fs::File, io::{BufRead, BufReader, Write}, ops::Range, path::{Component, Path, PathBuf}};
 
-use crate::{env::GetEnv, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{dialects::{nth_reference, report_undefined_var}, env::GetEnv, error::SourceLocation, options::{IllegalOption, OptionType}, Encoding, Env, Error, ErrorKind, Options, Result, DEBUG_PREFIX};
 
 pub fn config_python_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options: &Options<&Path>) -> Result<()> {
     let mut string = String::new();
@@ -38,10 +38,21 @@ pub fn config_python_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options
             continue;
         };
 
+        if !options.check_key(key, binding.location.lineno(), binding.location.column())? {
+            continue;
+        }
+
         if let Some(value) = &binding.value {
-            // the original has interpolation as an option, but defaults to true
-            let value = interpolate(value, env.as_get_env());
-            options.set_var_cut_null(env, key, &value);
+            if options.dialect_options.python.interpolate {
+                let mut undefined = Vec::new();
+                let interpolated = interpolate(value, env.as_get_env(), &mut undefined);
+                for (name, offset) in undefined {
+                    report_undefined_var(&reader.string, reader.get_marked(), name, nth_reference(value, name, offset), options)?;
+                }
+                options.set_var_cut_null(env, key, &interpolated);
+            } else {
+                options.set_var_cut_null(env, key, value);
+            }
         } else {
             let key = key.split('\0').next().unwrap();
             env.remove(key.as_ref());
@@ -52,8 +63,11 @@ pub fn config_python_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, options
 }
 
 // see: https://github.com/theskumar/python-dotenv/blob/main/src/dotenv/variables.py
-fn interpolate(mut src: &str, env: &dyn GetEnv) -> String {
+/// References to undefined variables without default value are added to
+/// `undefined` with their offset in `src`.
+fn interpolate<'a>(mut src: &'a str, env: &dyn GetEnv, undefined: &mut Vec<(&'a str, usize)>) -> String {
     let mut buf = String::new();
+    let len = src.len();
 
     loop {
         let Some(index) = src.find("${") else {
@@ -66,9 +80,11 @@ fn interpolate(mut src: &str, env: &dyn GetEnv) -> String {
 
         let index = src.find(|ch: char| ch == ':' || ch == '}').unwrap_or(src.len());
         let key = &src[..index];
+        let key_offset = len - src.len();
         src = &src[index..];
 
-        let default = if src.starts_with(":-") {
+        let has_default = src.starts_with(":-");
+        let default = if has_default {
             src = &src[2..];
             let index = src.find('}').unwrap_or(src.len());
             let default = &src[..index];
@@ -89,6 +105,9 @@ fn interpolate(mut src: &str, env: &dyn GetEnv) -> String {
         if let Some(value) = env.get(key.as_ref()) {
             buf.push_str(value.to_string_lossy().as_ref());
         } else {
+            if !has_default {
+                undefined.push((key, key_offset));
+            }
             buf.push_str(default);
         }
     }
@@ -98,6 +117,360 @@ fn interpolate(mut src: &str, env: &dyn GetEnv) -> String {
     buf
 }
 
+// The file helpers of python-dotenv.
+// see: https://github.com/theskumar/python-dotenv/blob/main/src/dotenv/main.py
+
+/// How [`set_key()`] quotes the written value.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum QuoteMode {
+    /// Always put the value in single quotes.
+    #[default]
+    Always,
+    /// Only quote values that aren't purely alphanumeric.
+    Auto,
+    /// Write the value verbatim.
+    Never,
+}
+
+impl std::fmt::Display for QuoteMode {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuoteMode::Always => "always".fmt(f),
+            QuoteMode::Auto   => "auto".fmt(f),
+            QuoteMode::Never  => "never".fmt(f),
+        }
+    }
+}
+
+impl TryFrom<&OsStr> for QuoteMode {
+    type Error = IllegalOption;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, Self::Error> {
+        if value.eq_ignore_ascii_case("always") {
+            Ok(QuoteMode::Always)
+        } else if value.eq_ignore_ascii_case("auto") {
+            Ok(QuoteMode::Auto)
+        } else if value.eq_ignore_ascii_case("never") {
+            Ok(QuoteMode::Never)
+        } else {
+            Err(IllegalOption::new("quote_mode".into(), value.into(), OptionType::QuoteMode))
+        }
+    }
+}
+
+/// Search `filename` in the current working directory (if `usecwd` is `true`)
+/// or the directory of the running executable and then in all of their parent
+/// directories. This is the equivalent of `find_dotenv(usecwd=...)`, the
+/// executable takes the place of the calling Python script.
+pub fn find_dotenv(filename: impl AsRef<Path>, usecwd: bool) -> std::io::Result<Option<PathBuf>> {
+    let start = if usecwd {
+        std::env::current_dir()?
+    } else {
+        std::env::current_exe()?
+    };
+    find_dotenv_from(filename, start)
+}
+
+/// Search `filename` in `start` and then in all of its parent directories.
+/// If `start` is a file the search starts in its directory.
+pub fn find_dotenv_from(filename: impl AsRef<Path>, start: impl AsRef<Path>) -> std::io::Result<Option<PathBuf>> {
+    let filename = filename.as_ref();
+    let start = start.as_ref();
+
+    if !start.exists() {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Starting path not found"));
+    }
+
+    let start = if start.is_file() {
+        start.parent().unwrap_or(Path::new(""))
+    } else {
+        start
+    };
+
+    let mut dir = Some(abspath(start)?);
+    while let Some(current) = dir {
+        let check_path = current.join(filename);
+        if check_path.is_file() {
+            return Ok(Some(check_path));
+        }
+        dir = current.parent().map(Path::to_path_buf);
+    }
+
+    Ok(None)
+}
+
+/// Like Python's `os.path.abspath()`, which normalizes `..` without resolving symlinks.
+fn abspath(path: &Path) -> std::io::Result<PathBuf> {
+    let mut abs = PathBuf::new();
+    let path = if path.is_absolute() {
+        Cow::Borrowed(path)
+    } else {
+        Cow::Owned(std::env::current_dir()?.join(path))
+    };
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => { abs.pop(); }
+            _ => abs.push(component),
+        }
+    }
+
+    Ok(abs)
+}
+
+/// Get the value of `key` from the file at `options.path`, like `get_key()`.
+/// Variables are interpolated, unless `options.dialect_options.python.interpolate`
+/// is `false`. Other variables defined in the file take precedence over
+/// the ones from `parent`.
+///
+/// A missing file is treated like an empty file.
+pub fn get_key<P>(options: &Options<P>, parent: &impl GetEnv, key: &str) -> Result<Option<String>>
+where P: AsRef<Path> {
+    let path = options.path.as_ref();
+    let Some((string, _, _)) = read_file(path, options.encoding, options.debug)? else {
+        return Ok(None);
+    };
+
+    let mut reader = Reader::with_path(string, path);
+    let statements = parse_stream(&mut reader, options.debug);
+
+    let mut values: HashMap<&str, Option<String>> = HashMap::new();
+    let mut found = false;
+    for statement in &statements {
+        let Some(name) = &statement.key else {
+            continue;
+        };
+
+        let value = if options.dialect_options.python.interpolate {
+            statement.value.as_ref().map(|value| interpolate(value, &ResolveEnv { values: &values, parent }, &mut Vec::new()))
+        } else {
+            statement.value.clone()
+        };
+
+        found |= name == key;
+        values.insert(name, value);
+    }
+
+    if !found && options.debug {
+        eprintln!("{DEBUG_PREFIX}{}: Key {key} not found", path.to_string_lossy());
+    }
+
+    Ok(values.remove(key).flatten())
+}
+
+/// Set `key` to `value` in the file at `options.path`, like `set_key()`.
+/// An existing definition is replaced in place, otherwise the definition is
+/// appended. Creates the file if it doesn't exist.
+///
+/// Like with python-dotenv the whole file is re-written with `\n` line endings.
+pub fn set_key<P>(options: &Options<P>, key: &str, value: &str, quote_mode: QuoteMode, export: bool) -> Result<()>
+where P: AsRef<Path> {
+    let quote = match quote_mode {
+        QuoteMode::Always => true,
+        QuoteMode::Auto   => value.is_empty() || !value.chars().all(char::is_alphanumeric),
+        QuoteMode::Never  => false,
+    };
+
+    let value_out = if quote {
+        Cow::Owned(format!("'{}'", value.replace('\'', "\\'")))
+    } else {
+        Cow::Borrowed(value)
+    };
+
+    let line_out = if export {
+        format!("export {key}={value_out}\n")
+    } else {
+        format!("{key}={value_out}\n")
+    };
+
+    let path = options.path.as_ref();
+    let (string, encoding, bom) = read_file(path, options.encoding, options.debug)?.
+        unwrap_or_else(|| (String::new(), options.encoding, false));
+    let mut reader = Reader::with_path(string, path);
+    let statements = parse_stream(&mut reader, options.debug);
+
+    let mut dest = String::with_capacity(reader.string.len() + line_out.len());
+    let mut replaced = false;
+    let mut missing_newline = false;
+    for statement in &statements {
+        if statement.key.as_deref() == Some(key) {
+            dest.push_str(&line_out);
+            replaced = true;
+        } else {
+            let original = &reader.string[statement.original.clone()];
+            dest.push_str(original);
+            missing_newline = !original.ends_with('\n');
+        }
+    }
+
+    if !replaced {
+        if missing_newline {
+            dest.push('\n');
+        }
+        dest.push_str(&line_out);
+    }
+
+    write_file(path, encoding, bom, &dest)
+}
+
+/// Remove all definitions of `key` from the file at `options.path`, like
+/// `unset_key()`. Returns `false` if the file or the key doesn't exist, in
+/// which case the file isn't touched.
+pub fn unset_key<P>(options: &Options<P>, key: &str) -> Result<bool>
+where P: AsRef<Path> {
+    let path = options.path.as_ref();
+    let Some((string, encoding, bom)) = read_file(path, options.encoding, options.debug)? else {
+        return Ok(false);
+    };
+
+    let mut reader = Reader::with_path(string, path);
+    let statements = parse_stream(&mut reader, options.debug);
+
+    let mut dest = String::with_capacity(reader.string.len());
+    let mut removed = false;
+    for statement in &statements {
+        if statement.key.as_deref() == Some(key) {
+            removed = true;
+        } else {
+            dest.push_str(&reader.string[statement.original.clone()]);
+        }
+    }
+
+    if !removed {
+        if options.debug {
+            eprintln!("{DEBUG_PREFIX}{}: Key {key} not removed - key doesn't exist", path.to_string_lossy());
+        }
+        return Ok(false);
+    }
+
+    write_file(path, encoding, bom, &dest)?;
+
+    Ok(true)
+}
+
+/// Read a file like Python does in text mode, i.e. with universal newlines.
+/// Also returns the actual encoding (see [`Encoding::resolve()`]) and whether
+/// the file started with a byte order mark, so it can be written back the
+/// same way.
+fn read_file(path: &Path, encoding: Encoding, debug: bool) -> Result<Option<(String, Encoding, bool)>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            if debug {
+                eprintln!("{DEBUG_PREFIX}{}: {err}", path.to_string_lossy());
+            }
+            return Ok(None);
+        }
+        Err(err) => return Err(Error::with_cause(ErrorKind::IOError, err)),
+    };
+
+    let mut reader = BufReader::new(file);
+    let (encoding, bom) = encoding.resolve_bom(&mut reader)?;
+    let mut string = String::new();
+    encoding.read_to_string(&mut reader, &mut string)?;
+
+    if string.contains('\r') {
+        string = string.replace("\r\n", "\n").replace('\r', "\n");
+    }
+
+    Ok(Some((string, encoding, bom)))
+}
+
+/// Write to a temporary file in the same directory first and then move it in place.
+fn write_file(path: &Path, encoding: Encoding, bom: bool, string: &str) -> Result<()> {
+    let mut bytes = encoding.encode(string)?;
+    if bom {
+        bytes.splice(0..0, encoding.bom().iter().cloned());
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or(OsStr::new(".env")).to_owned();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let res = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bytes)?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    })();
+
+    if let Err(err) = res {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(Error::with_cause(ErrorKind::IOError, err));
+    }
+
+    Ok(())
+}
+
+/// Definitions read so far shadow the parent environment, like in
+/// `resolve_variables(override=True)`. A variable without a value still
+/// shadows the parent, but resolves to an empty string.
+struct ResolveEnv<'a, E: GetEnv> {
+    values: &'a HashMap<&'a str, Option<String>>,
+    parent: &'a E,
+}
+
+impl<'a, E: GetEnv> GetEnv for ResolveEnv<'a, E> {
+    fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
+        if let Some(value) = key.to_str().and_then(|key| self.values.get(key)) {
+            return Some(Cow::Borrowed(value.as_deref().unwrap_or("").as_ref()));
+        }
+        self.parent.get(key)
+    }
+
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        for (key, value) in self.values {
+            f(key.as_ref(), value.as_deref().unwrap_or("").as_ref());
+        }
+
+        self.parent.for_each(&mut |key, value| {
+            if !key.to_str().is_some_and(|key| self.values.contains_key(key)) {
+                f(key, value);
+            }
+        });
+    }
+}
+
+/// A binding together with its source text, like python-dotenv's `parse_stream()`.
+struct Statement {
+    key: Option<String>,
+    value: Option<String>,
+    original: Range<usize>,
+}
+
+fn parse_stream(reader: &mut Reader, debug: bool) -> Vec<Statement> {
+    let mut statements = Vec::new();
+
+    while reader.has_next() {
+        let statement = match reader.parse_binding() {
+            Ok(binding) => {
+                let (key, value) = binding.map(|binding| (binding.key, binding.value)).unwrap_or_default();
+                Statement { key, value, original: reader.get_marked() }
+            }
+            Err(_) => {
+                if debug {
+                    eprintln!("{DEBUG_PREFIX}{}:{}: could not parse statement", reader.path, reader.mark.lineno);
+                }
+                Statement { key: None, value: None, original: reader.get_marked() }
+            }
+        };
+
+        if statement.original.is_empty() {
+            // should not happen, but better not loop forever
+            break;
+        }
+
+        statements.push(statement);
+    }
+
+    statements
+}
+
 struct Position {
     index: usize,
     lineno: usize,
@@ -121,14 +494,10 @@ impl Position {
     }
 }
 
-// struct Original<'a> {
-//     string: &'a str,
-//     lineno: usize,
-// }
-
 struct Binding {
     key: Option<String>,
     value: Option<String>,
+    location: SourceLocation,
 }
 
 struct Reader<'a> {
@@ -141,11 +510,16 @@ struct Reader<'a> {
 impl<'a> Reader<'a> {
     #[inline]
     pub fn new(string: String, options: &Options<&'a Path>) -> Self {
+        Self::with_path(string, options.path)
+    }
+
+    #[inline]
+    fn with_path(string: String, path: &'a Path) -> Self {
         Self {
             string,
             position: Position::start(),
             mark: Position::start(),
-            path: options.path.to_string_lossy()
+            path: path.to_string_lossy()
         }
     }
 
@@ -159,26 +533,27 @@ impl<'a> Reader<'a> {
         self.mark.set(&self.position);
     }
 
-    // #[inline]
-    // pub fn get_marked(&self) -> Original {
-    //     Original {
-    //         string: &self.string[self.mark.index..self.position.index],
-    //         lineno: self.mark.lineno,
-    //     }
-    // }
+    #[inline]
+    pub fn get_marked(&self) -> Range<usize> {
+        self.mark.index..self.position.index
+    }
 
     #[inline]
     pub fn peek(&self) -> Option<char> {
         self.string[self.position.index..].chars().next()
     }
 
+    pub fn location(&self) -> SourceLocation {
+        let line_start = self.string[..self.position.index].
+            rfind(|ch| ch == '\n' || ch == '\r').
+            map(|pos| pos + 1).
+            unwrap_or(0);
+        SourceLocation::new(self.position.lineno, 1 + self.position.index - line_start)
+    }
+
     pub fn read_pattern(&mut self, pattern: fn(string: &str, index: usize) -> Option<Match>) -> Result<Match> {
         let Some(m) = pattern(&self.string, self.position.index) else {
-            let line_start = self.string[..self.position.index].
-                rfind(|ch| ch == '\n' || ch == '\r').
-                map(|pos| pos + 1).
-                unwrap_or(0);
-            return Err(Error::syntax_error(self.position.lineno, 1 + self.position.index - line_start));
+            return Err(Error::with_location(ErrorKind::SyntaxError, self.location()));
         };
 
         self.position.advance(&self.string[m.start_index..m.end_index]);
@@ -256,6 +631,7 @@ impl<'a> Reader<'a> {
 
             reader.read_pattern(match_export)?;
 
+            let location = reader.location();
             let key = reader.parse_key()?;
 
             reader.read_pattern(match_whitespace)?;
@@ -274,6 +650,7 @@ impl<'a> Reader<'a> {
             Ok(Some(Binding {
                 key,
                 value,
+                location,
             }))
         }
 
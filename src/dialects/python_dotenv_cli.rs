@@ -54,6 +54,10 @@ pub fn config_python_dotenv_cli(reader: &mut dyn BufRead, env: &mut dyn Env, opt
         key = key.trim();
         value = value.trim();
 
+        if !options.check_key(key, lineno, 1)? {
+            continue;
+        }
+
         // remove quotes (not sure if this is standard behaviour)
         if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
             value = &value[1..value.len() - 1];
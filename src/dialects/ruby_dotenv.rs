@@ -1,15 +1,25 @@
 // trying to emulate: https://github.com/bkeepers/dotenv/blob/27c80ed122f9bbe403033282e922d74ca717d518/lib/dotenv/parser.rb
 use std::{ffi::OsStr, io::BufRead, path::Path};
 
-use crate::{env::GetEnv, Env, Error, Options, Result, DEBUG_PREFIX};
+use crate::{command::{run_command, split_command}, deferred::cached_substitution, dialects::{nth_reference, report_undefined_var}, env::GetEnv, error::SourceLocation, Env, Error, ErrorKind, Options, Result, AUDIT_PREFIX, DEBUG_PREFIX};
 
 pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
-    let legacy_linebreak = if let Some(value) = parent.get("DOTENV_LINEBREAK_MODE".as_ref()) {
-        let value: &OsStr = value.as_ref();
-        value == "legacy"
-    } else {
-        false
-    };
+    parse_ruby_dotenv(reader, env, parent, options, false)
+}
+
+// Before version 2.8 the dotenv gem always replaced "\n" and "\r" in double quoted
+// values. This is what is now called the "legacy" linebreak mode.
+// See: https://github.com/bkeepers/dotenv/blob/v2.7.6/lib/dotenv/parser.rb
+#[inline]
+pub fn config_ruby_dotenv2(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    parse_ruby_dotenv(reader, env, parent, options, true)
+}
+
+fn parse_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>, always_legacy_linebreak: bool) -> Result<()> {
+    // DOTENV_LINEBREAK_MODE is still honored like the dotenv gem does it,
+    // but the explicit option can't be overridden by it.
+    let always_legacy_linebreak = always_legacy_linebreak || options.dialect_options.ruby.legacy_linebreaks;
+    let legacy_linebreak = always_legacy_linebreak || is_legacy_linebreak(parent).unwrap_or(false);
     let path_str = options.path.to_string_lossy();
     let mut buf = String::new();
     options.encoding.read_to_string(reader, &mut buf)?;
@@ -187,6 +197,7 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
         let tail = &parser.buf[parser.index..];
 
         let value_start = parser.index;
+        let value_location = SourceLocation::new(parser.lineno, value_start - parser.line_start + 1);
         let mut value_end = value_start;
         let mut quote = false;
         if let Some(ch) = tail.chars().next() {
@@ -230,13 +241,23 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
         let value;
         if value_slice.len() > 1 && value_slice.starts_with('\'') && value_slice.ends_with('\'') {
             value = parser.buf[value_start + 1..value_end - 1].to_owned();
-        } else if value_slice.len() > 1 && value_slice.starts_with('"') && value_slice.ends_with('"') {
-            value = perform_substitutions(&unescape_double_quoted(&parser.buf[value_start + 1..value_end - 1], env, legacy_linebreak), env.as_get_env());
         } else {
-            value = perform_substitutions(&unescape_single_unquoted(value_slice.trim_end_matches(|ch| matches!(ch, '\t' | '\x0B' | '\x0C' | ' '))), env.as_get_env());
+            let unescaped = if value_slice.len() > 1 && value_slice.starts_with('"') && value_slice.ends_with('"') {
+                unescape_double_quoted(&parser.buf[value_start + 1..value_end - 1],
+                    always_legacy_linebreak || is_legacy_linebreak(env.as_get_env()).unwrap_or(legacy_linebreak))
+            } else {
+                unescape_single_unquoted(value_slice.trim_end_matches(|ch| matches!(ch, '\t' | '\x0B' | '\x0C' | ' ')))
+            };
+            let mut undefined = Vec::new();
+            value = perform_substitutions(&unescaped, env.as_get_env(), options, value_location, &mut undefined)?;
+            for (name, offset) in undefined {
+                report_undefined_var(&parser.buf, value_start..value_end, name, nth_reference(&unescaped, name, offset), options)?;
+            }
         }
 
-        options.set_var_cut_null(env, parser.buf[key_start..key_end].as_ref(), value.as_ref());
+        if options.check_key(&parser.buf[key_start..key_end], key_lineno, key_start - key_line_start + 1)? {
+            options.set_var_cut_null(env, parser.buf[key_start..key_end].as_ref(), value.as_ref());
+        }
 
         parser.skip_ws_inline();
         let Some(ch) = parser.buf[parser.index..].chars().next() else {
@@ -265,8 +286,19 @@ pub fn config_ruby_dotenv(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &
     Ok(())
 }
 
-fn perform_substitutions(mut src: &str, env: &dyn GetEnv) -> String {
+/// Substitute variables in arbitrary text, commands are never executed.
+pub(crate) fn substitute_text(src: &str, env: &dyn GetEnv) -> String {
+    let options = Options::with_path(Path::new("-"));
+    // can't fail if command substitution is disabled
+    perform_substitutions(src, env, &options, SourceLocation::new(1, 1), &mut Vec::new()).unwrap_or_default()
+}
+
+/// References to undefined variables are added to `undefined` with their
+/// offset in `src`.
+fn perform_substitutions<'a>(mut src: &'a str, env: &dyn GetEnv, options: &Options<&Path>, location: SourceLocation, undefined: &mut Vec<(&'a str, usize)>) -> Result<String> {
+    let commands = !options.dialect_options.ruby.allow_commands.is_empty();
     let mut buf = String::new();
+    let len = src.len();
 
     loop {
         let Some(index) = src.find(|ch| ch == '$' || ch == '\\') else {
@@ -287,6 +319,12 @@ fn perform_substitutions(mut src: &str, env: &dyn GetEnv) -> String {
             }
             buf.push(ch);
             src = &src[ch.len_utf8()..];
+        } else if let (true, Some(cmd_end)) = (commands, find_command_end(src)) {
+            // Unlike the dotenv gem commands are substituted in the same pass
+            // as variables, so the value of a variable is never executed.
+            let cmd = &src[2..cmd_end - 1];
+            src = &src[cmd_end..];
+            buf.push_str(&substitute_command(cmd, options, location)?);
         } else {
             let mut var_start = 1;
             if src.starts_with("${") {
@@ -301,6 +339,8 @@ fn perform_substitutions(mut src: &str, env: &dyn GetEnv) -> String {
                 let key = &src[var_start..var_end];
                 if let Some(value) = env.get(key.as_ref()) {
                     buf.push_str(&value.to_string_lossy());
+                } else {
+                    undefined.push((key, len - src.len() + var_start));
                 }
                 // yes, the { is independent to the } in the original!
                 src = &src[var_end..];
@@ -313,7 +353,63 @@ fn perform_substitutions(mut src: &str, env: &dyn GetEnv) -> String {
 
     buf.push_str(src);
 
-    buf
+    Ok(buf)
+}
+
+/// Find the end of `$(...)` at the start of `src`, after the closing
+/// parenthesis. Like the dotenv gem the parenthesis need to be balanced and
+/// the command may not be empty.
+fn find_command_end(src: &str) -> Option<usize> {
+    if !src.starts_with("$(") {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    for (index, ch) in src.char_indices().skip(1) {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return if index == 2 { None } else { Some(index + 1) };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn substitute_command(cmd: &str, options: &Options<&Path>, location: SourceLocation) -> Result<String> {
+    let ruby = &options.dialect_options.ruby;
+    let path_str = options.path.to_string_lossy();
+    let lineno = location.lineno();
+    let column = location.column();
+
+    // the audit message is only printed when the command actually runs
+    let result = cached_substitution(&path_str, location, cmd, || {
+        match split_command(cmd).and_then(|args| run_command(&args, &ruby.allow_commands, ruby.command_path.as_deref(), ruby.command_timeout)) {
+            Ok((output, status)) => {
+                eprintln!("{AUDIT_PREFIX}{path_str}:{lineno}:{column}: executed $({cmd}): {status}");
+                Ok(output)
+            }
+            Err(err) => {
+                eprintln!("{AUDIT_PREFIX}{path_str}:{lineno}:{column}: failed to execute $({cmd}): {err}");
+                Err(err.to_string())
+            }
+        }
+    });
+
+    match result {
+        Ok(output) => Ok(output),
+        Err(err) => {
+            if options.strict {
+                return Err(Error::new(ErrorKind::ExecError, err, location));
+            }
+            Ok(String::new())
+        }
+    }
 }
 
 fn unescape_single_unquoted(mut value: &str) -> String {
@@ -344,14 +440,13 @@ fn unescape_single_unquoted(mut value: &str) -> String {
     buf
 }
 
-fn unescape_double_quoted(mut value: &str, env: &dyn Env, parent_legacy_linebreak: bool) -> String {
-    let legacy_linebreak = if let Some(value) = env.get("DOTENV_LINEBREAK_MODE".as_ref()) {
-        let value: &OsStr = value.as_ref();
-        value == "legacy"
-    } else {
-        parent_legacy_linebreak
-    };
+fn is_legacy_linebreak(env: &dyn GetEnv) -> Option<bool> {
+    let value = env.get("DOTENV_LINEBREAK_MODE".as_ref())?;
+    let value: &OsStr = value.as_ref();
+    Some(value == "legacy")
+}
 
+fn unescape_double_quoted(mut value: &str, legacy_linebreak: bool) -> String {
     let mut buf = String::new();
 
     while !value.is_empty() {
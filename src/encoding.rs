@@ -10,12 +10,110 @@ pub enum Encoding {
     UTF16LE,
     UTF32BE,
     UTF32LE,
+    /// Detect the encoding by the byte order mark, see [`Encoding::detect()`].
+    Auto,
 }
 
+const BOM_UTF8:    &[u8] = &[0xEF, 0xBB, 0xBF];
+const BOM_UTF16BE: &[u8] = &[0xFE, 0xFF];
+const BOM_UTF16LE: &[u8] = &[0xFF, 0xFE];
+const BOM_UTF32BE: &[u8] = &[0x00, 0x00, 0xFE, 0xFF];
+const BOM_UTF32LE: &[u8] = &[0xFF, 0xFE, 0x00, 0x00];
+
+/// How many bytes are looked at to guess the encoding of text without a BOM.
+const SNIFF_LIMIT: usize = 1024;
+
 impl Encoding {
+    /// The byte order mark of this encoding. Empty for encodings that don't
+    /// have one.
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::UTF8    => BOM_UTF8,
+            Encoding::UTF16BE => BOM_UTF16BE,
+            Encoding::UTF16LE => BOM_UTF16LE,
+            Encoding::UTF32BE => BOM_UTF32BE,
+            Encoding::UTF32LE => BOM_UTF32LE,
+            Encoding::ASCII | Encoding::Latin1 | Encoding::Auto => &[],
+        }
+    }
+
+    /// Guess the encoding of `bytes`, the start of a file.
+    ///
+    /// A UTF-8, UTF-16, or UTF-32 byte order mark decides. Without one the
+    /// text is UTF-16 if at least every second code unit has a null byte,
+    /// and all of them on the same side, which is what mostly ASCII text
+    /// looks like in UTF-16. Anything else is UTF-8.
+    pub fn sniff(bytes: &[u8]) -> Encoding {
+        // UTF-32LE before UTF-16LE, since their BOMs share the first two bytes
+        for encoding in [Encoding::UTF8, Encoding::UTF32LE, Encoding::UTF32BE, Encoding::UTF16LE, Encoding::UTF16BE] {
+            if bytes.starts_with(encoding.bom()) {
+                return encoding;
+            }
+        }
+
+        let bytes = &bytes[..bytes.len().min(SNIFF_LIMIT) & !1];
+        let units = bytes.len() / 2;
+        let mut even_nulls = 0;
+        let mut odd_nulls = 0;
+        for unit in bytes.chunks_exact(2) {
+            if unit[0] == 0 {
+                even_nulls += 1;
+            }
+            if unit[1] == 0 {
+                odd_nulls += 1;
+            }
+        }
+
+        if even_nulls == 0 && odd_nulls > 0 && odd_nulls * 2 >= units {
+            Encoding::UTF16LE
+        } else if odd_nulls == 0 && even_nulls > 0 && even_nulls * 2 >= units {
+            Encoding::UTF16BE
+        } else {
+            Encoding::UTF8
+        }
+    }
+
+    /// Detect the encoding of `reader` with [`Encoding::sniff()`] and skip
+    /// its byte order mark. Only what `reader` has already buffered is
+    /// looked at, nothing but the BOM is consumed.
+    #[inline]
+    pub fn detect(reader: &mut dyn BufRead) -> std::io::Result<Encoding> {
+        Ok(Encoding::Auto.resolve_bom(reader)?.0)
+    }
+
+    /// The encoding to read `reader` with: the detected one for
+    /// [`Encoding::Auto`], `self` otherwise. A matching byte order mark is
+    /// skipped either way, so it doesn't end up in the first key.
+    #[inline]
+    pub fn resolve(&self, reader: &mut dyn BufRead) -> std::io::Result<Encoding> {
+        Ok(self.resolve_bom(reader)?.0)
+    }
+
+    /// Like [`resolve()`](Self::resolve), but also returns whether there was
+    /// a byte order mark.
+    pub(crate) fn resolve_bom(&self, reader: &mut dyn BufRead) -> std::io::Result<(Encoding, bool)> {
+        let bytes = reader.fill_buf()?;
+        let encoding = if *self == Encoding::Auto {
+            Encoding::sniff(bytes)
+        } else {
+            *self
+        };
+
+        let bom = encoding.bom();
+        if bom.is_empty() || !bytes.starts_with(bom) {
+            return Ok((encoding, false));
+        }
+
+        reader.consume(bom.len());
+        Ok((encoding, true))
+    }
+
     pub(crate) fn read_line(&self, reader: &mut dyn BufRead, line: &mut String) -> std::io::Result<usize> {
         match self {
             Encoding::UTF8    => reader.read_line(line),
+            // config_with_reader() and config_punktum() resolve Auto before
+            // anything is parsed
+            Encoding::Auto    => reader.read_line(line),
             Encoding::ASCII   => read_line_ascii(reader, line),
             Encoding::Latin1  => read_line_latin1(reader, line),
             Encoding::UTF16LE => read_line_utf16le(reader, line),
@@ -52,6 +150,49 @@ impl Encoding {
             Encoding::UTF16LE => read_utf16(reader, buf, u16::from_le_bytes),
             Encoding::UTF32BE => read_utf32(reader, buf, u32::from_be_bytes),
             Encoding::UTF32LE => read_utf32(reader, buf, u32::from_le_bytes),
+            Encoding::Auto => Encoding::detect(reader)?.read_to_string(reader, buf),
+        }
+    }
+
+    pub(crate) fn encode(&self, src: &str) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::UTF8 | Encoding::Auto => Ok(src.as_bytes().to_vec()),
+            Encoding::ASCII => {
+                if !src.is_ascii() {
+                    return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+                }
+                Ok(src.as_bytes().to_vec())
+            },
+            Encoding::Latin1 => {
+                let mut bytes = Vec::with_capacity(src.len());
+                for ch in src.chars() {
+                    let Ok(byte) = u8::try_from(ch) else {
+                        return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+                    };
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            },
+            Encoding::UTF16BE => Ok(src.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+            Encoding::UTF16LE => Ok(src.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Encoding::UTF32BE => Ok(src.chars().flat_map(|ch| (ch as u32).to_be_bytes()).collect()),
+            Encoding::UTF32LE => Ok(src.chars().flat_map(|ch| (ch as u32).to_le_bytes()).collect()),
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::ASCII   => "ASCII".fmt(f),
+            Encoding::Latin1  => "ISO-8859-1".fmt(f),
+            Encoding::UTF8    => "UTF-8".fmt(f),
+            Encoding::UTF16BE => "UTF-16BE".fmt(f),
+            Encoding::UTF16LE => "UTF-16LE".fmt(f),
+            Encoding::UTF32BE => "UTF-32BE".fmt(f),
+            Encoding::UTF32LE => "UTF-32LE".fmt(f),
+            Encoding::Auto    => "auto".fmt(f),
         }
     }
 }
@@ -104,6 +245,8 @@ impl TryFrom<&OsStr> for Encoding {
                   value.eq_ignore_ascii_case("utf32be") ||
                   value.eq_ignore_ascii_case("windows-12001") {
             Ok(Encoding::UTF32BE)
+        } else if value.eq_ignore_ascii_case("auto") {
+            Ok(Encoding::Auto)
         } else {
             Err(IllegalEncoding())
         }
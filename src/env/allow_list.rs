@@ -1,23 +1,33 @@
 use std::{borrow::Cow, collections::HashSet, ffi::OsStr};
 
-use super::{Env, GetEnv};
+use crate::DEBUG_PREFIX;
 
+use super::{eq_ignore_case, Env, GetEnv, KeyPattern};
+
+/// Only writes keys that are in the allow list or match one of the
+/// [patterns](KeyPattern).
 pub struct AllowListEnv<'a, E> {
     env: E,
     allow_list: HashSet<&'a OsStr>,
+    patterns: Vec<KeyPattern>,
+    ignore_case: bool,
+    debug: bool,
 }
 
 impl<'a, E> AllowListEnv<'a, E> {
     #[inline]
     pub fn new(env: E, allow_list: HashSet<&'a OsStr>) -> Self {
-        Self { env, allow_list }
+        Self { env, allow_list, patterns: Vec::new(), ignore_case: false, debug: false }
     }
 
     #[inline]
     pub fn from_slice(env: E, allow_list: &'a [impl AsRef<OsStr>]) -> Self {
         Self {
             env,
-            allow_list: allow_list.iter().map(|key| (*key).as_ref()).collect()
+            allow_list: allow_list.iter().map(|key| (*key).as_ref()).collect(),
+            patterns: Vec::new(),
+            ignore_case: false,
+            debug: false,
         }
     }
 
@@ -25,10 +35,33 @@ impl<'a, E> AllowListEnv<'a, E> {
     pub fn from_iter(env: E, allow_list: impl Iterator<Item=&'a OsStr>) -> Self {
         Self {
             env,
-            allow_list: allow_list.collect()
+            allow_list: allow_list.collect(),
+            patterns: Vec::new(),
+            ignore_case: false,
+            debug: false,
         }
     }
 
+    #[inline]
+    pub fn from_patterns(env: E, patterns: Vec<KeyPattern>) -> Self {
+        Self { env, allow_list: HashSet::new(), patterns, ignore_case: false, debug: false }
+    }
+
+    /// Compare keys case-insensitively, for
+    /// [`KeyCase::Insensitive`](crate::options::KeyCase::Insensitive).
+    #[inline]
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Print a debug message for every key that is dropped.
+    #[inline]
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
     #[inline]
     pub fn env(&self) -> &E {
         &self.env
@@ -39,6 +72,40 @@ impl<'a, E> AllowListEnv<'a, E> {
         &self.allow_list
     }
 
+    #[inline]
+    pub fn patterns(&self) -> &[KeyPattern] {
+        &self.patterns
+    }
+
+    #[inline]
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    #[inline]
+    pub fn debug(&self) -> bool {
+        self.debug
+    }
+
+    pub fn is_allowed(&self, key: &OsStr) -> bool {
+        let allowed = if self.ignore_case {
+            self.allow_list.iter().any(|name| eq_ignore_case(name, key)) ||
+            self.patterns.iter().any(|pattern| pattern.matches_ignore_case(key))
+        } else {
+            self.allow_list.contains(key) || self.patterns.iter().any(|pattern| pattern.matches(key))
+        };
+
+        if allowed {
+            return true;
+        }
+
+        if self.debug {
+            eprintln!("{DEBUG_PREFIX}{key:?} matches no name or pattern of the allow list, ignoring it");
+        }
+
+        false
+    }
+
     #[inline]
     pub fn into_env(self) -> E {
         self.env
@@ -51,6 +118,12 @@ where E: GetEnv {
     fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
         self.env.get(key)
     }
+
+    /// Like `get()` this isn't filtered, the list only applies to writes.
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.env.for_each(f);
+    }
 }
 
 impl<'a, E> Env for AllowListEnv<'a, E>
@@ -62,14 +135,14 @@ where E: Env {
 
     #[inline]
     fn set(&mut self, key: &OsStr, value: &OsStr) {
-        if self.allow_list.contains(key) {
+        if self.is_allowed(key) {
             self.env.set(key, value);
         }
     }
 
     #[inline]
     fn remove(&mut self, key: &OsStr) {
-        if self.allow_list.contains(key) {
+        if self.is_allowed(key) {
             self.env.remove(key);
         }
     }
@@ -0,0 +1,136 @@
+use std::{borrow::Cow, collections::{hash_map::RandomState, HashMap}, ffi::{OsStr, OsString}, hash::BuildHasher};
+
+use super::{key_case::uppercase_key, Env, GetEnv};
+
+/// A `HashMap` based environment with case-insensitive keys, like the
+/// environment on Windows. A variable keeps the spelling of the key it was
+/// first set with.
+///
+/// When built from a map with keys that only differ in case the smallest
+/// spelling in byte order wins (e.g. `PATH` over `Path`), independent of the
+/// iteration order of the map. Use [`case_conflicts()`](super::case_conflicts)
+/// to find such keys beforehand.
+#[derive(Debug, Clone, Default)]
+pub struct CaseInsensitiveMap<BH=RandomState> {
+    map: HashMap<OsString, (OsString, OsString), BH>,
+}
+
+impl CaseInsensitiveMap {
+    #[inline]
+    pub fn new() -> Self {
+        Self { map: HashMap::new() }
+    }
+}
+
+impl<BH: BuildHasher> CaseInsensitiveMap<BH> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The key with the spelling it is stored with.
+    #[inline]
+    pub fn get_key(&self, key: &OsStr) -> Option<&OsStr> {
+        self.map.get(&*uppercase_key(key)).map(|(key, _)| key.as_os_str())
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item=(&OsStr, &OsStr)> {
+        self.map.values().map(|(key, value)| (key.as_os_str(), value.as_os_str()))
+    }
+
+    #[inline]
+    pub fn into_hash_map(self) -> HashMap<OsString, OsString> {
+        self.map.into_values().collect()
+    }
+
+    /// Insert unless there already is a smaller spelling of the same key.
+    fn insert_smallest(&mut self, key: OsString, value: OsString) {
+        let upper = uppercase_key(&key).into_owned();
+        match self.map.get(&upper) {
+            Some((existing, _)) if *existing <= key => {}
+            _ => {
+                self.map.insert(upper, (key, value));
+            }
+        }
+    }
+}
+
+impl<BH: BuildHasher> GetEnv for CaseInsensitiveMap<BH> {
+    #[inline]
+    fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
+        self.map.get(&*uppercase_key(key)).map(|(_, value)| Cow::from(value.as_os_str()))
+    }
+
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        for (key, value) in self.map.values() {
+            f(key, value);
+        }
+    }
+}
+
+impl<BH: BuildHasher> Env for CaseInsensitiveMap<BH> {
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        let upper = uppercase_key(key);
+        if let Some((_, existing)) = self.map.get_mut(&*upper) {
+            *existing = value.to_os_string();
+        } else {
+            self.map.insert(upper.into_owned(), (key.to_os_string(), value.to_os_string()));
+        }
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        self.map.remove(&*uppercase_key(key));
+    }
+
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for CaseInsensitiveMap
+where K: Into<OsString>, V: Into<OsString> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert_smallest(key.into(), value.into());
+        }
+        map
+    }
+}
+
+impl<BH: BuildHasher> From<HashMap<OsString, OsString, BH>> for CaseInsensitiveMap {
+    #[inline]
+    fn from(map: HashMap<OsString, OsString, BH>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<BH: BuildHasher> From<HashMap<String, String, BH>> for CaseInsensitiveMap {
+    #[inline]
+    fn from(map: HashMap<String, String, BH>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<'a> AsMut<dyn Env + 'a> for CaseInsensitiveMap where Self: 'a {
+    #[inline]
+    fn as_mut(&mut self) -> &mut (dyn Env + 'a) {
+        self
+    }
+}
+
+impl<'a> AsRef<dyn GetEnv + 'a> for CaseInsensitiveMap where Self: 'a {
+    #[inline]
+    fn as_ref(&self) -> &(dyn GetEnv + 'a) {
+        self
+    }
+}
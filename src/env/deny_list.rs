@@ -1,23 +1,33 @@
 use std::{borrow::Cow, collections::HashSet, ffi::OsStr};
 
-use super::{Env, GetEnv};
+use crate::DEBUG_PREFIX;
 
+use super::{eq_ignore_case, Env, GetEnv, KeyPattern};
+
+/// Doesn't write keys that are in the deny list or match one of the
+/// [patterns](KeyPattern).
 pub struct DenyListEnv<'a, E> {
     env: E,
     deny_list: HashSet<&'a OsStr>,
+    patterns: Vec<KeyPattern>,
+    ignore_case: bool,
+    debug: bool,
 }
 
 impl<'a, E> DenyListEnv<'a, E> {
     #[inline]
     pub fn new(env: E, deny_list: HashSet<&'a OsStr>) -> Self {
-        Self { env, deny_list }
+        Self { env, deny_list, patterns: Vec::new(), ignore_case: false, debug: false }
     }
 
     #[inline]
     pub fn from_slice(env: E, deny_list: &'a [impl AsRef<OsStr>]) -> Self {
         Self {
             env,
-            deny_list: deny_list.iter().map(|key| (*key).as_ref()).collect()
+            deny_list: deny_list.iter().map(|key| (*key).as_ref()).collect(),
+            patterns: Vec::new(),
+            ignore_case: false,
+            debug: false,
         }
     }
 
@@ -25,10 +35,34 @@ impl<'a, E> DenyListEnv<'a, E> {
     pub fn from_iter(env: E, deny_list: impl Iterator<Item=&'a OsStr>) -> Self {
         Self {
             env,
-            deny_list: deny_list.collect()
+            deny_list: deny_list.collect(),
+            patterns: Vec::new(),
+            ignore_case: false,
+            debug: false,
         }
     }
 
+    #[inline]
+    pub fn from_patterns(env: E, patterns: Vec<KeyPattern>) -> Self {
+        Self { env, deny_list: HashSet::new(), patterns, ignore_case: false, debug: false }
+    }
+
+    /// Compare keys case-insensitively, for
+    /// [`KeyCase::Insensitive`](crate::options::KeyCase::Insensitive).
+    #[inline]
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Print a debug message naming the blocking pattern for every key that
+    /// is dropped.
+    #[inline]
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
     #[inline]
     pub fn env(&self) -> &E {
         &self.env
@@ -39,6 +73,51 @@ impl<'a, E> DenyListEnv<'a, E> {
         &self.deny_list
     }
 
+    #[inline]
+    pub fn patterns(&self) -> &[KeyPattern] {
+        &self.patterns
+    }
+
+    #[inline]
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    #[inline]
+    pub fn debug(&self) -> bool {
+        self.debug
+    }
+
+    pub fn is_denied(&self, key: &OsStr) -> bool {
+        let listed = if self.ignore_case {
+            self.deny_list.iter().any(|name| eq_ignore_case(name, key))
+        } else {
+            self.deny_list.contains(key)
+        };
+
+        if listed {
+            if self.debug {
+                eprintln!("{DEBUG_PREFIX}{key:?} is in the deny list, ignoring it");
+            }
+            return true;
+        }
+
+        let found = self.patterns.iter().find(|pattern| if self.ignore_case {
+            pattern.matches_ignore_case(key)
+        } else {
+            pattern.matches(key)
+        });
+
+        if let Some(pattern) = found {
+            if self.debug {
+                eprintln!("{DEBUG_PREFIX}{key:?} is denied by {pattern}, ignoring it");
+            }
+            return true;
+        }
+
+        false
+    }
+
     #[inline]
     pub fn into_env(self) -> E {
         self.env
@@ -51,6 +130,12 @@ where E: GetEnv {
     fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
         self.env.get(key)
     }
+
+    /// Like `get()` this isn't filtered, the list only applies to writes.
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.env.for_each(f);
+    }
 }
 
 impl<'a, E> Env for DenyListEnv<'a, E>
@@ -62,14 +147,14 @@ where E: Env {
 
     #[inline]
     fn set(&mut self, key: &OsStr, value: &OsStr) {
-        if !self.deny_list.contains(key) {
+        if !self.is_denied(key) {
             self.env.set(key, value);
         }
     }
 
     #[inline]
     fn remove(&mut self, key: &OsStr) {
-        if !self.deny_list.contains(key) {
+        if !self.is_denied(key) {
             self.env.remove(key);
         }
     }
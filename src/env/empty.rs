@@ -25,6 +25,9 @@ impl GetEnv for EmptyEnv {
     fn get<'a>(&'a self, _key: &OsStr) -> Option<Cow<'a, OsStr>> {
         None
     }
+
+    #[inline]
+    fn for_each(&self, _f: &mut dyn FnMut(&OsStr, &OsStr)) {}
 }
 
 impl AsRef<EmptyEnv> for EmptyEnv {
@@ -0,0 +1,255 @@
+use std::{borrow::Cow, cell::RefCell, collections::{BTreeMap, BTreeSet, HashMap}, ffi::{OsStr, OsString}};
+
+use crate::options::KeyCase;
+
+use super::{Env, GetEnv};
+
+/// The key in upper case. Keys that aren't valid Unicode only have their
+/// ASCII letters converted.
+pub fn uppercase_key(key: &OsStr) -> Cow<'_, OsStr> {
+    if let Some(str_key) = key.to_str() {
+        let upper = str_key.to_uppercase();
+        if upper == str_key {
+            return Cow::Borrowed(key);
+        }
+        return Cow::Owned(upper.into());
+    }
+
+    let bytes = key.as_encoded_bytes();
+    if !bytes.iter().any(u8::is_ascii_lowercase) {
+        return Cow::Borrowed(key);
+    }
+
+    // SAFETY: Only ASCII bytes are replaced with other ASCII bytes.
+    Cow::Owned(unsafe { OsString::from_encoded_bytes_unchecked(bytes.to_ascii_uppercase()) })
+}
+
+/// Compare two keys case-insensitively.
+#[inline]
+pub fn eq_ignore_case(a: &OsStr, b: &OsStr) -> bool {
+    a == b || uppercase_key(a) == uppercase_key(b)
+}
+
+/// Groups of keys of `env` that only differ in case, e.g. `["PATH", "Path"]`.
+/// Keys in a group and the groups themselves are sorted in byte order, so the
+/// result doesn't depend on the iteration order of `env`.
+pub fn case_conflicts(env: &(impl GetEnv + ?Sized)) -> Vec<Vec<OsString>> {
+    let mut groups = BTreeMap::<OsString, Vec<OsString>>::new();
+    env.for_each(&mut |key, _| {
+        groups.entry(uppercase_key(key).into_owned()).or_default().push(key.to_owned());
+    });
+
+    let mut conflicts: Vec<_> = groups.into_values().filter(|keys| keys.len() > 1).map(|mut keys| {
+        keys.sort();
+        keys
+    }).collect();
+    conflicts.sort();
+    conflicts
+}
+
+/// Applies a [`KeyCase`] policy to the keys read from and written to `env`.
+///
+/// Every access with a key that collides with a differently spelled key (an
+/// existing variable or a key written before) is recorded, see
+/// [`mismatches()`](Self::mismatches). Reads are recorded too, because a
+/// definition that doesn't override an existing variable only reads it.
+///
+/// For [`KeyCase::Insensitive`] the keys of `env` are indexed by their upper
+/// case spelling on first use. All changes to `env` have to go through this
+/// wrapper afterwards.
+pub struct KeyCaseEnv<E> {
+    env: E,
+    key_case: KeyCase,
+    written: RefCell<HashMap<OsString, OsString>>,
+    mismatches: RefCell<Vec<(OsString, OsString)>>,
+    index: RefCell<Option<HashMap<OsString, BTreeSet<OsString>>>>,
+    conflicts: RefCell<Vec<Vec<OsString>>>,
+}
+
+impl<E> KeyCaseEnv<E> {
+    #[inline]
+    pub fn new(env: E, key_case: KeyCase) -> Self {
+        Self {
+            env,
+            key_case,
+            written: RefCell::new(HashMap::new()),
+            mismatches: RefCell::new(Vec::new()),
+            index: RefCell::new(None),
+            conflicts: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[inline]
+    pub fn env(&self) -> &E {
+        &self.env
+    }
+
+    #[inline]
+    pub fn key_case(&self) -> KeyCase {
+        self.key_case
+    }
+
+    /// Pairs of a used key and the differently spelled key it collided
+    /// with, in the order they happened.
+    #[inline]
+    pub fn mismatches(&self) -> Vec<(OsString, OsString)> {
+        self.mismatches.borrow().clone()
+    }
+
+    /// Groups of existing keys that only differ in case, like
+    /// [`case_conflicts()`], but only those that were accessed (with
+    /// [`KeyCase::Insensitive`]), in the order they were first accessed.
+    #[inline]
+    pub fn conflicts(&self) -> Vec<Vec<OsString>> {
+        self.conflicts.borrow().clone()
+    }
+
+    #[inline]
+    pub fn into_env(self) -> E {
+        self.env
+    }
+}
+
+impl<E> KeyCaseEnv<E>
+where E: GetEnv {
+    /// The key as it is stored in the wrapped environment.
+    fn resolve<'b>(&self, key: &'b OsStr) -> Cow<'b, OsStr> {
+        match self.key_case {
+            KeyCase::Preserve => Cow::Borrowed(key),
+            KeyCase::Uppercase => uppercase_key(key),
+            KeyCase::Insensitive => {
+                let upper = uppercase_key(key);
+                let mut index = self.index.borrow_mut();
+                let index = index.get_or_insert_with(|| {
+                    let mut index = HashMap::<OsString, BTreeSet<OsString>>::new();
+                    self.env.for_each(&mut |other, _| {
+                        index.entry(uppercase_key(other).into_owned()).or_default().insert(other.to_owned());
+                    });
+                    index
+                });
+
+                let Some(keys) = index.get(upper.as_ref() as &OsStr) else {
+                    return Cow::Borrowed(key);
+                };
+
+                if keys.len() > 1 {
+                    let keys: Vec<_> = keys.iter().cloned().collect();
+                    let mut conflicts = self.conflicts.borrow_mut();
+                    if !conflicts.contains(&keys) {
+                        conflicts.push(keys);
+                    }
+                }
+
+                match keys.first() {
+                    Some(found) if found != key => Cow::Owned(found.clone()),
+                    _ => Cow::Borrowed(key),
+                }
+            }
+        }
+    }
+
+    /// Keep the index of [`KeyCase::Insensitive`] up to date.
+    fn update_index(&self, key: &OsStr) {
+        let mut index = self.index.borrow_mut();
+        let Some(index) = index.as_mut() else {
+            return;
+        };
+
+        let upper = uppercase_key(key).into_owned();
+        if self.env.get(key).is_some() {
+            index.entry(upper).or_default().insert(key.to_owned());
+        } else if let Some(keys) = index.get_mut(&upper) {
+            keys.remove(key);
+            if keys.is_empty() {
+                index.remove(&upper);
+            }
+        }
+    }
+
+    fn record(&self, key: &OsStr, resolved: &OsStr, write: bool) {
+        let upper = uppercase_key(key);
+        let mut written = self.written.borrow_mut();
+
+        let other = if self.key_case == KeyCase::Insensitive && resolved != key {
+            Some(resolved.to_owned())
+        } else {
+            written.get(upper.as_ref() as &OsStr).filter(|previous| *previous != key).cloned()
+        };
+
+        if write && !written.contains_key(upper.as_ref() as &OsStr) {
+            written.insert(upper.into_owned(), key.to_owned());
+        }
+
+        if let Some(other) = other {
+            let mut mismatches = self.mismatches.borrow_mut();
+            if !mismatches.iter().any(|(a, b)| a == key && *b == other) {
+                mismatches.push((key.to_owned(), other));
+            }
+        }
+    }
+}
+
+impl<E> GetEnv for KeyCaseEnv<E>
+where E: GetEnv {
+    fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
+        match self.key_case {
+            KeyCase::Preserve => self.env.get(key),
+            KeyCase::Uppercase => {
+                self.record(key, key, false);
+                self.env.get(&uppercase_key(key)).or_else(|| self.env.get(key))
+            }
+            KeyCase::Insensitive => {
+                let resolved = self.resolve(key);
+                let value = self.env.get(&resolved);
+                if value.is_some() {
+                    self.record(key, &resolved, false);
+                }
+                value
+            }
+        }
+    }
+
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.env.for_each(f);
+    }
+}
+
+impl<E> Env for KeyCaseEnv<E>
+where E: Env {
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        if self.key_case == KeyCase::Preserve {
+            return self.env.set(key, value);
+        }
+
+        let resolved = self.resolve(key).into_owned();
+        self.record(key, &resolved, true);
+        self.env.set(&resolved, value);
+        self.update_index(&resolved);
+    }
+
+    fn remove(&mut self, key: &OsStr) {
+        let resolved = self.resolve(key).into_owned();
+        self.env.remove(&resolved);
+        self.update_index(&resolved);
+    }
+}
+
+impl<E> AsMut<KeyCaseEnv<E>> for KeyCaseEnv<E> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut KeyCaseEnv<E> {
+        self
+    }
+}
+
+impl<E> AsRef<KeyCaseEnv<E>> for KeyCaseEnv<E> {
+    #[inline]
+    fn as_ref(&self) -> &KeyCaseEnv<E> {
+        self
+    }
+}
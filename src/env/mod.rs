@@ -2,15 +2,29 @@ pub mod system;
 pub mod empty;
 pub mod allow_list;
 pub mod deny_list;
+pub mod overlay;
+pub mod prefix;
+pub mod strip_prefix;
+pub mod rename;
+pub mod pattern;
+pub mod key_case;
+pub mod case_insensitive;
 
-use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, hash::BuildHasher};
+use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, hash::BuildHasher, time::Duration};
 
-use crate::{options::{DEFAULT_PATH, IllegalOption, OptionType}, Dialect, Encoding, Error, ErrorKind, Result};
+use crate::{decode::Decoders, file_secrets::FileSecrets, options::{ComposeGoOptions, DialectOptions, IllegalOption, JavaScriptDotenvOptions, OptionType, PythonDotenvOptions, RubyDotenvOptions, DEFAULT_COMPOSEGO_COMPAT_ERRORS, DEFAULT_COMPOSEGO_STRICT_SUBSTITUTION, DEFAULT_DEFERRED_SUBSTITUTION, DEFAULT_JAVASCRIPT_MULTILINE, DEFAULT_LIST_SEPARATOR, DEFAULT_PATH, DEFAULT_PYTHON_INTERPOLATE, DEFAULT_RUBY_COMMAND_TIMEOUT, DEFAULT_RUBY_LEGACY_LINEBREAKS, KeyCase, KeyValidation, UndefinedVars}, Dialect, Encoding, Error, ErrorKind, Result};
 
 pub use system::{SystemEnv, SYSTEM_ENV};
 pub use empty::EmptyEnv;
 pub use allow_list::AllowListEnv;
 pub use deny_list::DenyListEnv;
+pub use overlay::OverlayEnv;
+pub use prefix::PrefixEnv;
+pub use strip_prefix::StripPrefixEnv;
+pub use rename::RenameEnv;
+pub use pattern::KeyPattern;
+pub use key_case::{case_conflicts, eq_ignore_case, uppercase_key, KeyCaseEnv};
+pub use case_insensitive::CaseInsensitiveMap;
 
 pub fn parse_bool(value: &OsStr) -> Option<bool> {
     if value.eq_ignore_ascii_case("true") || value == "1" {
@@ -22,9 +36,30 @@ pub fn parse_bool(value: &OsStr) -> Option<bool> {
     }
 }
 
+/// Parse a comma separated list. Surrounding white space of the items is
+/// ignored and an empty string is an empty list.
+pub fn parse_comma_list(value: &OsStr) -> Option<Vec<OsString>> {
+    let value = value.to_str()?.trim();
+    if value.is_empty() {
+        return Some(vec![]);
+    }
+
+    Some(value.split(',').map(|word| OsString::from(word.trim())).collect())
+}
+
+/// Parse a non-negative number of seconds, fractions are allowed.
+pub fn parse_seconds(value: &OsStr) -> Option<Duration> {
+    let value: f64 = value.to_str()?.trim().parse().ok()?;
+    Duration::try_from_secs_f64(value).ok()
+}
+
 pub trait GetEnv {
     fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>>;
 
+    /// Call `f` for every variable, i.e. every key for which `get()` returns
+    /// a value, in no particular order.
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr));
+
     #[inline]
     fn get_config_path(&self) -> Cow<'_, OsStr> {
         self.get("DOTENV_CONFIG_PATH".as_ref())
@@ -34,6 +69,14 @@ pub trait GetEnv {
             })
     }
 
+    /// An empty value is an empty separator, not the default.
+    #[inline]
+    fn get_list_separator(&self) -> OsString {
+        self.get("DOTENV_CONFIG_LIST_SEPARATOR".as_ref())
+            .map(Cow::into_owned)
+            .unwrap_or_else(|| DEFAULT_LIST_SEPARATOR.into())
+    }
+
     #[inline]
     fn get_override_env(&self) -> Result<bool> {
         self.get_bool("DOTENV_CONFIG_OVERRIDE".as_ref(), false)
@@ -49,6 +92,117 @@ pub trait GetEnv {
         self.get_bool("DOTENV_CONFIG_DEBUG".as_ref(), false)
     }
 
+    #[inline]
+    fn get_deferred_substitution(&self) -> Result<bool> {
+        self.get_bool("DOTENV_CONFIG_DEFERRED_SUBSTITUTION".as_ref(), DEFAULT_DEFERRED_SUBSTITUTION)
+    }
+
+    fn get_file_secrets(&self) -> Result<FileSecrets> {
+        let key = OsStr::new("DOTENV_CONFIG_FILE_SECRETS");
+        let Some(value) = self.get(key) else {
+            return Ok(FileSecrets::default());
+        };
+
+        if value.is_empty() {
+            return Ok(FileSecrets::default());
+        }
+
+        let Ok(file_secrets) = FileSecrets::try_from(value.as_ref()) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    key.to_owned(),
+                    value.into(),
+                    OptionType::FileSecrets)));
+        };
+
+        Ok(file_secrets)
+    }
+
+    fn get_decoders(&self) -> Result<Decoders> {
+        let key = OsStr::new("DOTENV_CONFIG_DECODERS");
+        let Some(value) = self.get(key) else {
+            return Ok(Decoders::default());
+        };
+
+        let Ok(decoders) = Decoders::try_from(value.as_ref()) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    key.to_owned(),
+                    value.into(),
+                    OptionType::Decoders)));
+        };
+
+        Ok(decoders)
+    }
+
+    fn get_undefined_vars(&self) -> Result<UndefinedVars> {
+        let key = OsStr::new("DOTENV_CONFIG_UNDEFINED_VARS");
+        let Some(value) = self.get(key) else {
+            return Ok(UndefinedVars::default());
+        };
+
+        if value.is_empty() {
+            return Ok(UndefinedVars::default());
+        }
+
+        let Ok(undefined_vars) = UndefinedVars::try_from(value.as_ref()) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    key.to_owned(),
+                    value.into(),
+                    OptionType::UndefinedVars)));
+        };
+
+        Ok(undefined_vars)
+    }
+
+    fn get_key_case(&self) -> Result<KeyCase> {
+        let key = OsStr::new("DOTENV_CONFIG_KEY_CASE");
+        let Some(value) = self.get(key) else {
+            return Ok(KeyCase::default());
+        };
+
+        if value.is_empty() {
+            return Ok(KeyCase::default());
+        }
+
+        let Ok(key_case) = KeyCase::try_from(value.as_ref()) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    key.to_owned(),
+                    value.into(),
+                    OptionType::KeyCase)));
+        };
+
+        Ok(key_case)
+    }
+
+    fn get_key_validation(&self) -> Result<KeyValidation> {
+        let key = OsStr::new("DOTENV_CONFIG_KEY_VALIDATION");
+        let Some(value) = self.get(key) else {
+            return Ok(KeyValidation::default());
+        };
+
+        if value.is_empty() {
+            return Ok(KeyValidation::default());
+        }
+
+        let Ok(key_validation) = KeyValidation::try_from(value.as_ref()) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    key.to_owned(),
+                    value.into(),
+                    OptionType::KeyValidation)));
+        };
+
+        Ok(key_validation)
+    }
+
     fn get_encoding(&self) -> Result<Encoding> {
         let encoding_key = OsStr::new("DOTENV_CONFIG_ENCODING");
         let encoding = self.get(encoding_key);
@@ -89,6 +243,65 @@ pub trait GetEnv {
         Ok(dialect)
     }
 
+    fn get_dialect_options(&self) -> Result<DialectOptions> {
+        Ok(DialectOptions {
+            ruby: RubyDotenvOptions {
+                legacy_linebreaks: self.get_bool("DOTENV_CONFIG_RUBY_LEGACY_LINEBREAKS".as_ref(), DEFAULT_RUBY_LEGACY_LINEBREAKS)?,
+                allow_commands: self.get_comma_list("DOTENV_CONFIG_RUBY_ALLOW_COMMANDS".as_ref())?,
+                command_timeout: self.get_seconds("DOTENV_CONFIG_RUBY_COMMAND_TIMEOUT".as_ref(), DEFAULT_RUBY_COMMAND_TIMEOUT)?,
+                command_path: self.get("PATH".as_ref()).map(Cow::into_owned).or_else(|| std::env::var_os("PATH")),
+            },
+            composego: ComposeGoOptions {
+                strict_substitution: self.get_bool("DOTENV_CONFIG_COMPOSEGO_STRICT_SUBSTITUTION".as_ref(), DEFAULT_COMPOSEGO_STRICT_SUBSTITUTION)?,
+                compat_errors: self.get_bool("DOTENV_CONFIG_COMPOSEGO_COMPAT_ERRORS".as_ref(), DEFAULT_COMPOSEGO_COMPAT_ERRORS)?,
+            },
+            python: PythonDotenvOptions {
+                interpolate: self.get_bool("DOTENV_CONFIG_PYTHON_INTERPOLATE".as_ref(), DEFAULT_PYTHON_INTERPOLATE)?,
+            },
+            javascript: JavaScriptDotenvOptions {
+                multiline: self.get_bool("DOTENV_CONFIG_JAVASCRIPT_MULTILINE".as_ref(), DEFAULT_JAVASCRIPT_MULTILINE)?,
+            },
+        })
+    }
+
+    fn get_comma_list(&self, key: &OsStr) -> Result<Vec<OsString>> {
+        let Some(value) = self.get(key) else {
+            return Ok(Vec::new());
+        };
+
+        let Some(value) = parse_comma_list(&value) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    key.to_owned(),
+                    value.into(),
+                    OptionType::CommaList)));
+        };
+
+        Ok(value)
+    }
+
+    fn get_seconds(&self, key: &OsStr, default_value: Duration) -> Result<Duration> {
+        let Some(value) = self.get(key) else {
+            return Ok(default_value);
+        };
+
+        if value.is_empty() {
+            return Ok(default_value);
+        }
+
+        let Some(value) = parse_seconds(&value) else {
+            return Err(Error::with_cause(
+                ErrorKind::OptionsParseError,
+                IllegalOption::new(
+                    key.to_owned(),
+                    value.into(),
+                    OptionType::Seconds)));
+        };
+
+        Ok(value)
+    }
+
     fn get_bool(&self, key: &OsStr, default_value: bool) -> Result<bool> {
         if let Some(value) = self.get(key) {
             let value: &OsStr = &value;
@@ -147,21 +360,31 @@ impl<'a> AsRef<dyn GetEnv + 'a> for HashMap<String, String> where Self: 'a {
 }
 
 // XXX: Why do I need these three?
-impl<T: GetEnv> GetEnv for &T {
+impl<T: GetEnv + ?Sized> GetEnv for &T {
     #[inline]
     fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
         (**self).get(key)
     }
+
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        (**self).for_each(f);
+    }
 }
 
-impl<T: GetEnv> GetEnv for &mut T {
+impl<T: GetEnv + ?Sized> GetEnv for &mut T {
     #[inline]
     fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
         (**self).get(key)
     }
+
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        (**self).for_each(f);
+    }
 }
 
-impl<T: Env> Env for &mut T where Self: GetEnv {
+impl<T: Env + ?Sized> Env for &mut T where Self: GetEnv {
     #[inline]
     fn as_get_env(&self) -> &dyn GetEnv {
         (**self).as_get_env()
@@ -183,6 +406,13 @@ impl<BH: BuildHasher> GetEnv for HashMap<OsString, OsString, BH> {
     fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
         HashMap::get(self, key).map(Cow::from)
     }
+
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
 }
 
 impl<BH: BuildHasher> Env for HashMap<OsString, OsString, BH> {
@@ -210,6 +440,13 @@ impl<BH: BuildHasher> GetEnv for HashMap<String, String, BH> {
             Cow::from(value)
         })
     }
+
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        for (key, value) in self {
+            f(key.as_ref(), value.as_ref());
+        }
+    }
 }
 
 impl<BH: BuildHasher> Env for HashMap<String, String, BH> {
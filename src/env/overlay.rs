@@ -0,0 +1,148 @@
+use std::{borrow::Cow, collections::{HashMap, HashSet}, ffi::{OsStr, OsString}};
+
+use super::{Env, GetEnv};
+
+/// An environment layered over another one. Reads fall through from the top
+/// layer to the base, but writes only go to the top layer and removals are
+/// recorded as tombstones. The base is only changed by [`commit()`](Self::commit).
+pub struct OverlayEnv<Base, Top=HashMap<OsString, OsString>> {
+    base: Base,
+    top: Top,
+    removed: HashSet<OsString>,
+}
+
+impl<Base> OverlayEnv<Base> {
+    #[inline]
+    pub fn new(base: Base) -> Self {
+        Self::with_top(base, HashMap::new())
+    }
+}
+
+impl<Base, Top> OverlayEnv<Base, Top> {
+    #[inline]
+    pub fn with_top(base: Base, top: Top) -> Self {
+        Self { base, top, removed: HashSet::new() }
+    }
+
+    #[inline]
+    pub fn base(&self) -> &Base {
+        &self.base
+    }
+
+    #[inline]
+    pub fn top(&self) -> &Top {
+        &self.top
+    }
+
+    /// Keys removed in the top layer.
+    #[inline]
+    pub fn removed(&self) -> &HashSet<OsString> {
+        &self.removed
+    }
+
+    #[inline]
+    pub fn into_parts(self) -> (Base, Top) {
+        (self.base, self.top)
+    }
+}
+
+impl<Base, Top> OverlayEnv<Base, Top>
+where Base: GetEnv, Top: GetEnv {
+    /// The changes relative to the base, sorted by key. `None` means the
+    /// variable is removed. Writes of the value the base already has and
+    /// removals of variables the base doesn't have are no changes.
+    pub fn diff(&self) -> Vec<(OsString, Option<OsString>)> {
+        let mut changes = Vec::new();
+
+        self.top.for_each(&mut |key, value| {
+            if self.base.get(key).as_deref() != Some(value) {
+                changes.push((key.to_owned(), Some(value.to_owned())));
+            }
+        });
+
+        for key in &self.removed {
+            if self.base.get(key).is_some() {
+                changes.push((key.clone(), None));
+            }
+        }
+
+        changes.sort();
+        changes
+    }
+}
+
+impl<Base, Top> OverlayEnv<Base, Top>
+where Base: Env, Top: Env {
+    /// Apply the changes to the base and clear the top layer.
+    pub fn commit(&mut self) {
+        for key in self.removed.drain() {
+            self.base.remove(&key);
+        }
+
+        let mut vars = Vec::new();
+        self.top.for_each(&mut |key, value| vars.push((key.to_owned(), value.to_owned())));
+
+        for (key, value) in vars {
+            self.base.set(&key, &value);
+            self.top.remove(&key);
+        }
+    }
+}
+
+impl<Base, Top> GetEnv for OverlayEnv<Base, Top>
+where Base: GetEnv, Top: GetEnv {
+    fn get<'a>(&'a self, key: &OsStr) -> Option<Cow<'a, OsStr>> {
+        if let Some(value) = self.top.get(key) {
+            return Some(value);
+        }
+
+        if self.removed.contains(key) {
+            return None;
+        }
+
+        self.base.get(key)
+    }
+
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.top.for_each(f);
+        self.base.for_each(&mut |key, value| {
+            if !self.removed.contains(key) && self.top.get(key).is_none() {
+                f(key, value);
+            }
+        });
+    }
+}
+
+impl<Base, Top> Env for OverlayEnv<Base, Top>
+where Base: GetEnv, Top: Env {
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        self.removed.remove(key);
+        self.top.set(key, value);
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        self.top.remove(key);
+        self.removed.insert(key.to_owned());
+    }
+}
+
+impl<Base, Top> AsMut<OverlayEnv<Base, Top>> for OverlayEnv<Base, Top> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut OverlayEnv<Base, Top> {
+        self
+    }
+}
+
+impl<Base, Top> AsRef<OverlayEnv<Base, Top>> for OverlayEnv<Base, Top> {
+    #[inline]
+    fn as_ref(&self) -> &OverlayEnv<Base, Top> {
+        self
+    }
+}
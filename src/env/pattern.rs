@@ -0,0 +1,145 @@
+use std::ffi::{OsStr, OsString};
+
+use crate::{glob::Glob, Error, ErrorKind, Result};
+
+use super::eq_ignore_case;
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Literal(OsString),
+    Glob(Glob),
+    #[cfg(feature = "regex")]
+    Regex { regex: regex::Regex, regex_ignore_case: regex::Regex },
+}
+
+/// A key pattern of [`AllowListEnv`](super::AllowListEnv) and
+/// [`DenyListEnv`](super::DenyListEnv).
+///
+/// [`KeyPattern::parse()`] understands these forms:
+///
+/// | Form            | Description |
+/// | --------------- | ----------- |
+/// | `literal:NAME`  | Exactly `NAME`, even if it contains `*`, `?`, or `[`. |
+/// | `glob:PATTERN`  | A shell glob that has to match the whole key. |
+/// | `regex:PATTERN` | A regular expression that has to match the whole key. Requires the `regex` feature. |
+/// | `PATTERN`       | A glob if it contains `*`, `?`, or `[`, a literal name otherwise. |
+#[derive(Debug, Clone)]
+pub struct KeyPattern {
+    source: OsString,
+    matcher: Matcher,
+}
+
+impl KeyPattern {
+    #[inline]
+    pub fn literal(name: impl Into<OsString>) -> Self {
+        let name = name.into();
+        Self { source: name.clone(), matcher: Matcher::Literal(name) }
+    }
+
+    #[inline]
+    pub fn glob(pattern: &str) -> Self {
+        Self { source: pattern.into(), matcher: Matcher::Glob(Glob::new(pattern)) }
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn regex(pattern: &str) -> Result<Self> {
+        let anchored = format!("^(?:{pattern})$");
+        let build = |ignore_case| regex::RegexBuilder::new(&anchored).case_insensitive(ignore_case).build().map_err(|err| {
+            Error::with_cause(ErrorKind::OptionsParseError, format!("illegal regular expression {pattern:?}: {err}"))
+        });
+        let regex = build(false)?;
+        let regex_ignore_case = build(true)?;
+        Ok(Self { source: pattern.into(), matcher: Matcher::Regex { regex, regex_ignore_case } })
+    }
+
+    pub fn parse(pattern: &OsStr) -> Result<Self> {
+        let Some(str_pattern) = pattern.to_str() else {
+            return Ok(Self::literal(pattern));
+        };
+
+        if let Some(name) = str_pattern.strip_prefix("literal:") {
+            Ok(Self::literal(name))
+        } else if let Some(glob) = str_pattern.strip_prefix("glob:") {
+            Ok(Self::glob(glob))
+        } else if let Some(_regex) = str_pattern.strip_prefix("regex:") {
+            #[cfg(feature = "regex")]
+            return Self::regex(_regex);
+
+            #[cfg(not(feature = "regex"))]
+            return Err(Error::with_cause(ErrorKind::OptionsParseError,
+                format!("regular expressions are not supported, punktum was built without the regex feature: {str_pattern:?}")));
+        } else if str_pattern.contains(['*', '?', '[']) {
+            Ok(Self::glob(str_pattern))
+        } else {
+            Ok(Self::literal(pattern))
+        }
+    }
+
+    /// Parse every pattern, see [`KeyPattern::parse()`].
+    pub fn parse_all(patterns: impl IntoIterator<Item=impl AsRef<OsStr>>) -> Result<Vec<Self>> {
+        patterns.into_iter().map(|pattern| Self::parse(pattern.as_ref())).collect()
+    }
+
+    /// The pattern without its `literal:`, `glob:`, or `regex:` prefix.
+    #[inline]
+    pub fn source(&self) -> &OsStr {
+        &self.source
+    }
+
+    #[inline]
+    pub fn is_literal(&self) -> bool {
+        matches!(self.matcher, Matcher::Literal(_))
+    }
+
+    pub fn matches(&self, key: &OsStr) -> bool {
+        match &self.matcher {
+            Matcher::Literal(name) => name == key,
+            Matcher::Glob(glob) => {
+                let Some(key) = key.to_str() else {
+                    return false;
+                };
+                let chars: Vec<char> = key.chars().collect();
+                glob.matches(&chars)
+            }
+            #[cfg(feature = "regex")]
+            Matcher::Regex { regex, .. } => key.to_str().is_some_and(|key| regex.is_match(key)),
+        }
+    }
+
+    /// Like [`matches()`](Self::matches), but ignoring case, for
+    /// [`KeyCase::Insensitive`](crate::options::KeyCase::Insensitive).
+    pub fn matches_ignore_case(&self, key: &OsStr) -> bool {
+        match &self.matcher {
+            Matcher::Literal(name) => eq_ignore_case(name, key),
+            Matcher::Glob(glob) => {
+                let Some(key) = key.to_str() else {
+                    return false;
+                };
+                let chars: Vec<char> = key.chars().collect();
+                glob.matches_ignore_case(&chars)
+            }
+            #[cfg(feature = "regex")]
+            Matcher::Regex { regex_ignore_case, .. } => key.to_str().is_some_and(|key| regex_ignore_case.is_match(key)),
+        }
+    }
+}
+
+impl std::fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = self.source.to_string_lossy();
+        match &self.matcher {
+            Matcher::Literal(_) => write!(f, "name {source:?}"),
+            Matcher::Glob(_) => write!(f, "glob {source:?}"),
+            #[cfg(feature = "regex")]
+            Matcher::Regex { .. } => write!(f, "regex {source:?}"),
+        }
+    }
+}
+
+impl From<&OsStr> for KeyPattern {
+    /// A literal name.
+    #[inline]
+    fn from(name: &OsStr) -> Self {
+        Self::literal(name)
+    }
+}
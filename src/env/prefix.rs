@@ -0,0 +1,96 @@
+use std::{borrow::Cow, ffi::{OsStr, OsString}};
+
+use super::{Env, GetEnv};
+
+#[inline]
+pub(crate) fn concat(prefix: &OsStr, key: &OsStr) -> OsString {
+    let mut prefixed = OsString::with_capacity(prefix.len() + key.len());
+    prefixed.push(prefix);
+    prefixed.push(key);
+    prefixed
+}
+
+#[inline]
+pub(crate) fn strip_prefix<'a>(key: &'a OsStr, prefix: &OsStr) -> Option<&'a OsStr> {
+    key.as_encoded_bytes().strip_prefix(prefix.as_encoded_bytes()).map(|key| {
+        // SAFETY: Only strips a valid OsStr from the start of a valid OsStr.
+        unsafe { OsStr::from_encoded_bytes_unchecked(key) }
+    })
+}
+
+/// Adds a prefix to every key, i.e. `FOO` is written to and read from
+/// `APP_FOO`. Variables without the prefix aren't visible through it.
+pub struct PrefixEnv<'a, E> {
+    env: E,
+    prefix: &'a OsStr,
+}
+
+impl<'a, E> PrefixEnv<'a, E> {
+    #[inline]
+    pub fn new(env: E, prefix: &'a OsStr) -> Self {
+        Self { env, prefix }
+    }
+
+    #[inline]
+    pub fn env(&self) -> &E {
+        &self.env
+    }
+
+    #[inline]
+    pub fn prefix(&self) -> &'a OsStr {
+        self.prefix
+    }
+
+    #[inline]
+    pub fn into_env(self) -> E {
+        self.env
+    }
+}
+
+impl<'a, E> GetEnv for PrefixEnv<'a, E>
+where E: GetEnv {
+    #[inline]
+    fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
+        self.env.get(&concat(self.prefix, key))
+    }
+
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.env.for_each(&mut |key, value| {
+            if let Some(key) = strip_prefix(key, self.prefix) {
+                f(key, value);
+            }
+        });
+    }
+}
+
+impl<'a, E> Env for PrefixEnv<'a, E>
+where E: Env {
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        self.env.set(&concat(self.prefix, key), value);
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        self.env.remove(&concat(self.prefix, key));
+    }
+}
+
+impl<'a, E> AsMut<PrefixEnv<'a, E>> for PrefixEnv<'a, E> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut PrefixEnv<'a, E> {
+        self
+    }
+}
+
+impl<'a, E> AsRef<PrefixEnv<'a, E>> for PrefixEnv<'a, E> {
+    #[inline]
+    fn as_ref(&self) -> &PrefixEnv<'a, E> {
+        self
+    }
+}
@@ -0,0 +1,113 @@
+use std::{borrow::Cow, collections::HashMap, ffi::OsStr};
+
+use super::{Env, GetEnv};
+
+/// Renames keys by a mapping from the name used in the `.env` file to the
+/// name in the wrapped environment, i.e. with `DB_HOST=APP_DATABASE_HOST`
+/// `DB_HOST` is written to and read from `APP_DATABASE_HOST`. Other keys are
+/// passed through as they are.
+pub struct RenameEnv<'a, E> {
+    env: E,
+    renames: HashMap<&'a OsStr, &'a OsStr>,
+}
+
+impl<'a, E> RenameEnv<'a, E> {
+    #[inline]
+    pub fn new(env: E, renames: HashMap<&'a OsStr, &'a OsStr>) -> Self {
+        Self { env, renames }
+    }
+
+    #[inline]
+    pub fn from_slice(env: E, renames: &'a [(impl AsRef<OsStr>, impl AsRef<OsStr>)]) -> Self {
+        Self {
+            env,
+            renames: renames.iter().map(|(from, to)| (from.as_ref(), to.as_ref())).collect()
+        }
+    }
+
+    #[inline]
+    pub fn from_iter(env: E, renames: impl Iterator<Item=(&'a OsStr, &'a OsStr)>) -> Self {
+        Self {
+            env,
+            renames: renames.collect()
+        }
+    }
+
+    #[inline]
+    pub fn env(&self) -> &E {
+        &self.env
+    }
+
+    #[inline]
+    pub fn renames(&self) -> &HashMap<&'a OsStr, &'a OsStr> {
+        &self.renames
+    }
+
+    #[inline]
+    pub fn into_env(self) -> E {
+        self.env
+    }
+
+    #[inline]
+    fn rename<'b>(&self, key: &'b OsStr) -> &'b OsStr where 'a: 'b {
+        self.renames.get(key).copied().unwrap_or(key)
+    }
+}
+
+impl<'a, E> GetEnv for RenameEnv<'a, E>
+where E: GetEnv {
+    #[inline]
+    fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
+        self.env.get(self.rename(key))
+    }
+
+    /// Renamed variables are visited by the name used in the `.env` file,
+    /// and by their own name unless that is renamed too.
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.env.for_each(&mut |key, value| {
+            if !self.renames.contains_key(key) {
+                f(key, value);
+            }
+        });
+
+        for (from, to) in &self.renames {
+            if let Some(value) = self.env.get(to) {
+                f(from, &value);
+            }
+        }
+    }
+}
+
+impl<'a, E> Env for RenameEnv<'a, E>
+where E: Env {
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        let key = self.rename(key);
+        self.env.set(key, value);
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        let key = self.rename(key);
+        self.env.remove(key);
+    }
+}
+
+impl<'a, E> AsMut<RenameEnv<'a, E>> for RenameEnv<'a, E> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut RenameEnv<'a, E> {
+        self
+    }
+}
+
+impl<'a, E> AsRef<RenameEnv<'a, E>> for RenameEnv<'a, E> {
+    #[inline]
+    fn as_ref(&self) -> &RenameEnv<'a, E> {
+        self
+    }
+}
@@ -0,0 +1,88 @@
+use std::{borrow::Cow, ffi::OsStr};
+
+use super::{prefix::{concat, strip_prefix}, Env, GetEnv};
+
+/// Only accepts keys with a prefix and strips it, i.e. `APP_FOO` is written
+/// to and read from `FOO` and writes of keys without the prefix are ignored.
+/// Like with [`AllowListEnv`](super::AllowListEnv) reads of other keys aren't
+/// filtered.
+pub struct StripPrefixEnv<'a, E> {
+    env: E,
+    prefix: &'a OsStr,
+}
+
+impl<'a, E> StripPrefixEnv<'a, E> {
+    #[inline]
+    pub fn new(env: E, prefix: &'a OsStr) -> Self {
+        Self { env, prefix }
+    }
+
+    #[inline]
+    pub fn env(&self) -> &E {
+        &self.env
+    }
+
+    #[inline]
+    pub fn prefix(&self) -> &'a OsStr {
+        self.prefix
+    }
+
+    #[inline]
+    pub fn into_env(self) -> E {
+        self.env
+    }
+}
+
+impl<'a, E> GetEnv for StripPrefixEnv<'a, E>
+where E: GetEnv {
+    #[inline]
+    fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
+        self.env.get(strip_prefix(key, self.prefix).unwrap_or(key))
+    }
+
+    /// Visits every variable with and without the prefix.
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.env.for_each(&mut |key, value| {
+            if strip_prefix(key, self.prefix).is_none() {
+                f(key, value);
+            }
+            f(&concat(self.prefix, key), value);
+        });
+    }
+}
+
+impl<'a, E> Env for StripPrefixEnv<'a, E>
+where E: Env {
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        if let Some(key) = strip_prefix(key, self.prefix) {
+            self.env.set(key, value);
+        }
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        if let Some(key) = strip_prefix(key, self.prefix) {
+            self.env.remove(key);
+        }
+    }
+}
+
+impl<'a, E> AsMut<StripPrefixEnv<'a, E>> for StripPrefixEnv<'a, E> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut StripPrefixEnv<'a, E> {
+        self
+    }
+}
+
+impl<'a, E> AsRef<StripPrefixEnv<'a, E>> for StripPrefixEnv<'a, E> {
+    #[inline]
+    fn as_ref(&self) -> &StripPrefixEnv<'a, E> {
+        self
+    }
+}
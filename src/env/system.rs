@@ -85,6 +85,20 @@ impl GetEnv for SystemEnv {
 
         std::env::var_os(key).map(Cow::from)
     }
+
+    /// Works on a snapshot, so `f` may access the environment itself.
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        let vars: Vec<_> = {
+            #[cfg(not(target_family = "windows"))]
+            let _lock = MUTEX.lock();
+
+            std::env::vars_os().collect()
+        };
+
+        for (key, value) in vars {
+            f(&key, &value);
+        }
+    }
 }
 
 impl Env for SystemEnv {
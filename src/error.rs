@@ -1,9 +1,12 @@
+use std::path::{Path, PathBuf};
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ErrorKind {
     OptionsParseError,
     IOError,
     SyntaxError,
     SubstitutionError,
+    DecodeError,
     ExecError,
     IllegalArgument,
     NotEnoughArguments,
@@ -44,6 +47,7 @@ pub struct Error {
     kind: ErrorKind,
     cause: Option<Box<dyn std::error::Error>>,
     location: Option<SourceLocation>,
+    path: Option<PathBuf>,
 }
 
 impl Error {
@@ -54,6 +58,7 @@ impl Error {
             cause: Some(cause.into()),
             kind,
             location: Some(location),
+            path: None,
         }
     }
 
@@ -64,6 +69,7 @@ impl Error {
             cause: Some(cause.into()),
             kind,
             location: None,
+            path: None,
         }
     }
 
@@ -73,6 +79,7 @@ impl Error {
             cause: None,
             kind,
             location: Some(location),
+            path: None,
         }
     }
 
@@ -82,6 +89,7 @@ impl Error {
             cause: None,
             kind: ErrorKind::SyntaxError,
             location: Some(SourceLocation::new(lineno, column)),
+            path: None,
         }
     }
 
@@ -91,6 +99,7 @@ impl Error {
             cause: None,
             kind: ErrorKind::SubstitutionError,
             location: Some(SourceLocation::new(lineno, column)),
+            path: None,
         }
     }
 
@@ -103,12 +112,25 @@ impl Error {
     pub fn location(&self) -> &Option<SourceLocation> {
         &self.location
     }
+
+    /// The file the error occurred in, if known. Only set by functions that
+    /// load several files, like [`config_transaction()`](crate::config_transaction).
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    #[inline]
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
 }
 
 impl From<ErrorKind> for Error {
     #[inline]
     fn from(kind: ErrorKind) -> Self {
-        Self { kind, cause: None, location: None }
+        Self { kind, cause: None, location: None, path: None }
     }
 }
 
@@ -117,6 +139,10 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(&self.kind, f)?;
 
+        if let Some(path) = &self.path {
+            write!(f, " in {}", path.to_string_lossy())?;
+        }
+
         if let Some(location) = self.location {
             write!(f, " on line {} at column {}", location.lineno, location.column)?;
         }
@@ -0,0 +1,196 @@
+// The `_FILE` convention of many container images: instead of the secret
+// itself `DB_PASSWORD_FILE=/run/secrets/db_password` names a file containing
+// it, and `DB_PASSWORD` is set to the content of that file.
+use std::{borrow::Cow, ffi::{OsStr, OsString}, path::Path};
+
+use crate::{env::GetEnv, options::{IllegalOption, OptionType}, Env, Error, ErrorKind, Options, Result, DEBUG_PREFIX};
+
+/// Whether and how to load `KEY` from the file named by `KEY_FILE`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum FileSecrets {
+    #[default]
+    Disabled,
+    /// Use the content of the file as it is.
+    Raw,
+    /// Remove a single trailing newline (`\n` or `\r\n`), like the shell's
+    /// `$(cat file)` would.
+    TrimNewline,
+    /// Remove white space from both ends.
+    Trim,
+}
+
+impl FileSecrets {
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        *self != FileSecrets::Disabled
+    }
+
+    pub fn trim<'a>(&self, value: &'a str) -> &'a str {
+        match self {
+            FileSecrets::Disabled | FileSecrets::Raw => value,
+            FileSecrets::TrimNewline => {
+                let value = value.strip_suffix('\n').unwrap_or(value);
+                value.strip_suffix('\r').unwrap_or(value)
+            }
+            FileSecrets::Trim => value.trim(),
+        }
+    }
+}
+
+impl std::fmt::Display for FileSecrets {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileSecrets::Disabled    => "false".fmt(f),
+            FileSecrets::Raw         => "raw".fmt(f),
+            FileSecrets::TrimNewline => "trim-newline".fmt(f),
+            FileSecrets::Trim        => "trim".fmt(f),
+        }
+    }
+}
+
+impl TryFrom<&OsStr> for FileSecrets {
+    type Error = IllegalOption;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, Self::Error> {
+        if value.eq_ignore_ascii_case("false") || value == "0" {
+            Ok(FileSecrets::Disabled)
+        } else if value.eq_ignore_ascii_case("raw") {
+            Ok(FileSecrets::Raw)
+        } else if value.eq_ignore_ascii_case("trim-newline") {
+            Ok(FileSecrets::TrimNewline)
+        } else if value.eq_ignore_ascii_case("trim") || value.eq_ignore_ascii_case("true") || value == "1" {
+            Ok(FileSecrets::Trim)
+        } else {
+            Err(IllegalOption::new("file_secrets".into(), value.into(), OptionType::FileSecrets))
+        }
+    }
+}
+
+/// Remembers the keys that are set while parsing a file.
+pub(crate) struct DefinedKeys<'a> {
+    env: &'a mut dyn Env,
+    keys: Vec<OsString>,
+}
+
+impl<'a> DefinedKeys<'a> {
+    #[inline]
+    pub(crate) fn new(env: &'a mut dyn Env) -> Self {
+        Self { env, keys: Vec::new() }
+    }
+
+    #[inline]
+    pub(crate) fn into_keys(self) -> Vec<OsString> {
+        self.keys
+    }
+}
+
+impl<'a> GetEnv for DefinedKeys<'a> {
+    #[inline]
+    fn get<'b>(&'b self, key: &OsStr) -> Option<Cow<'b, OsStr>> {
+        self.env.get(key)
+    }
+
+    #[inline]
+    fn for_each(&self, f: &mut dyn FnMut(&OsStr, &OsStr)) {
+        self.env.for_each(f);
+    }
+}
+
+impl<'a> Env for DefinedKeys<'a> {
+    #[inline]
+    fn set(&mut self, key: &OsStr, value: &OsStr) {
+        if !self.keys.iter().any(|other| other == key) {
+            self.keys.push(key.to_owned());
+        }
+        self.env.set(key, value);
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &OsStr) {
+        self.env.remove(key);
+    }
+
+    #[inline]
+    fn as_get_env(&self) -> &dyn GetEnv {
+        self
+    }
+}
+
+fn read_secret(path: &Path, mode: FileSecrets) -> std::result::Result<String, String> {
+    let metadata = std::fs::metadata(path).map_err(|err| err.to_string())?;
+    if !metadata.is_file() {
+        return Err("not a regular file".to_owned());
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Anyone who can write the file can inject the secret. Readable by
+        // everyone is fine, Docker mounts secrets with mode 0444.
+        let file_mode = metadata.permissions().mode();
+        if file_mode & 0o022 != 0 {
+            return Err(format!("insecure file permissions {:o}, file is writable by group or others", file_mode & 0o777));
+        }
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    Ok(mode.trim(&content).to_owned())
+}
+
+/// The directory relative paths in the `.env` file are relative to.
+pub(crate) fn base_dir<'a>(options: &Options<&'a Path>) -> &'a Path {
+    match options.path.parent() {
+        Some(dir) if options.path.as_os_str() != "-" => dir,
+        _ => Path::new(""),
+    }
+}
+
+/// Set `KEY` for every `KEY_FILE` in `keys`. Relative paths are relative to
+/// the directory of the `.env` file.
+pub(crate) fn load_file_secrets(env: &mut dyn Env, keys: Vec<OsString>, options: &Options<&Path>) -> Result<()> {
+    let dir = base_dir(options);
+
+    for file_key in keys {
+        let Some(key) = file_key.to_str().and_then(|file_key| file_key.strip_suffix("_FILE")) else {
+            continue;
+        };
+
+        if key.is_empty() {
+            continue;
+        }
+
+        // might have been removed again
+        let Some(path) = env.get(&file_key).map(|file| dir.join(file)) else {
+            continue;
+        };
+
+        // an explicit definition wins, that is no error
+        if env.get(key.as_ref()).is_some() {
+            if options.debug {
+                eprintln!("{DEBUG_PREFIX}{}: {key} is already defined, {key}_FILE is ignored", options.path.to_string_lossy());
+            }
+            continue;
+        }
+
+        let message = match read_secret(&path, options.file_secrets) {
+            Ok(value) => {
+                options.set_var(env, key.as_ref(), value.as_ref());
+                continue;
+            }
+            Err(err) => format!("{key}_FILE: {}: {err}", path.to_string_lossy()),
+        };
+
+        if options.debug {
+            eprintln!("{DEBUG_PREFIX}{}: {message}", options.path.to_string_lossy());
+        }
+
+        if options.strict {
+            return Err(Error::with_cause(ErrorKind::IOError, message));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,209 @@
+// Shell style glob patterns: `*`, `?`, `[...]` (with `!` or `^` negation,
+// ranges, and `[:class:]` character classes), and `\` to escape the next
+// character. Patterns always have to match the whole text.
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Named(fn(char) -> bool),
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyString,
+    Class { negated: bool, items: Vec<ClassItem> },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Glob {
+    tokens: Vec<Token>,
+}
+
+fn named_class(name: &str) -> Option<fn(char) -> bool> {
+    let class: fn(char) -> bool = match name {
+        "alnum"  => |ch| ch.is_alphanumeric(),
+        "alpha"  => |ch| ch.is_alphabetic(),
+        "digit"  => |ch| ch.is_ascii_digit(),
+        "lower"  => |ch| ch.is_lowercase(),
+        "upper"  => |ch| ch.is_uppercase(),
+        "space"  => |ch| ch.is_whitespace(),
+        "punct"  => |ch| ch.is_ascii_punctuation(),
+        "xdigit" => |ch| ch.is_ascii_hexdigit(),
+        _ => return None,
+    };
+    Some(class)
+}
+
+/// Parses a bracket expression, `chars` starts after the `[`. Returns `None`
+/// if there is no closing `]`, in which case the `[` is matched literally.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let mut index = 0;
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    if negated {
+        index += 1;
+    }
+
+    let mut items = Vec::new();
+    let start_index = index;
+    loop {
+        let ch = *chars.get(index)?;
+
+        // a `]` right at the start is a member of the class
+        if ch == ']' && index > start_index {
+            return Some((Token::Class { negated, items }, index + 1));
+        }
+
+        if ch == '[' && chars.get(index + 1) == Some(&':') {
+            let name_start = index + 2;
+            if let Some(len) = chars[name_start..].windows(2).position(|pair| pair == [':', ']']) {
+                let name: String = chars[name_start..name_start + len].iter().collect();
+                if let Some(class) = named_class(&name) {
+                    items.push(ClassItem::Named(class));
+                    index = name_start + len + 2;
+                    continue;
+                }
+            }
+        }
+
+        let ch = if ch == '\\' {
+            index += 1;
+            *chars.get(index)?
+        } else {
+            ch
+        };
+        index += 1;
+
+        if chars.get(index) == Some(&'-') && chars.get(index + 1).is_some_and(|&end| end != ']') {
+            let mut end = chars[index + 1];
+            index += 2;
+            if end == '\\' {
+                end = *chars.get(index)?;
+                index += 1;
+            }
+            items.push(ClassItem::Range(ch, end));
+        } else {
+            items.push(ClassItem::Range(ch, ch));
+        }
+    }
+}
+
+impl Token {
+    #[inline]
+    fn matches(&self, ch: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == ch,
+            Token::AnyChar => true,
+            Token::AnyString => false,
+            Token::Class { negated, items } => {
+                let found = items.iter().any(|item| match item {
+                    ClassItem::Range(start, end) => *start <= ch && ch <= *end,
+                    ClassItem::Named(class) => class(ch),
+                });
+                found != *negated
+            }
+        }
+    }
+
+    #[inline]
+    fn matches_ignore_case(&self, ch: char) -> bool {
+        if let Token::Char(expected) = self {
+            return expected.to_uppercase().eq(ch.to_uppercase());
+        }
+
+        self.matches(ch) ||
+        ch.to_uppercase().any(|upper| self.matches(upper)) ||
+        ch.to_lowercase().any(|lower| self.matches(lower))
+    }
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut index = 0;
+
+        while let Some(&ch) = chars.get(index) {
+            index += 1;
+            match ch {
+                '*' => {
+                    if !matches!(tokens.last(), Some(Token::AnyString)) {
+                        tokens.push(Token::AnyString);
+                    }
+                }
+                '?' => tokens.push(Token::AnyChar),
+                '[' => {
+                    if let Some((token, len)) = parse_class(&chars[index..]) {
+                        tokens.push(token);
+                        index += len;
+                    } else {
+                        tokens.push(Token::Char('['));
+                    }
+                }
+                '\\' if index < chars.len() => {
+                    tokens.push(Token::Char(chars[index]));
+                    index += 1;
+                }
+                _ => tokens.push(Token::Char(ch)),
+            }
+        }
+
+        Self { tokens }
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Matches a single character, used for the `${VAR^pattern}` family of
+    /// case conversions. An empty pattern matches any character.
+    pub(crate) fn matches_char(&self, ch: char) -> bool {
+        match self.tokens.as_slice() {
+            [] | [Token::AnyString] => true,
+            [token] => token.matches(ch),
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn matches(&self, text: &[char]) -> bool {
+        self.matches_impl(text, Token::matches)
+    }
+
+    #[inline]
+    pub(crate) fn matches_ignore_case(&self, text: &[char]) -> bool {
+        self.matches_impl(text, Token::matches_ignore_case)
+    }
+
+    fn matches_impl(&self, text: &[char], token_matches: fn(&Token, char) -> bool) -> bool {
+        let tokens = &self.tokens;
+        let mut text_index = 0;
+        let mut token_index = 0;
+        let mut backtrack = None;
+
+        while text_index < text.len() {
+            match tokens.get(token_index) {
+                Some(Token::AnyString) => {
+                    token_index += 1;
+                    backtrack = Some((token_index, text_index));
+                }
+                Some(token) if token_matches(token, text[text_index]) => {
+                    token_index += 1;
+                    text_index += 1;
+                }
+                _ => {
+                    let Some((star_token_index, star_text_index)) = backtrack else {
+                        return false;
+                    };
+                    token_index = star_token_index;
+                    text_index = star_text_index + 1;
+                    backtrack = Some((star_token_index, text_index));
+                }
+            }
+        }
+
+        tokens[token_index..].iter().all(|token| matches!(token, Token::AnyString))
+    }
+}
@@ -12,42 +12,49 @@ use std::io::BufReader;
 use std::path::Path;
 
 pub mod error;
-use dialects::binary::config_binary;
-use dialects::composego::config_composego;
-use dialects::go_dotenv::config_go_dotenv;
-use dialects::java_dotenv::config_java_dotenv;
-use dialects::javascript_dotenv::config_javascript_dotenv;
-use dialects::nodejs::config_nodejs;
-use dialects::punktum::config_punktum;
-use dialects::python_dotenv::config_python_dotenv;
-use dialects::python_dotenv_cli::config_python_dotenv_cli;
-use dialects::ruby_dotenv::config_ruby_dotenv;
 use env::SYSTEM_ENV;
 pub use error::Error;
 pub use error::ErrorKind;
 
 pub mod options;
 pub use options::Options;
-use options::Builder;
+use options::{Builder, KeyCase};
 
 pub mod result;
 pub use result::Result;
 
 pub mod env;
 pub use env::Env;
-use env::{GetEnv, SystemEnv};
+use env::{GetEnv, KeyCaseEnv, OverlayEnv, SystemEnv};
 
 pub mod encoding;
 pub use encoding::Encoding;
 
 pub mod dialect;
-pub use dialect::Dialect;
+pub use dialect::{Dialect, DialectParser};
 
 pub mod dialects;
 
 pub mod line_splitter;
 
+pub mod render;
+
+pub mod resolver;
+
+pub mod file_secrets;
+use file_secrets::DefinedKeys;
+
+pub mod decode;
+
+pub(crate) mod command;
+
+pub(crate) mod deferred;
+
+pub(crate) mod glob;
+
 pub(crate) const DEBUG_PREFIX: &str = concat!("[", env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"), "][DEBUG] ");
+pub(crate) const WARNING_PREFIX: &str = concat!("[", env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"), "][WARNING] ");
+pub(crate) const AUDIT_PREFIX: &str = concat!("[", env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"), "][AUDIT] ");
 
 #[inline]
 pub fn build() -> Builder {
@@ -110,32 +117,113 @@ where P: AsRef<Path> {
     config_with_reader(&mut reader, env, parent, options)
 }
 
+/// Run `f` on an [`OverlayEnv`] over `env` and only apply the changes to
+/// `env` if it succeeds, i.e. `env` is left untouched on error.
+pub fn transaction<E, R>(env: &mut E, f: impl FnOnce(&mut OverlayEnv<&mut E>) -> Result<R>) -> Result<R>
+where E: Env {
+    let mut overlay = OverlayEnv::new(env);
+    let result = f(&mut overlay)?;
+    overlay.commit();
+    Ok(result)
+}
+
+/// Load all `paths` in order, all or nothing. Nothing is written to `env`
+/// before every file is parsed successfully. The error names the file that
+/// aborted the transaction (see [`Error::path()`]).
+pub fn config_transaction<P>(env: &mut impl Env, parent: &impl GetEnv, paths: &[impl AsRef<Path>], options: &Options<P>) -> Result<()> {
+    transaction(env, |env| {
+        for path in paths {
+            let path = path.as_ref();
+            config_with_options(env, parent, &options.for_path(path)).map_err(|err| err.with_path(path))?;
+        }
+        Ok(())
+    })
+}
+
 #[inline]
 pub fn config_with_reader<P>(reader: &mut dyn BufRead, env: &mut impl Env, parent: &impl GetEnv, options: &Options<P>) -> Result<()>
 where P: AsRef<Path> {
-    let options = Options {
-        override_env: options.override_env,
-        strict:       options.strict,
-        debug:        options.debug,
-        encoding:     options.encoding,
-        dialect:      options.dialect,
-        path:         options.path.as_ref(),
-    };
+    let mut options = options.for_path(options.path.as_ref());
+
+    // The binary dialect ignores the encoding, for everything else a BOM is
+    // never part of the content.
+    if options.dialect != Dialect::Binary {
+        let encoding = options.encoding.resolve(reader)?;
+        if options.debug && options.encoding == Encoding::Auto {
+            eprintln!("{DEBUG_PREFIX}{}: detected encoding {encoding}", options.path.to_string_lossy());
+        }
+        options.encoding = encoding;
+    }
+
+    if options.key_case == KeyCase::Preserve {
+        return load(reader, env, parent, &options);
+    }
+
+    let mut env = KeyCaseEnv::new(env, options.key_case);
+    let parent = KeyCaseEnv::new(parent, options.key_case);
+    let result = load(reader, &mut env, &parent, &options);
+
+    // Existing keys used by the file that only differ in case are ambiguous,
+    // the smallest spelling was picked. Both lists are in a deterministic
+    // order.
+    if options.debug {
+        let path = options.path.to_string_lossy();
+        for keys in env.conflicts() {
+            let keys: Vec<_> = keys.iter().map(|key| format!("{key:?}")).collect();
+            eprintln!("{DEBUG_PREFIX}{path}: environment variables differ only in case, using the first: {}", keys.join(", "));
+        }
+
+        for (key, other) in env.mismatches() {
+            eprintln!("{DEBUG_PREFIX}{path}: {key:?} and {other:?} differ only in case");
+        }
+    }
+
+    result
+}
+
+fn load(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    if options.file_secrets.is_enabled() || options.decoders.is_enabled() {
+        let mut defined_keys = DefinedKeys::new(env);
+        parse(reader, &mut defined_keys, parent, options)?;
+        let keys = defined_keys.into_keys();
+
+        if options.decoders.is_enabled() {
+            decode::decode_values(env, &keys, options)?;
+        }
 
-    match options.dialect {
-        Dialect::Punktum          => config_punktum(          reader, env, parent, &options),
-        Dialect::JavaScriptDotenv => config_javascript_dotenv(reader, env, &options),
-        Dialect::NodeJS           => config_nodejs(           reader, env, &options),
-        Dialect::PythonDotenv     => config_python_dotenv(    reader, env, &options),
-        Dialect::PythonDotenvCLI  => config_python_dotenv_cli(reader, env, &options),
-        Dialect::ComposeGo        => config_composego(        reader, env, parent, &options),
-        Dialect::GoDotenv         => config_go_dotenv(        reader, env, &options),
-        Dialect::RubyDotenv       => config_ruby_dotenv(      reader, env, parent, &options),
-        Dialect::JavaDotenv       => config_java_dotenv(      reader, env, &options),
-        Dialect::Binary           => config_binary(           reader, env, &options),
+        if options.file_secrets.is_enabled() {
+            file_secrets::load_file_secrets(env, keys, options)?;
+        }
+
+        return Ok(());
+    }
+
+    parse(reader, env, parent, options)
+}
+
+#[inline]
+fn parse(reader: &mut dyn BufRead, env: &mut dyn Env, parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+    if options.deferred_substitution {
+        return deferred::config_deferred(reader, env, parent, options);
     }
+
+    options.dialect.parse(reader, env, parent, options)
 }
 
+/// Write the variables of an environment as `KEY='VALUE'` lines.
+///
+/// This is implemented for `HashMap<K, V>` with string keys and values, for
+/// the environments in [`env`] and for `dyn GetEnv`. A `HashMap<OsString,
+/// OsString>` can't be both, write it as a `dyn GetEnv`:
+///
+/// ```
+/// use std::{collections::HashMap, ffi::OsString};
+/// use punktum::{env::GetEnv, EnvWrite};
+///
+/// let env: HashMap<OsString, OsString> = HashMap::from([("A".into(), "1".into())]);
+/// (&env as &dyn GetEnv).write_env(std::io::stdout())?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
 pub trait EnvWrite {
     fn write_env(&self, writer: impl std::io::Write) -> std::io::Result<()>;
 }
@@ -151,6 +239,44 @@ where
     }
 }
 
+/// Write every variable of `env` like [`EnvWrite::write_env()`].
+pub fn write_get_env(mut writer: impl std::io::Write, env: &(impl GetEnv + ?Sized)) -> std::io::Result<()> {
+    let mut result = Ok(());
+    env.for_each(&mut |key, value| {
+        if result.is_ok() {
+            result = write_var(&mut writer, key.to_string_lossy(), value.to_string_lossy());
+        }
+    });
+    result
+}
+
+macro_rules! impl_env_write {
+    ($(impl$([$($generics:tt)*])? for $type:ty;)*) => {
+        $(
+            impl$(<$($generics)*>)? EnvWrite for $type where $type: GetEnv {
+                #[inline]
+                fn write_env(&self, writer: impl std::io::Write) -> std::io::Result<()> {
+                    write_get_env(writer, self)
+                }
+            }
+        )*
+    };
+}
+
+impl_env_write! {
+    impl['a] for dyn GetEnv + 'a;
+    impl for SystemEnv;
+    impl for env::EmptyEnv;
+    impl['a, E] for env::AllowListEnv<'a, E>;
+    impl['a, E] for env::DenyListEnv<'a, E>;
+    impl[Base, Top] for OverlayEnv<Base, Top>;
+    impl['a, E] for env::PrefixEnv<'a, E>;
+    impl['a, E] for env::StripPrefixEnv<'a, E>;
+    impl['a, E] for env::RenameEnv<'a, E>;
+    impl[E] for KeyCaseEnv<E>;
+    impl[BH] for env::CaseInsensitiveMap<BH>;
+}
+
 pub fn write_var(mut writer: impl std::io::Write, key: impl AsRef<str>, value: impl AsRef<str>)  -> std::io::Result<()> {
     let key = key.as_ref();
     let mut value = value.as_ref();
@@ -1,17 +1,56 @@
-use std::{collections::HashMap, env::ArgsOs, ffi::{OsStr, OsString}, io::Write, path::Path, process::Command};
+use std::{collections::HashMap, env::ArgsOs, ffi::{OsStr, OsString}, io::{Read, Write}, path::Path, process::Command, time::Duration};
 
 #[cfg(target_family = "unix")]
 use std::os::unix::process::CommandExt;
 
-use punktum::{env::{parse_bool, AllowListEnv, DenyListEnv}, options::{Builder, IllegalOption, OptionType}, Dialect, Encoding, Env, Error, ErrorKind};
+use punktum::{dialects::{composego::interpolate_with_unresolved, python_dotenv::{find_dotenv, get_key, set_key, unset_key, QuoteMode}}, env::{parse_bool, parse_comma_list, parse_seconds, AllowListEnv, DenyListEnv, GetEnv, KeyPattern, PrefixEnv, RenameEnv, StripPrefixEnv}, decode::Decoders, file_secrets::FileSecrets, options::{Builder, IllegalOption, KeyCase, KeyValidation, OptionType, UndefinedVars}, render::{render, RenderOptions, Syntax}, Dialect, Encoding, Env, Error, ErrorKind};
 
 const USAGE: &str = concat!("\
 usage: ", env!("CARGO_BIN_NAME"), " [--file=PATH...] [--replace] [--] command [args...]
        ", env!("CARGO_BIN_NAME"), " [--file=PATH...] [--replace] --print-env [--sorted] [--export] [--binary]
+       ", env!("CARGO_BIN_NAME"), " [--file=PATH] get-key KEY
+       ", env!("CARGO_BIN_NAME"), " [--file=PATH] [--quote-mode=MODE] [--export] set-key KEY VALUE
+       ", env!("CARGO_BIN_NAME"), " [--file=PATH] unset-key KEY
+       ", env!("CARGO_BIN_NAME"), " [--usecwd] find-dotenv [FILENAME]
+       ", env!("CARGO_BIN_NAME"), " [--file=PATH...] [--replace] interpolate COMPOSE_FILE
+       ", env!("CARGO_BIN_NAME"), " [--file=PATH...] [--replace] [--syntax=SYNTAX] [--vars=LIST] render [TEMPLATE]
        ", env!("CARGO_BIN_NAME"), " [--help] [--version]
 
 Punktum executes a given command with environment variables loaded from a .env file.
 
+Sub-commands:
+  A sub-command is given in place of the command to execute, options may
+  follow it. To execute a program with the same name as a sub-command put --
+  in front of it, e.g.: punktum -- render
+
+  The following sub-commands work like the python-dotenv functions of the same
+  name and always use the PythonDotenv dialect. Files are re-written exactly
+  like python-dotenv would do it.
+
+  get-key KEY               Print the value of KEY. Exits with status 1 if the
+                            value is empty or the key doesn't exist.
+  set-key KEY VALUE         Set KEY to VALUE, replacing an existing definition
+                            or appending a new one. The file is created if it
+                            doesn't exist.
+  unset-key KEY             Remove all definitions of KEY. Exits with status 1
+                            if the key doesn't exist.
+  find-dotenv [FILENAME]    Search for FILENAME (default: \".env\") in the
+                            directory of the punktum executable (or the current
+                            working directory with --usecwd) and its parent
+                            directories and print the path of the first match.
+                            Exits with status 1 if nothing was found.
+
+  The following sub-commands use the environment built from the .env files.
+
+  interpolate COMPOSE_FILE  Substitute variables in COMPOSE_FILE exactly like
+                            docker compose does it in compose.yaml files and
+                            print the result. Pass \"-\" to read from stdin.
+                            Unset variables without default are reported
+                            with their line on stderr.
+  render [TEMPLATE]         Substitute variables in TEMPLATE (default: stdin)
+                            and print the result, like envsubst. In strict mode
+                            references to undefined variables are an error.
+
 Positional arguments:
   command                   Program to execute.
 
@@ -22,17 +61,69 @@ Optional arguments:
                             This option can be passed multiple times.
                             All files are loaded in order.
                             Pass \"-\" to read from stdin.
+      --allow=PATTERNS      Only load variables matching one of the comma separated
+                            PATTERNS. A pattern containing *, ?, or [ is a shell
+                            glob, anything else a literal name. Use the prefixes
+                            literal:, glob:, or regex: to be explicit. regex:
+                            requires punktum to be built with the regex feature.
+                            With --debug ignored variables are reported.
+      --deny=PATTERNS       Don't load variables matching one of the comma separated
+                            PATTERNS. Same syntax as --allow.
+      --strip-prefix=PREFIX Only load variables starting with PREFIX and remove
+                            the prefix, e.g. APP_HOST=x sets HOST.
+      --rename=FROM=TO      Load the variable FROM as TO.
+                            This option can be passed multiple times.
+      --prefix=PREFIX       Add PREFIX to every loaded variable, e.g. with
+                            --prefix=APP_ the line HOST=x sets APP_HOST.
+                            Variables are read with the prefix, too.
+                            Keys are filtered and mapped in this order:
+                            --allow, --deny, --strip-prefix, --rename, --prefix
   -r, --replace             Completely replace the environment with the one loaded
                             from the .env file.
   -p, --print-env           Instead of running a command print the built environment
                             in a syntax compatible to Punktum and bash.
       --sorted              Sort printed environment variables for reproducible output.
-      --export              Add \"export \" prefix to every printed environment variable.
+      --export              Add \"export \" prefix to every printed environment variable
+                            or to the line written by set-key.
+      --quote-mode=MODE     How set-key quotes the value. One of: always, auto, never
+                            \"auto\" only quotes values that are not alphanumeric.
+                            [default: always]
+      --usecwd              Start the search of find-dotenv in the current working
+                            directory.
+      --syntax=SYNTAX       Variable substitution syntax used by render.
+                            One of: punktum, composego, ruby
+                            [default: punktum]
+      --vars=LIST           Comma separated list of variables substituted by
+                            render. References to other variables are kept as
+                            they are. [default: all variables]
       --strict=bool         Overwrite DOTENV_CONFIG_STRICT
       --debug=bool          Overwrite DOTENV_CONFIG_DEBUG
       --override=bool       Overwrite DOTENV_CONFIG_OVERRIDE
       --encoding=ENCODING   Overwrite DOTENV_CONFIG_ENCODING
       --dialect=DIALECT     Overwrite DOTENV_CONFIG_DIALECT
+      --deferred-substitution=bool
+                            Overwrite DOTENV_CONFIG_DEFERRED_SUBSTITUTION
+      --resolvers=LIST      Overwrite DOTENV_CONFIG_RESOLVERS
+      --file-secrets=MODE   Overwrite DOTENV_CONFIG_FILE_SECRETS
+      --undefined-vars=MODE Overwrite DOTENV_CONFIG_UNDEFINED_VARS
+      --key-case=MODE       Overwrite DOTENV_CONFIG_KEY_CASE
+      --key-validation=MODE Overwrite DOTENV_CONFIG_KEY_VALIDATION
+      --list-separator=SEP  Overwrite DOTENV_CONFIG_LIST_SEPARATOR
+      --decoders=LIST       Overwrite DOTENV_CONFIG_DECODERS
+      --ruby-legacy-linebreaks=bool
+                            Overwrite DOTENV_CONFIG_RUBY_LEGACY_LINEBREAKS
+      --ruby-allow-commands=LIST
+                            Overwrite DOTENV_CONFIG_RUBY_ALLOW_COMMANDS
+      --ruby-command-timeout=SECONDS
+                            Overwrite DOTENV_CONFIG_RUBY_COMMAND_TIMEOUT
+      --composego-strict-substitution=bool
+                            Overwrite DOTENV_CONFIG_COMPOSEGO_STRICT_SUBSTITUTION
+      --composego-compat-errors=bool
+                            Overwrite DOTENV_CONFIG_COMPOSEGO_COMPAT_ERRORS
+      --python-interpolate=bool
+                            Overwrite DOTENV_CONFIG_PYTHON_INTERPOLATE
+      --javascript-multiline=bool
+                            Overwrite DOTENV_CONFIG_JAVASCRIPT_MULTILINE
 
 Environemnt variables:
   DOTENV_CONFIG_PATH=FILE
@@ -69,6 +160,9 @@ Environemnt variables:
     - UTF-16LE
     - UTF-32BE
     - UTF-32LE
+    - auto        Detect UTF-8, UTF-16, or UTF-32 by the byte order mark,
+                  guess UTF-16 by its null bytes, UTF-8 otherwise.
+    A byte order mark matching the encoding is skipped.
 
   DOTENV_CONFIG_DIALECT=DIALECT
     Dialect for the parser to use.
@@ -78,59 +172,242 @@ Environemnt variables:
     Supported values:
     - Punktum (default)
     - NodeJS
+    - NodeJS20
     - JavaScriptDotenv
     - PythonDotenv
     - PythonDotenvCLI
     - ComposeGo
     - GoDotenv
     - RubyDotenv
+    - RubyDotenv2
     - JavaDotenv
+    - DotNetEnv
+    - ElixirDotenvy
     - Binary
 
+    The NodeJS and RubyDotenv dialects can also be selected by the version of
+    the emulated implementation, e.g.: nodejs@20, ruby-dotenv@2.7
+
+    Applications using the punktum library can register further dialects,
+    which are then also accepted here by name.
+
+  DOTENV_CONFIG_DEFERRED_SUBSTITUTION=true|false
+    Resolve references to variables that are defined later in the same file,
+    like dotenv-expand does. The file is parsed multiple times for this.
+    Cyclic references are an error.
+    This can be overwritten with --deferred-substitution.
+    [default: false]
+
+  DOTENV_CONFIG_RESOLVERS=LIST
+    Punktum dialect-only. Comma separated list of resolvers that serve
+    ${scheme:argument} references. Built-in resolvers:
+    - file  ${file:PATH} is the trimmed content of the file, relative paths
+            are relative to the .env file
+    - env   ${env:NAME} is the variable from the parent environment
+    This can be overwritten with --resolvers.
+    [default: no resolvers]
+
+  DOTENV_CONFIG_FILE_SECRETS=false|raw|trim-newline|trim
+    For every KEY_FILE defined in the .env file set KEY to the content of the
+    named file (Docker secrets). Relative paths are relative to the .env file.
+    The modes define how the content is trimmed:
+    - false         Disabled.
+    - raw           The content as it is.
+    - trim-newline  Remove a single trailing newline.
+    - trim          Remove white space from both ends. (alias: true)
+    An explicitly defined KEY is never overwritten, that is only a debug
+    message. Missing files and files that are writable by group or others are
+    errors in strict mode.
+    This can be overwritten with --file-secrets.
+    [default: false]
+
+  DOTENV_CONFIG_UNDEFINED_VARS=ignore|warn|error
+    What to do about references to undefined variables without a default
+    value in the Punktum, PythonDotenv, ComposeGo, and RubyDotenv dialects.
+    - ignore  Substitute an empty string.
+    - warn    Substitute an empty string and print a warning.
+    - error   Error at the location of the reference in strict mode, print a
+              warning otherwise.
+    This can be overwritten with --undefined-vars.
+    [default: ignore]
+
+  DOTENV_CONFIG_KEY_CASE=preserve|uppercase|insensitive
+    How keys are normalised and compared:
+    - preserve     Keys are case-sensitive and used as written.
+    - uppercase    Keys are converted to upper case.
+    - insensitive  Keys are compared case-insensitively, like on Windows.
+                   Assignments use the spelling of an existing variable.
+    Keys that only differ in case (e.g. PATH and Path) are reported in debug
+    mode. If the environment already has several of them the smallest
+    spelling in byte order is used. --allow and --deny ignore case unless this is preserve.
+    This can be overwritten with --key-case.
+    [default: preserve]
+
+  DOTENV_CONFIG_KEY_VALIDATION=dialect|posix|any
+    Which variable names are accepted:
+    - dialect  Whatever the dialect parses as a name.
+    - posix    Only portable names: [A-Za-z_][A-Za-z0-9_]*
+    - any      Anything that isn't empty and contains no = and no NUL byte.
+    Illegal names are syntax errors in strict mode and are skipped with a
+    debug message otherwise.
+    This can be overwritten with --key-validation.
+    [default: dialect]
+
+  DOTENV_CONFIG_LIST_SEPARATOR=SEP
+    Punktum dialect-only. Inserted between the current and the new value by
+    the += (append) and ^= (prepend) operators. Set it to an empty string for
+    plain concatenation.
+    This can be overwritten with --list-separator.
+    [default: \":\" or \";\" on Windows]
+
+  DOTENV_CONFIG_DECODERS=LIST
+    Comma separated list of decoders that are applied to the values after
+    parsing, for any dialect:
+    - base64  KEY=base64:SGVsbG8= is decoded from base64.
+    - hex     KEY=hex:48656c6c6f is decoded from hex.
+    - file    KEY=@path/to/file is replaced with the content of the file.
+              Relative paths are relative to the .env file.
+    - all     All of the above.
+    Decoded values that contain NUL bytes (or aren't valid UTF-8 on Windows)
+    are errors in strict mode.
+    This can be overwritten with --decoders.
+    [default: no decoders]
+
+  DOTENV_CONFIG_RUBY_LEGACY_LINEBREAKS=true|false
+    RubyDotenv dialect-only. Replace \"\\n\" and \"\\r\" in unquoted values and
+    double quoted values with actual newline and carrige return characters.
+    This can be overwritten with --ruby-legacy-linebreaks.
+    [default: false]
+
   DOTENV_LINEBREAK_MODE=legacy
-    RubyDotenv dialect-only. If this environment variable is set to \"legacy\"
-    \"\\n\" and \"\\r\" in unquoted values and double quoted values are replaced
-    with actual newline and carrige return characters.
+    RubyDotenv dialect-only. Same as DOTENV_CONFIG_RUBY_LEGACY_LINEBREAKS=true,
+    but read from the parent environment or the .env file itself, just like
+    the dotenv gem does.
+
+  DOTENV_CONFIG_RUBY_ALLOW_COMMANDS=LIST
+    RubyDotenv dialect-only. Comma separated list of programs that may be run
+    by $(command) substitutions. Command substitution is disabled if this is
+    empty. Commands are not run through a shell, get an empty environment, and
+    every execution is reported on stderr.
+    This can be overwritten with --ruby-allow-commands.
+    [default: \"\"]
+
+  DOTENV_CONFIG_RUBY_COMMAND_TIMEOUT=SECONDS
+    RubyDotenv dialect-only. Commands of $(command) substitutions that run
+    longer than this are killed.
+    This can be overwritten with --ruby-command-timeout.
+    [default: 5]
+
+  DOTENV_CONFIG_COMPOSEGO_STRICT_SUBSTITUTION=true|false
+    ComposeGo dialect-only. Malformed variable substitutions are an error
+    (like compose-go's \"Invalid template\") instead of being kept literally.
+    This can be overwritten with --composego-strict-substitution.
+    [default: false]
+
+  DOTENV_CONFIG_COMPOSEGO_COMPAT_ERRORS=true|false
+    ComposeGo dialect-only. Fail exactly where compose-go (docker compose)
+    fails, with the same error messages. Any error aborts loading, even if
+    DOTENV_CONFIG_STRICT=false, and malformed variable substitutions are
+    errors.
+    This can be overwritten with --composego-compat-errors.
+    [default: false]
+
+  DOTENV_CONFIG_PYTHON_INTERPOLATE=true|false
+    PythonDotenv dialect-only. Perform variable substitution, like
+    dotenv_values(interpolate=...) does.
+    This can be overwritten with --python-interpolate.
+    [default: true]
+
+  DOTENV_CONFIG_JAVASCRIPT_MULTILINE=true|false
+    JavaScriptDotenv dialect-only. Allow quoted values to span multiple lines.
+    If false quoted values have to end on the same line like with dotenv
+    before version 15.
+    This can be overwritten with --javascript-multiline.
+    [default: true]
 
 © 2024 ", env!("CARGO_PKG_AUTHORS"), "
 GitHub: https://github.com/panzi/punktum
 ");
 
-fn config_with_lists<P>(env: &mut impl Env, allow_list: &Option<Vec<OsString>>, deny_list: &Option<Vec<OsString>>, files: &[impl AsRef<OsStr>], builder: &Builder<P>) -> punktum::Result<()>
+/// Filters and mappings of the keys defined in the .env files. A key passes
+/// them in this order: --allow, --deny, --strip-prefix, --rename, --prefix
+#[derive(Debug, Default)]
+struct KeyFilters {
+    allow_list: Option<Vec<KeyPattern>>,
+    deny_list: Option<Vec<KeyPattern>>,
+    strip_prefix: Option<OsString>,
+    renames: Vec<(OsString, OsString)>,
+    prefix: Option<OsString>,
+}
+
+impl KeyFilters {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.allow_list.is_none() && self.deny_list.is_none() && self.strip_prefix.is_none() &&
+        self.renames.is_empty() && self.prefix.is_none()
+    }
+}
+
+fn config_with_filters<P>(env: &mut impl Env, filters: &KeyFilters, files: &[impl AsRef<OsStr>], builder: &Builder<P>) -> punktum::Result<()>
 where P: AsRef<Path> {
-    if let Some(allow_list) = allow_list {
-        let mut env = AllowListEnv::from_slice(env, allow_list);
-        return next(&mut env, deny_list, files, builder);
-    } else {
-        return next(env, deny_list, files, builder);
+    let mut env: &mut dyn Env = env;
+
+    let mut prefix_env;
+    if let Some(prefix) = &filters.prefix {
+        prefix_env = PrefixEnv::new(env, prefix);
+        env = &mut prefix_env;
     }
 
-    fn next<P>(env: &mut impl Env, deny_list: &Option<Vec<OsString>>, files: &[impl AsRef<OsStr>], builder: &Builder<P>) -> punktum::Result<()>
-    where P: AsRef<Path> {
-        if let Some(deny_list) = deny_list {
-            let mut env = DenyListEnv::from_slice(env, deny_list);
-            return next(&mut env, files, builder);
-        } else {
-            return next(env, files, builder);
-        }
+    let mut rename_env;
+    if !filters.renames.is_empty() {
+        rename_env = RenameEnv::from_slice(env, &filters.renames);
+        env = &mut rename_env;
+    }
 
-        fn next<P>(env: &mut impl Env, files: &[impl AsRef<OsStr>], builder: &Builder<P>) -> punktum::Result<()>
-        where P: AsRef<Path> {
-            if files.is_empty() {
-                builder.options().config_env(env)?;
-            } else {
-                for file in files {
-                    builder.path(file.as_ref()).config_env(env)?;
-                }
-            }
-            Ok(())
+    let mut strip_prefix_env;
+    if let Some(prefix) = &filters.strip_prefix {
+        strip_prefix_env = StripPrefixEnv::new(env, prefix);
+        env = &mut strip_prefix_env;
+    }
+
+    let ignore_case = builder.options().key_case != KeyCase::Preserve;
+
+    let mut deny_list_env;
+    if let Some(deny_list) = &filters.deny_list {
+        deny_list_env = DenyListEnv::from_patterns(env, deny_list.clone()).with_ignore_case(ignore_case).with_debug(builder.options().debug);
+        env = &mut deny_list_env;
+    }
+
+    let mut allow_list_env;
+    if let Some(allow_list) = &filters.allow_list {
+        allow_list_env = AllowListEnv::from_patterns(env, allow_list.clone()).with_ignore_case(ignore_case).with_debug(builder.options().debug);
+        env = &mut allow_list_env;
+    }
+
+    if files.is_empty() {
+        builder.options().config_env(&mut env)?;
+    } else {
+        for file in files {
+            let file = file.as_ref();
+            builder.path(file).config_env(&mut env).map_err(|err| err.with_path(file))?;
         }
     }
+    Ok(())
 }
 
-fn parse_comma_list(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<Vec<OsString>> {
+fn parse_rename_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<(OsString, OsString)> {
     let value = value.as_ref();
-    let Some(value) = value.to_str() else {
+    let Some((from, to)) = value.to_str().and_then(|value| value.split_once('=')).filter(|(from, to)| !from.is_empty() && !to.is_empty()) else {
+        let option = option.as_ref().to_string_lossy();
+        eprintln!("Error: {option} expects FROM=TO, got: {value:?}");
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    };
+    Ok((from.into(), to.into()))
+}
+
+fn parse_comma_list_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<Vec<OsString>> {
+    let value = value.as_ref();
+    let Some(value) = parse_comma_list(value) else {
         return Err(Error::with_cause(
             ErrorKind::OptionsParseError,
             IllegalOption::new(
@@ -138,13 +415,25 @@ fn parse_comma_list(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punk
                 value.into(),
                 OptionType::CommaList)));
     };
+    Ok(value)
+}
 
-    let value = value.trim();
-    if value.is_empty() {
-        return Ok(vec![]);
-    }
+fn parse_key_patterns_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<Vec<KeyPattern>> {
+    let patterns = parse_comma_list_option(option, value)?;
+    KeyPattern::parse_all(&patterns)
+}
 
-    Ok(value.split(',').map(|word| OsString::from(word.trim())).collect())
+fn parse_seconds_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<Duration> {
+    let value = value.as_ref();
+    let Some(value) = parse_seconds(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::Seconds)));
+    };
+    Ok(value)
 }
 
 fn parse_bool_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<bool> {
@@ -186,6 +475,283 @@ fn parse_dialect_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) ->
     Ok(value)
 }
 
+/// Sub-commands take the place of the program to execute. A program of the
+/// same name can still be executed by putting `--` in front of it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Subcommand {
+    /// Works on a single file, like the python-dotenv function of the same name.
+    File(FileSubcommand),
+    Interpolate,
+    Render,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum FileSubcommand {
+    GetKey,
+    SetKey,
+    UnsetKey,
+    FindDotenv,
+}
+
+impl Subcommand {
+    fn from_arg(arg: &OsStr) -> Option<Self> {
+        if arg == "get-key" {
+            Some(Subcommand::File(FileSubcommand::GetKey))
+        } else if arg == "set-key" {
+            Some(Subcommand::File(FileSubcommand::SetKey))
+        } else if arg == "unset-key" {
+            Some(Subcommand::File(FileSubcommand::UnsetKey))
+        } else if arg == "find-dotenv" {
+            Some(Subcommand::File(FileSubcommand::FindDotenv))
+        } else if arg == "interpolate" {
+            Some(Subcommand::Interpolate)
+        } else if arg == "render" {
+            Some(Subcommand::Render)
+        } else {
+            None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Subcommand::File(subcommand) => subcommand.name(),
+            Subcommand::Interpolate => "interpolate",
+            Subcommand::Render      => "render",
+        }
+    }
+}
+
+impl FileSubcommand {
+    fn name(self) -> &'static str {
+        match self {
+            FileSubcommand::GetKey     => "get-key",
+            FileSubcommand::SetKey     => "set-key",
+            FileSubcommand::UnsetKey   => "unset-key",
+            FileSubcommand::FindDotenv => "find-dotenv",
+        }
+    }
+}
+
+fn parse_syntax_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<Syntax> {
+    let value = value.as_ref();
+    let Ok(value) = Syntax::try_from(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::Syntax)));
+    };
+    Ok(value)
+}
+
+fn parse_quote_mode_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<QuoteMode> {
+    let value = value.as_ref();
+    let Ok(value) = QuoteMode::try_from(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::QuoteMode)));
+    };
+    Ok(value)
+}
+
+fn parse_decoders_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<Decoders> {
+    let value = value.as_ref();
+    let Ok(value) = Decoders::try_from(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::Decoders)));
+    };
+    Ok(value)
+}
+
+fn parse_file_secrets_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<FileSecrets> {
+    let value = value.as_ref();
+    let Ok(value) = FileSecrets::try_from(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::FileSecrets)));
+    };
+    Ok(value)
+}
+
+fn parse_key_validation_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<KeyValidation> {
+    let value = value.as_ref();
+    let Ok(value) = KeyValidation::try_from(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::KeyValidation)));
+    };
+    Ok(value)
+}
+
+fn parse_key_case_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<KeyCase> {
+    let value = value.as_ref();
+    let Ok(value) = KeyCase::try_from(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::KeyCase)));
+    };
+    Ok(value)
+}
+
+fn parse_undefined_vars_option(option: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> punktum::Result<UndefinedVars> {
+    let value = value.as_ref();
+    let Ok(value) = UndefinedVars::try_from(value) else {
+        return Err(Error::with_cause(
+            ErrorKind::OptionsParseError,
+            IllegalOption::new(
+                option.as_ref().to_owned(),
+                value.into(),
+                OptionType::UndefinedVars)));
+    };
+    Ok(value)
+}
+
+fn str_args(args: &[OsString]) -> punktum::Result<Vec<&str>> {
+    let mut str_args = Vec::with_capacity(args.len());
+    for arg in args {
+        let Some(arg) = arg.to_str() else {
+            eprintln!("Error: illegal argument: {arg:?}");
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        };
+        str_args.push(arg);
+    }
+    Ok(str_args)
+}
+
+fn run_subcommand<P>(subcommand: FileSubcommand, args: &[OsString], builder: Builder<P>, quote_mode: QuoteMode, export: bool, usecwd: bool) -> punktum::Result<()>
+where P: AsRef<Path> {
+    let expected_args = match subcommand {
+        FileSubcommand::GetKey | FileSubcommand::UnsetKey => 1..=1,
+        FileSubcommand::SetKey => 2..=2,
+        FileSubcommand::FindDotenv => 0..=1,
+    };
+
+    if !expected_args.contains(&args.len()) {
+        eprintln!("Error: wrong number of arguments for {}", subcommand.name());
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    }
+
+    let options = builder.into_options();
+    match subcommand {
+        FileSubcommand::FindDotenv => {
+            let filename = args.first().map(OsString::as_os_str).unwrap_or(OsStr::new(".env"));
+            let Some(path) = find_dotenv(filename, usecwd)? else {
+                std::process::exit(1);
+            };
+            println!("{}", path.to_string_lossy());
+        }
+        FileSubcommand::GetKey => {
+            let str_args = str_args(args)?;
+            let value = get_key(&options, &punktum::system_env(), str_args[0])?;
+            let Some(value) = value.filter(|value| !value.is_empty()) else {
+                std::process::exit(1);
+            };
+            println!("{value}");
+        }
+        FileSubcommand::SetKey => {
+            let str_args = str_args(args)?;
+            set_key(&options, str_args[0], str_args[1], quote_mode, export)?;
+            println!("{}={}", str_args[0], str_args[1]);
+        }
+        FileSubcommand::UnsetKey => {
+            let str_args = str_args(args)?;
+            if !unset_key(&options, str_args[0])? {
+                std::process::exit(1);
+            }
+            println!("Successfully removed {}", str_args[0]);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_text(path: &OsStr) -> punktum::Result<String> {
+    let mut text = String::new();
+    if path == "-" {
+        std::io::stdin().lock().read_to_string(&mut text)?;
+    } else {
+        text = std::fs::read_to_string(path)?;
+    }
+    Ok(text)
+}
+
+fn run_interpolate(args: &[OsString], env: &dyn GetEnv) -> punktum::Result<()> {
+    let [path] = args else {
+        eprintln!("Error: wrong number of arguments for {}", Subcommand::Interpolate.name());
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    };
+
+    let text = read_text(path)?;
+    let (text, unresolved) = interpolate_with_unresolved(&text, env)?;
+
+    let path = path.to_string_lossy();
+    for (name, lineno) in unresolved {
+        eprintln!("{path}:{lineno}: The {name:?} variable is not set. Defaulting to a blank string.");
+    }
+
+    std::io::stdout().lock().write_all(text.as_bytes())?;
+
+    Ok(())
+}
+
+fn run_render(args: &[OsString], env: &dyn GetEnv, options: &RenderOptions) -> punktum::Result<()> {
+    if args.len() > 1 {
+        eprintln!("Error: wrong number of arguments for {}", Subcommand::Render.name());
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    }
+
+    let path = args.first().map(OsString::as_os_str).unwrap_or(OsStr::new("-"));
+    let text = read_text(path)?;
+    let text = render(&text, env, options)?;
+
+    std::io::stdout().lock().write_all(text.as_bytes())?;
+
+    Ok(())
+}
+
+fn print_env_vars(env: &dyn GetEnv, sorted: bool, export: bool, binary: bool) -> std::io::Result<()> {
+    let mut vars = vec![];
+    env.for_each(&mut |key, value| {
+        vars.push((key.to_string_lossy().into_owned(), value.to_string_lossy().into_owned()));
+    });
+
+    if sorted {
+        vars.sort();
+    }
+
+    let mut out = std::io::stdout().lock();
+
+    if binary {
+        return punktum::write_iter_binary(&mut out, vars.into_iter());
+    }
+
+    for (key, value) in vars {
+        if export {
+            write!(out.by_ref(), "export ")?;
+        }
+        punktum::write_var(&mut out, key, value)?;
+    }
+
+    Ok(())
+}
+
 fn require_arg(option: &OsStr, args: &mut ArgsOs) -> punktum::Result<OsString> {
     let Some(value) = args.next() else {
         let option = option.to_string_lossy();
@@ -209,13 +775,37 @@ fn exec() -> punktum::Result<()> {
     let mut override_env: Option<bool> = None;
     let mut encoding: Option<Encoding> = None;
     let mut dialect: Option<Dialect> = None;
-    let mut allow_list: Option<Vec<OsString>> = None;
-    let mut deny_list: Option<Vec<OsString>> = None;
+    let mut deferred_substitution: Option<bool> = None;
+    let mut resolvers: Option<Vec<OsString>> = None;
+    let mut file_secrets: Option<FileSecrets> = None;
+    let mut undefined_vars: Option<UndefinedVars> = None;
+    let mut key_case: Option<KeyCase> = None;
+    let mut key_validation: Option<KeyValidation> = None;
+    let mut list_separator: Option<OsString> = None;
+    let mut decoders: Option<Decoders> = None;
+    let mut ruby_legacy_linebreaks: Option<bool> = None;
+    let mut ruby_allow_commands: Option<Vec<OsString>> = None;
+    let mut ruby_command_timeout: Option<Duration> = None;
+    let mut composego_strict_substitution: Option<bool> = None;
+    let mut composego_compat_errors: Option<bool> = None;
+    let mut python_interpolate: Option<bool> = None;
+    let mut javascript_multiline: Option<bool> = None;
+    let mut filters = KeyFilters::default();
+    let mut quote_mode: Option<QuoteMode> = None;
+    let mut syntax: Option<Syntax> = None;
+    let mut vars: Option<Vec<OsString>> = None;
+    let mut usecwd: bool = false;
+    let mut subcommand: Option<Subcommand> = None;
+    let mut subcommand_args: Vec<OsString> = vec![];
 
     args.next();
     while let Some(arg) = args.next() {
         if arg == "--" {
-            program = args.next();
+            if subcommand.is_some() {
+                subcommand_args.extend(args.by_ref());
+            } else {
+                program = args.next();
+            }
             break;
         } else if arg == "-r" || arg == "--replace" {
             replace = true;
@@ -227,6 +817,17 @@ fn exec() -> punktum::Result<()> {
             export = true;
         } else if arg == "--binary" {
             binary = true;
+        } else if arg == "--usecwd" {
+            usecwd = true;
+        } else if arg == "--quote-mode" {
+            let value = require_arg(&arg, &mut args)?;
+            quote_mode = Some(parse_quote_mode_option(&arg, &value)?);
+        } else if arg == "--syntax" {
+            let value = require_arg(&arg, &mut args)?;
+            syntax = Some(parse_syntax_option(&arg, &value)?);
+        } else if arg == "--vars" {
+            let value = require_arg(&arg, &mut args)?;
+            vars = Some(parse_comma_list_option(&arg, &value)?);
         } else if arg == "-f" || arg == "--file" {
             files.push(require_arg(&arg, &mut args)?);
         } else if arg == "--override" {
@@ -244,12 +845,63 @@ fn exec() -> punktum::Result<()> {
         } else if arg == "--dialect" {
             let value = require_arg(&arg, &mut args)?;
             dialect = Some(parse_dialect_option(&arg, &value)?);
+        } else if arg == "--deferred-substitution" {
+            let value = require_arg(&arg, &mut args)?;
+            deferred_substitution = Some(parse_bool_option(&arg, &value)?);
+        } else if arg == "--resolvers" {
+            let value = require_arg(&arg, &mut args)?;
+            resolvers = Some(parse_comma_list_option(&arg, &value)?);
+        } else if arg == "--file-secrets" {
+            let value = require_arg(&arg, &mut args)?;
+            file_secrets = Some(parse_file_secrets_option(&arg, &value)?);
+        } else if arg == "--undefined-vars" {
+            let value = require_arg(&arg, &mut args)?;
+            undefined_vars = Some(parse_undefined_vars_option(&arg, &value)?);
+        } else if arg == "--key-case" {
+            let value = require_arg(&arg, &mut args)?;
+            key_case = Some(parse_key_case_option(&arg, &value)?);
+        } else if arg == "--key-validation" {
+            let value = require_arg(&arg, &mut args)?;
+            key_validation = Some(parse_key_validation_option(&arg, &value)?);
+        } else if arg == "--list-separator" {
+            list_separator = Some(require_arg(&arg, &mut args)?);
+        } else if arg == "--decoders" {
+            let value = require_arg(&arg, &mut args)?;
+            decoders = Some(parse_decoders_option(&arg, &value)?);
+        } else if arg == "--ruby-legacy-linebreaks" {
+            let value = require_arg(&arg, &mut args)?;
+            ruby_legacy_linebreaks = Some(parse_bool_option(&arg, &value)?);
+        } else if arg == "--ruby-allow-commands" {
+            let value = require_arg(&arg, &mut args)?;
+            ruby_allow_commands = Some(parse_comma_list_option(&arg, &value)?);
+        } else if arg == "--ruby-command-timeout" {
+            let value = require_arg(&arg, &mut args)?;
+            ruby_command_timeout = Some(parse_seconds_option(&arg, &value)?);
+        } else if arg == "--composego-strict-substitution" {
+            let value = require_arg(&arg, &mut args)?;
+            composego_strict_substitution = Some(parse_bool_option(&arg, &value)?);
+        } else if arg == "--composego-compat-errors" {
+            let value = require_arg(&arg, &mut args)?;
+            composego_compat_errors = Some(parse_bool_option(&arg, &value)?);
+        } else if arg == "--python-interpolate" {
+            let value = require_arg(&arg, &mut args)?;
+            python_interpolate = Some(parse_bool_option(&arg, &value)?);
+        } else if arg == "--javascript-multiline" {
+            let value = require_arg(&arg, &mut args)?;
+            javascript_multiline = Some(parse_bool_option(&arg, &value)?);
         } else if arg == "--allow" {
             let value = require_arg(&arg, &mut args)?;
-            allow_list = Some(parse_comma_list(&arg, &value)?);
+            filters.allow_list = Some(parse_key_patterns_option(&arg, &value)?);
         } else if arg == "--deny" {
             let value = require_arg(&arg, &mut args)?;
-            deny_list = Some(parse_comma_list(&arg, &value)?);
+            filters.deny_list = Some(parse_key_patterns_option(&arg, &value)?);
+        } else if arg == "--prefix" {
+            filters.prefix = Some(require_arg(&arg, &mut args)?);
+        } else if arg == "--strip-prefix" {
+            filters.strip_prefix = Some(require_arg(&arg, &mut args)?);
+        } else if arg == "--rename" {
+            let value = require_arg(&arg, &mut args)?;
+            filters.renames.push(parse_rename_option(&arg, &value)?);
         } else if arg == "-h" || arg == "--help" {
             print!("{USAGE}");
             return Ok(());
@@ -258,6 +910,10 @@ fn exec() -> punktum::Result<()> {
             return Ok(());
         } else {
             let Some(str_arg) = arg.to_str() else {
+                if subcommand.is_some() {
+                    subcommand_args.push(arg);
+                    continue;
+                }
                 program = Some(arg);
                 break;
             };
@@ -274,13 +930,59 @@ fn exec() -> punktum::Result<()> {
                 encoding = Some(parse_encoding_option("--encoding", value)?);
             } else if let Some(value) = str_arg.strip_prefix("--dialect=") {
                 dialect = Some(parse_dialect_option("--dialect", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--deferred-substitution=") {
+                deferred_substitution = Some(parse_bool_option("--deferred-substitution", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--resolvers=") {
+                resolvers = Some(parse_comma_list_option("--resolvers", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--file-secrets=") {
+                file_secrets = Some(parse_file_secrets_option("--file-secrets", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--undefined-vars=") {
+                undefined_vars = Some(parse_undefined_vars_option("--undefined-vars", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--key-case=") {
+                key_case = Some(parse_key_case_option("--key-case", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--key-validation=") {
+                key_validation = Some(parse_key_validation_option("--key-validation", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--list-separator=") {
+                list_separator = Some(value.into());
+            } else if let Some(value) = str_arg.strip_prefix("--decoders=") {
+                decoders = Some(parse_decoders_option("--decoders", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--ruby-legacy-linebreaks=") {
+                ruby_legacy_linebreaks = Some(parse_bool_option("--ruby-legacy-linebreaks", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--ruby-allow-commands=") {
+                ruby_allow_commands = Some(parse_comma_list_option("--ruby-allow-commands", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--ruby-command-timeout=") {
+                ruby_command_timeout = Some(parse_seconds_option("--ruby-command-timeout", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--composego-strict-substitution=") {
+                composego_strict_substitution = Some(parse_bool_option("--composego-strict-substitution", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--composego-compat-errors=") {
+                composego_compat_errors = Some(parse_bool_option("--composego-compat-errors", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--python-interpolate=") {
+                python_interpolate = Some(parse_bool_option("--python-interpolate", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--javascript-multiline=") {
+                javascript_multiline = Some(parse_bool_option("--javascript-multiline", value)?);
             } else if let Some(value) = str_arg.strip_prefix("--allow=") {
-                allow_list = Some(parse_comma_list("--allow", value)?);
+                filters.allow_list = Some(parse_key_patterns_option("--allow", value)?);
             } else if let Some(value) = str_arg.strip_prefix("--deny=") {
-                deny_list = Some(parse_comma_list("--deny", value)?);
-            } else if str_arg.starts_with('-') {
+                filters.deny_list = Some(parse_key_patterns_option("--deny", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--prefix=") {
+                filters.prefix = Some(value.into());
+            } else if let Some(value) = str_arg.strip_prefix("--strip-prefix=") {
+                filters.strip_prefix = Some(value.into());
+            } else if let Some(value) = str_arg.strip_prefix("--rename=") {
+                filters.renames.push(parse_rename_option("--rename", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--quote-mode=") {
+                quote_mode = Some(parse_quote_mode_option("--quote-mode", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--syntax=") {
+                syntax = Some(parse_syntax_option("--syntax", value)?);
+            } else if let Some(value) = str_arg.strip_prefix("--vars=") {
+                vars = Some(parse_comma_list_option("--vars", value)?);
+            } else if str_arg.starts_with('-') && str_arg != "-" {
                 eprintln!("Error: illegal argument: {arg:?}");
                 return Err(punktum::ErrorKind::IllegalArgument.into());
+            } else if subcommand.is_some() {
+                subcommand_args.push(arg);
+            } else if let Some(arg) = Subcommand::from_arg(&arg) {
+                subcommand = Some(arg);
             } else {
                 program = Some(arg);
                 break;
@@ -316,7 +1018,133 @@ fn exec() -> punktum::Result<()> {
         builder = builder.dialect(dialect);
     }
 
-    config_with_lists(&mut env, &allow_list, &deny_list, &files, &builder)?;
+    if let Some(deferred_substitution) = deferred_substitution {
+        builder = builder.deferred_substitution(deferred_substitution);
+    }
+
+    if let Some(resolvers) = resolvers {
+        builder = builder.resolvers(resolvers);
+    }
+
+    if let Some(file_secrets) = file_secrets {
+        builder = builder.file_secrets(file_secrets);
+    }
+
+    if let Some(undefined_vars) = undefined_vars {
+        builder = builder.undefined_vars(undefined_vars);
+    }
+
+    if let Some(key_case) = key_case {
+        builder = builder.key_case(key_case);
+    }
+
+    if let Some(key_validation) = key_validation {
+        builder = builder.key_validation(key_validation);
+    }
+
+    if let Some(list_separator) = list_separator {
+        builder = builder.list_separator(list_separator);
+    }
+
+    if let Some(decoders) = decoders {
+        builder = builder.decoders(decoders);
+    }
+
+    if let Some(ruby_legacy_linebreaks) = ruby_legacy_linebreaks {
+        builder = builder.ruby_legacy_linebreaks(ruby_legacy_linebreaks);
+    }
+
+    if let Some(ruby_allow_commands) = ruby_allow_commands {
+        builder = builder.ruby_allow_commands(ruby_allow_commands);
+    }
+
+    if let Some(ruby_command_timeout) = ruby_command_timeout {
+        builder = builder.ruby_command_timeout(ruby_command_timeout);
+    }
+
+    if let Some(composego_strict_substitution) = composego_strict_substitution {
+        builder = builder.composego_strict_substitution(composego_strict_substitution);
+    }
+
+    if let Some(composego_compat_errors) = composego_compat_errors {
+        builder = builder.composego_compat_errors(composego_compat_errors);
+    }
+
+    if let Some(python_interpolate) = python_interpolate {
+        builder = builder.python_interpolate(python_interpolate);
+    }
+
+    if let Some(javascript_multiline) = javascript_multiline {
+        builder = builder.javascript_multiline(javascript_multiline);
+    }
+
+    if (syntax.is_some() || vars.is_some()) && subcommand != Some(Subcommand::Render) {
+        eprintln!("Error: Options --syntax and --vars are only to be used in combination with render");
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    }
+
+    if let Some(Subcommand::File(subcommand)) = subcommand {
+        if print_env || replace || sorted || binary || !filters.is_empty() {
+            eprintln!("Error: Options --print-env, --replace, --sorted, --binary, --allow, --deny, --prefix, --strip-prefix, and --rename can't be used with {}", subcommand.name());
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        }
+
+        if export && subcommand != FileSubcommand::SetKey {
+            eprintln!("Error: Option --export is only to be used in combination with --print-env or set-key");
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        }
+
+        if quote_mode.is_some() && subcommand != FileSubcommand::SetKey {
+            eprintln!("Error: Option --quote-mode is only to be used in combination with set-key");
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        }
+
+        if usecwd && subcommand != FileSubcommand::FindDotenv {
+            eprintln!("Error: Option --usecwd is only to be used in combination with find-dotenv");
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        }
+
+        if files.len() > 1 {
+            eprintln!("Error: {} only accepts one --file", subcommand.name());
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        }
+
+        let quote_mode = quote_mode.unwrap_or_default();
+        if let Some(file) = files.first() {
+            return run_subcommand(subcommand, &subcommand_args, builder.path(file), quote_mode, export, usecwd);
+        }
+        return run_subcommand(subcommand, &subcommand_args, builder, quote_mode, export, usecwd);
+    }
+
+    if quote_mode.is_some() {
+        eprintln!("Error: Option --quote-mode is only to be used in combination with set-key");
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    }
+
+    if usecwd {
+        eprintln!("Error: Option --usecwd is only to be used in combination with find-dotenv");
+        return Err(punktum::ErrorKind::IllegalArgument.into());
+    }
+
+    punktum::transaction(&mut env, |env| config_with_filters(env, &filters, &files, &builder))?;
+
+    if let Some(subcommand) = subcommand {
+        if print_env || sorted || export || binary {
+            eprintln!("Error: Options --print-env, --sorted, --export, and --binary can't be used with {}", subcommand.name());
+            return Err(punktum::ErrorKind::IllegalArgument.into());
+        }
+
+        if subcommand == Subcommand::Render {
+            let options = RenderOptions {
+                syntax: syntax.unwrap_or_default(),
+                vars,
+                strict: builder.options().strict,
+            };
+            return run_render(&subcommand_args, &env, &options);
+        }
+
+        return run_interpolate(&subcommand_args, &env);
+    }
 
     if print_env {
         if binary && export {
@@ -329,44 +1157,7 @@ fn exec() -> punktum::Result<()> {
             return Err(punktum::ErrorKind::IllegalArgument.into());
         }
 
-        let mut out = std::io::stdout().lock();
-
-        if sorted {
-            let mut sorted_env = vec![];
-
-            for (key, value) in &env {
-                let key = key.to_string_lossy();
-                let value = value.to_string_lossy();
-                sorted_env.push((key, value));
-            }
-            sorted_env.sort();
-
-            if binary {
-                punktum::write_iter_binary(&mut out, sorted_env.into_iter())?;
-            } else {
-                for (key, value) in sorted_env {
-                    if export {
-                        write!(out.by_ref(), "export ")?;
-                    }
-                    punktum::write_var(&mut out, key, value)?;
-                }
-            }
-        } else if binary {
-            for (key, value) in env {
-                let key = key.to_string_lossy();
-                let value = value.to_string_lossy();
-                punktum::write_var_binary(&mut out, key, value)?;
-            }
-        } else {
-            for (key, value) in env {
-                let key = key.to_string_lossy();
-                let value = value.to_string_lossy();
-                if export {
-                    write!(out.by_ref(), "export ")?;
-                }
-                punktum::write_var(&mut out, key, value)?;
-            }
-        }
+        print_env_vars(&env, sorted, export, binary)?;
         return Ok(());
     }
 
@@ -1,6 +1,6 @@
-use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, io::BufRead, path::Path};
+use std::{borrow::Cow, collections::HashMap, ffi::{OsStr, OsString}, io::BufRead, path::Path, time::Duration};
 
-use crate::{encoding::Encoding, env::{GetEnv, SystemEnv, SYSTEM_ENV}, Dialect, Env, Result, DEBUG_PREFIX};
+use crate::{encoding::Encoding, env::{GetEnv, SystemEnv, SYSTEM_ENV}, error::SourceLocation, decode::Decoders, file_secrets::FileSecrets, Dialect, Env, Error, ErrorKind, Result, DEBUG_PREFIX, WARNING_PREFIX};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Options<P=&'static str> {
@@ -17,13 +17,340 @@ pub struct Options<P=&'static str> {
 
     pub dialect: Dialect,
 
+    /// Options that only affect certain dialects.
+    pub dialect_options: DialectOptions,
+
+    /// Resolve references to variables that are defined later in the file,
+    /// like dotenv-expand does. Cyclic references are an error.
+    pub deferred_substitution: bool,
+
+    /// Schemes of the [resolvers](crate::resolver) that serve
+    /// `${scheme:argument}` references in the Punktum dialect. None are
+    /// enabled per default.
+    pub resolvers: Vec<OsString>,
+
+    /// Set `KEY` to the content of the file named by `KEY_FILE` for every
+    /// such key defined in the file (Docker secrets).
+    pub file_secrets: FileSecrets,
+
+    /// What to do about references to undefined variables (`$VAR` without a
+    /// default value) in the Punktum, PythonDotenv, ComposeGo, and RubyDotenv
+    /// dialects.
+    pub undefined_vars: UndefinedVars,
+
+    /// Inserted between the current and the new value by the `+=` and `^=`
+    /// operators of the Punktum dialect. Per default the path separator of
+    /// the platform (`:` or `;`), empty for plain concatenation.
+    pub list_separator: OsString,
+
+    /// Decode `base64:`, `hex:`, and `@file` values after parsing, for any
+    /// dialect.
+    pub decoders: Decoders,
+
+    /// How keys are normalised and compared, see [`KeyCase`].
+    pub key_case: KeyCase,
+
+    /// Which keys are accepted, see [`KeyValidation`].
+    pub key_validation: KeyValidation,
+
     pub path: P,
 }
 
+/// Assignment operators of the Punktum dialect.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum AssignOp {
+    /// `KEY=value`
+    Assign,
+    /// `KEY+=value`
+    Append,
+    /// `KEY^=value`
+    Prepend,
+    /// `KEY?=value`
+    Default,
+}
+
+/// How references to undefined variables are reported.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum UndefinedVars {
+    /// Substitute an empty string.
+    #[default]
+    Ignore,
+    /// Substitute an empty string and print a warning.
+    Warn,
+    /// A `SubstitutionError` at the location of the reference in strict mode,
+    /// a warning otherwise.
+    Error,
+}
+
+impl UndefinedVars {
+    pub(crate) fn report(&self, path: &str, name: &str, location: SourceLocation, strict: bool, debug: bool) -> Result<()> {
+        let lineno = location.lineno();
+        let column = location.column();
+        match self {
+            UndefinedVars::Ignore => {}
+            UndefinedVars::Warn => {
+                eprintln!("{WARNING_PREFIX}{path}:{lineno}:{column}: undefined variable ${name}");
+            }
+            UndefinedVars::Error => {
+                if debug {
+                    eprintln!("{DEBUG_PREFIX}{path}:{lineno}:{column}: undefined variable ${name}");
+                }
+                if strict {
+                    return Err(Error::new(ErrorKind::SubstitutionError, format!("undefined variable {name}"), location));
+                }
+                if !debug {
+                    eprintln!("{WARNING_PREFIX}{path}:{lineno}:{column}: undefined variable ${name}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for UndefinedVars {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UndefinedVars::Ignore => "ignore".fmt(f),
+            UndefinedVars::Warn   => "warn".fmt(f),
+            UndefinedVars::Error  => "error".fmt(f),
+        }
+    }
+}
+
+impl TryFrom<&OsStr> for UndefinedVars {
+    type Error = IllegalOption;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, IllegalOption> {
+        if value.eq_ignore_ascii_case("ignore") {
+            Ok(UndefinedVars::Ignore)
+        } else if value.eq_ignore_ascii_case("warn") {
+            Ok(UndefinedVars::Warn)
+        } else if value.eq_ignore_ascii_case("error") {
+            Ok(UndefinedVars::Error)
+        } else {
+            Err(IllegalOption::new("undefined_vars".into(), value.into(), OptionType::UndefinedVars))
+        }
+    }
+}
+
+/// How keys are normalised and compared while loading.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum KeyCase {
+    /// Keys are case-sensitive and used as written.
+    #[default]
+    Preserve,
+    /// Keys are converted to upper case. References still find variables of
+    /// the parent environment that aren't upper case.
+    Uppercase,
+    /// Keys are compared case-insensitively, like on Windows. Assigning to a
+    /// key uses the spelling of an existing variable. If several variables
+    /// differ only in case the smallest spelling in byte order (e.g. `PATH`
+    /// before `Path`) is used.
+    Insensitive,
+}
+
+impl std::fmt::Display for KeyCase {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyCase::Preserve    => "preserve".fmt(f),
+            KeyCase::Uppercase   => "uppercase".fmt(f),
+            KeyCase::Insensitive => "insensitive".fmt(f),
+        }
+    }
+}
+
+impl TryFrom<&OsStr> for KeyCase {
+    type Error = IllegalOption;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, IllegalOption> {
+        if value.eq_ignore_ascii_case("preserve") {
+            Ok(KeyCase::Preserve)
+        } else if value.eq_ignore_ascii_case("uppercase") {
+            Ok(KeyCase::Uppercase)
+        } else if value.eq_ignore_ascii_case("insensitive") {
+            Ok(KeyCase::Insensitive)
+        } else {
+            Err(IllegalOption::new("key_case".into(), value.into(), OptionType::KeyCase))
+        }
+    }
+}
+
+/// Which keys are accepted, in addition to the syntax of the dialect. Keys
+/// that are rejected are syntax errors at the start of the key.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum KeyValidation {
+    /// Whatever the dialect parses as a key.
+    #[default]
+    Dialect,
+    /// POSIX portable names: ASCII letters, digits, and `_`, not starting with
+    /// a digit. These can be used by any shell.
+    Posix,
+    /// Anything but an empty key or one containing `=` or NUL, i.e. what
+    /// `std::env::set_var()` accepts. Some dialects otherwise silently cut
+    /// keys off at a NUL character.
+    Any,
+}
+
+impl KeyValidation {
+    /// Why `key` is rejected, if it is.
+    pub fn check(&self, key: &str) -> std::result::Result<(), &'static str> {
+        match self {
+            KeyValidation::Dialect => {}
+            KeyValidation::Posix => {
+                if key.is_empty() {
+                    return Err("empty name");
+                }
+                if key.starts_with(|ch: char| ch.is_ascii_digit()) {
+                    return Err("name starts with a digit");
+                }
+                if !key.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_') {
+                    return Err("not a POSIX portable name, only ASCII letters, digits, and '_' are allowed");
+                }
+            }
+            KeyValidation::Any => {
+                if key.is_empty() {
+                    return Err("empty name");
+                }
+                if key.contains('=') {
+                    return Err("name contains '='");
+                }
+                if key.contains('\0') {
+                    return Err("name contains a NUL character");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for KeyValidation {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyValidation::Dialect => "dialect".fmt(f),
+            KeyValidation::Posix   => "posix".fmt(f),
+            KeyValidation::Any     => "any".fmt(f),
+        }
+    }
+}
+
+impl TryFrom<&OsStr> for KeyValidation {
+    type Error = IllegalOption;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, IllegalOption> {
+        if value.eq_ignore_ascii_case("dialect") {
+            Ok(KeyValidation::Dialect)
+        } else if value.eq_ignore_ascii_case("posix") {
+            Ok(KeyValidation::Posix)
+        } else if value.eq_ignore_ascii_case("any") {
+            Ok(KeyValidation::Any)
+        } else {
+            Err(IllegalOption::new("key_validation".into(), value.into(), OptionType::KeyValidation))
+        }
+    }
+}
+
+/// Dialect specific options. Each dialect only reads its own options and
+/// ignores the others.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DialectOptions {
+    pub ruby: RubyDotenvOptions,
+    pub composego: ComposeGoOptions,
+    pub python: PythonDotenvOptions,
+    pub javascript: JavaScriptDotenvOptions,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RubyDotenvOptions {
+    /// Replace `\n` and `\r` in double quoted values with actual newline and
+    /// carrige return characters, like the dotenv gem does when
+    /// `DOTENV_LINEBREAK_MODE=legacy` is set.
+    pub legacy_linebreaks: bool,
+
+    /// Programs that may be run by `$(command)` substitutions. Command
+    /// substitution is disabled if this is empty.
+    pub allow_commands: Vec<OsString>,
+
+    /// Commands that run longer than this are killed.
+    pub command_timeout: Duration,
+
+    /// Directories (in the format of `PATH`) in which allow-listed programs
+    /// given without a directory are looked up. This is the `PATH` at the time
+    /// the options are created, so a `.env` file that sets `PATH` can't change
+    /// which program is run. If it is `None` such programs are not found.
+    pub command_path: Option<OsString>,
+}
+
+impl Default for RubyDotenvOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            legacy_linebreaks: DEFAULT_RUBY_LEGACY_LINEBREAKS,
+            allow_commands: Vec::new(),
+            command_timeout: DEFAULT_RUBY_COMMAND_TIMEOUT,
+            command_path: std::env::var_os("PATH"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ComposeGoOptions {
+    /// Treat malformed variable substitutions as errors (like compose-go's
+    /// "Invalid template") instead of keeping them literally.
+    pub strict_substitution: bool,
+
+    /// Fail exactly where compose-go fails, with the same error messages.
+    /// Any error aborts loading, even if strict mode is off, and implies
+    /// `strict_substitution`.
+    pub compat_errors: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PythonDotenvOptions {
+    /// Perform variable substitution, like `dotenv_values(interpolate=True)`.
+    pub interpolate: bool,
+}
+
+impl Default for PythonDotenvOptions {
+    #[inline]
+    fn default() -> Self {
+        Self { interpolate: DEFAULT_PYTHON_INTERPOLATE }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct JavaScriptDotenvOptions {
+    /// Allow quoted values to span multiple lines. Without this a quoted value
+    /// has to end on the same line, like with dotenv before version 15.
+    pub multiline: bool,
+}
+
+impl Default for JavaScriptDotenvOptions {
+    #[inline]
+    fn default() -> Self {
+        Self { multiline: DEFAULT_JAVASCRIPT_MULTILINE }
+    }
+}
+
 pub const DEFAULT_PATH: &str = ".env";
 pub const DEFAULT_OVERRIDE_ENV: bool = false;
 pub const DEFAULT_STRICT: bool = true;
 pub const DEFAULT_DEBUG: bool = false;
+pub const DEFAULT_DEFERRED_SUBSTITUTION: bool = false;
+pub const DEFAULT_RUBY_LEGACY_LINEBREAKS: bool = false;
+pub const DEFAULT_RUBY_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+pub const DEFAULT_COMPOSEGO_STRICT_SUBSTITUTION: bool = false;
+pub const DEFAULT_COMPOSEGO_COMPAT_ERRORS: bool = false;
+pub const DEFAULT_PYTHON_INTERPOLATE: bool = true;
+pub const DEFAULT_JAVASCRIPT_MULTILINE: bool = true;
+
+#[cfg(windows)]
+pub const DEFAULT_LIST_SEPARATOR: &str = ";";
+
+#[cfg(not(windows))]
+pub const DEFAULT_LIST_SEPARATOR: &str = ":";
 
 impl Default for Options {
     #[inline]
@@ -34,6 +361,15 @@ impl Default for Options {
             debug: DEFAULT_DEBUG,
             encoding: Encoding::default(),
             dialect: Dialect::default(),
+            dialect_options: DialectOptions::default(),
+            deferred_substitution: DEFAULT_DEFERRED_SUBSTITUTION,
+            resolvers: Vec::new(),
+            file_secrets: FileSecrets::default(),
+            undefined_vars: UndefinedVars::default(),
+            list_separator: DEFAULT_LIST_SEPARATOR.into(),
+            decoders: Decoders::default(),
+            key_case: KeyCase::default(),
+            key_validation: KeyValidation::default(),
             path: DEFAULT_PATH,
         }
     }
@@ -46,9 +382,18 @@ impl<'a> Options<Cow<'a, OsStr>> {
         let debug = env.get_debug()?;
         let encoding = env.get_encoding()?;
         let dialect = env.get_dialect()?;
+        let dialect_options = env.get_dialect_options()?;
+        let deferred_substitution = env.get_deferred_substitution()?;
+        let resolvers = env.get_comma_list("DOTENV_CONFIG_RESOLVERS".as_ref())?;
+        let file_secrets = env.get_file_secrets()?;
+        let undefined_vars = env.get_undefined_vars()?;
+        let list_separator = env.get_list_separator();
+        let decoders = env.get_decoders()?;
+        let key_case = env.get_key_case()?;
+        let key_validation = env.get_key_validation()?;
         let path = env.get_config_path();
 
-        Ok(Self { override_env, strict, debug, encoding, dialect, path })
+        Ok(Self { override_env, strict, debug, encoding, dialect, dialect_options, deferred_substitution, resolvers, file_secrets, undefined_vars, list_separator, decoders, key_case, key_validation, path })
     }
 
     #[inline]
@@ -66,11 +411,50 @@ impl<P> Options<P> {
             debug: DEFAULT_DEBUG,
             encoding: Encoding::default(),
             dialect: Dialect::default(),
+            dialect_options: DialectOptions::default(),
+            deferred_substitution: DEFAULT_DEFERRED_SUBSTITUTION,
+            resolvers: Vec::new(),
+            file_secrets: FileSecrets::default(),
+            undefined_vars: UndefinedVars::default(),
+            list_separator: DEFAULT_LIST_SEPARATOR.into(),
+            decoders: Decoders::default(),
+            key_case: KeyCase::default(),
+            key_validation: KeyValidation::default(),
             path,
         }
     }
 }
 
+impl<P> Options<P> {
+    /// The same options for a different file.
+    pub(crate) fn for_path<'a>(&self, path: &'a Path) -> Options<&'a Path> {
+        Options {
+            override_env: self.override_env,
+            strict:       self.strict,
+            debug:        self.debug,
+            encoding:     self.encoding,
+            dialect:      self.dialect,
+            dialect_options: self.dialect_options.clone(),
+            deferred_substitution: self.deferred_substitution,
+            resolvers:    self.resolvers.clone(),
+            file_secrets: self.file_secrets,
+            undefined_vars: self.undefined_vars,
+            list_separator: self.list_separator.clone(),
+            decoders:     self.decoders,
+            key_case:     self.key_case,
+            key_validation: self.key_validation,
+            path,
+        }
+    }
+
+    /// Load all `paths` instead of `self.path`, all or nothing. See
+    /// [`config_transaction()`](crate::config_transaction).
+    #[inline]
+    pub fn config_transaction(&self, env: &mut impl Env, parent: &impl GetEnv, paths: &[impl AsRef<Path>]) -> Result<()> {
+        crate::config_transaction(env, parent, paths, self)
+    }
+}
+
 impl<P> Options<P>
 where P: AsRef<Path> {
     #[inline]
@@ -114,6 +498,28 @@ where P: AsRef<Path> {
         Ok(env)
     }
 
+    /// Check `key` as demanded by [`key_validation`](Self::key_validation).
+    /// `Ok(false)` means the definition has to be skipped, in strict mode that
+    /// is a syntax error instead.
+    pub(crate) fn check_key(&self, key: &str, lineno: usize, column: usize) -> Result<bool> {
+        crate::deferred::key_location(SourceLocation::new(lineno, column));
+
+        let Err(reason) = self.key_validation.check(key) else {
+            return Ok(true);
+        };
+
+        if self.debug {
+            let path = self.path.as_ref().to_string_lossy();
+            eprintln!("{DEBUG_PREFIX}{path}:{lineno}:{column}: syntax error: illegal variable name {key:?}: {reason}");
+        }
+
+        if self.strict {
+            return Err(Error::new(ErrorKind::SyntaxError, format!("illegal variable name {key:?}: {reason}"), SourceLocation::new(lineno, column)));
+        }
+
+        Ok(false)
+    }
+
     #[inline]
     pub(crate) fn set_var(&self, env: &mut dyn Env, key: &OsStr, value: &OsStr) {
         if self.override_env {
@@ -127,6 +533,55 @@ where P: AsRef<Path> {
         }
     }
 
+    /// Like [`set_var`](Self::set_var), but combine `value` with the current
+    /// value of `key` (from `env` or else `parent`) as `op` demands.
+    pub(crate) fn assign_var(&self, env: &mut dyn Env, parent: &dyn GetEnv, key: &OsStr, value: &OsStr, op: AssignOp) {
+        if op == AssignOp::Assign {
+            return self.set_var(env, key, value);
+        }
+
+        let current = env.get(key).or_else(|| parent.get(key)).map(Cow::into_owned);
+        match op {
+            AssignOp::Assign => unreachable!(),
+            AssignOp::Default => {
+                if current.is_none() {
+                    self.set_var(env, key, value);
+                }
+            }
+            AssignOp::Append | AssignOp::Prepend => {
+                let current = current.unwrap_or_default();
+                if current.is_empty() {
+                    return self.set_var(env, key, value);
+                }
+
+                if value.is_empty() {
+                    return self.set_var(env, key, &current);
+                }
+
+                let (head, tail) = if op == AssignOp::Append {
+                    (current.as_os_str(), value)
+                } else {
+                    (value, current.as_os_str())
+                };
+
+                let mut new_value = OsString::with_capacity(head.len() + self.list_separator.len() + tail.len());
+                new_value.push(head);
+                new_value.push(&self.list_separator);
+                new_value.push(tail);
+
+                self.set_var(env, key, &new_value);
+            }
+        }
+    }
+
+    pub(crate) fn unset_var(&self, env: &mut dyn Env, key: &OsStr) {
+        if self.override_env {
+            env.remove(key);
+        } else if env.get(key).is_some() && self.debug {
+            eprintln!("{DEBUG_PREFIX}{key:?} is already defined and was NOT unset");
+        }
+    }
+
     #[inline]
     pub(crate) fn set_var_cut_null(&self, env: &mut dyn Env, key: &str, value: &str) {
         let key = key.split('\0').next().unwrap();
@@ -141,6 +596,14 @@ pub enum OptionType {
     Encoding,
     Dialect,
     CommaList,
+    QuoteMode,
+    Seconds,
+    Syntax,
+    FileSecrets,
+    UndefinedVars,
+    Decoders,
+    KeyCase,
+    KeyValidation,
 }
 
 impl std::fmt::Display for OptionType {
@@ -258,6 +721,114 @@ impl<P> Builder<P> {
         self
     }
 
+    #[inline]
+    pub fn dialect_options(mut self, value: DialectOptions) -> Self {
+        self.options.dialect_options = value;
+        self
+    }
+
+    #[inline]
+    pub fn deferred_substitution(mut self, value: bool) -> Self {
+        self.options.deferred_substitution = value;
+        self
+    }
+
+    /// Enable `${scheme:argument}` references for the given
+    /// [resolver](crate::resolver) schemes in the Punktum dialect.
+    #[inline]
+    pub fn resolvers(mut self, value: impl IntoIterator<Item=impl Into<OsString>>) -> Self {
+        self.options.resolvers = value.into_iter().map(Into::into).collect();
+        self
+    }
+
+    #[inline]
+    pub fn file_secrets(mut self, value: FileSecrets) -> Self {
+        self.options.file_secrets = value;
+        self
+    }
+
+    #[inline]
+    pub fn undefined_vars(mut self, value: UndefinedVars) -> Self {
+        self.options.undefined_vars = value;
+        self
+    }
+
+    #[inline]
+    pub fn key_case(mut self, value: KeyCase) -> Self {
+        self.options.key_case = value;
+        self
+    }
+
+    #[inline]
+    pub fn key_validation(mut self, value: KeyValidation) -> Self {
+        self.options.key_validation = value;
+        self
+    }
+
+    #[inline]
+    pub fn list_separator(mut self, value: impl Into<OsString>) -> Self {
+        self.options.list_separator = value.into();
+        self
+    }
+
+    #[inline]
+    pub fn decoders(mut self, value: Decoders) -> Self {
+        self.options.decoders = value;
+        self
+    }
+
+    #[inline]
+    pub fn ruby_legacy_linebreaks(mut self, value: bool) -> Self {
+        self.options.dialect_options.ruby.legacy_linebreaks = value;
+        self
+    }
+
+    /// Enable `$(command)` substitution in the RubyDotenv dialect, but only
+    /// for the given programs.
+    #[inline]
+    pub fn ruby_allow_commands(mut self, value: impl IntoIterator<Item=impl Into<OsString>>) -> Self {
+        self.options.dialect_options.ruby.allow_commands = value.into_iter().map(Into::into).collect();
+        self
+    }
+
+    #[inline]
+    pub fn ruby_command_timeout(mut self, value: Duration) -> Self {
+        self.options.dialect_options.ruby.command_timeout = value;
+        self
+    }
+
+    /// Look up allow-listed programs in these directories instead of the
+    /// `PATH` the options were created with.
+    #[inline]
+    pub fn ruby_command_path(mut self, value: impl Into<OsString>) -> Self {
+        self.options.dialect_options.ruby.command_path = Some(value.into());
+        self
+    }
+
+    #[inline]
+    pub fn composego_strict_substitution(mut self, value: bool) -> Self {
+        self.options.dialect_options.composego.strict_substitution = value;
+        self
+    }
+
+    #[inline]
+    pub fn composego_compat_errors(mut self, value: bool) -> Self {
+        self.options.dialect_options.composego.compat_errors = value;
+        self
+    }
+
+    #[inline]
+    pub fn python_interpolate(mut self, value: bool) -> Self {
+        self.options.dialect_options.python.interpolate = value;
+        self
+    }
+
+    #[inline]
+    pub fn javascript_multiline(mut self, value: bool) -> Self {
+        self.options.dialect_options.javascript.multiline = value;
+        self
+    }
+
     #[inline]
     pub fn options(&self) -> &Options<P> {
         &self.options
@@ -285,6 +856,15 @@ where P: AsRef<Path> {
                 strict: self.options.strict,
                 encoding: self.options.encoding,
                 dialect: self.options.dialect,
+                dialect_options: self.options.dialect_options.clone(),
+                deferred_substitution: self.options.deferred_substitution,
+                resolvers: self.options.resolvers.clone(),
+                file_secrets: self.options.file_secrets,
+                undefined_vars: self.options.undefined_vars,
+                list_separator: self.options.list_separator.clone(),
+                decoders: self.options.decoders,
+                key_case: self.options.key_case,
+                key_validation: self.options.key_validation,
                 path: value,
             }
         }
@@ -328,6 +908,12 @@ where P: AsRef<Path> {
     pub fn config_new(&self) -> Result<HashMap<OsString, OsString>> {
         self.options.config_new()
     }
+
+    #[inline]
+    pub fn config_transaction(self, env: &mut impl Env, parent: &impl GetEnv, paths: &[impl AsRef<Path>]) -> Result<Self> {
+        self.options.config_transaction(env, parent, paths)?;
+        Ok(self)
+    }
 }
 
 impl<P> From<Options<P>> for Builder<P>
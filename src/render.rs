@@ -0,0 +1,183 @@
+// Rendering of arbitrary text templates, like GNU envsubst, but with the
+// variable substitution syntax of one of the substituting dialects.
+use std::{borrow::Cow, ffi::{OsStr, OsString}};
+
+use crate::{dialects::{composego, punktum, ruby_dotenv}, env::GetEnv, error::SourceLocation, options::{IllegalOption, OptionType}, Error, ErrorKind, Result};
+
+/// The variable substitution syntax used by [`render()`].
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Syntax {
+    /// `$VAR`, `${VAR}`, and `${VAR:-default}` etc. like in double quoted
+    /// values of the Punktum dialect. Defaults can be quoted strings.
+    #[default]
+    Punktum,
+    /// The syntax of compose-go (`docker compose`). `$$` is an escaped `$`.
+    ComposeGo,
+    /// The syntax of the Ruby dotenv gem, only `$VAR` and `${VAR}`. `\$` is
+    /// an escaped `$`. Commands are never executed.
+    Ruby,
+}
+
+impl std::fmt::Display for Syntax {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Syntax::Punktum   => "punktum".fmt(f),
+            Syntax::ComposeGo => "composego".fmt(f),
+            Syntax::Ruby      => "ruby".fmt(f),
+        }
+    }
+}
+
+impl TryFrom<&OsStr> for Syntax {
+    type Error = IllegalOption;
+
+    fn try_from(value: &OsStr) -> std::result::Result<Self, Self::Error> {
+        if value.eq_ignore_ascii_case("punktum") {
+            Ok(Syntax::Punktum)
+        } else if value.eq_ignore_ascii_case("composego") {
+            Ok(Syntax::ComposeGo)
+        } else if value.eq_ignore_ascii_case("ruby") {
+            Ok(Syntax::Ruby)
+        } else {
+            Err(IllegalOption::new("syntax".into(), value.into(), OptionType::Syntax))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RenderOptions {
+    pub syntax: Syntax,
+
+    /// Only substitute these variables. References to any other variable are
+    /// copied to the output as they are. All variables are substituted if this
+    /// is `None`.
+    pub vars: Option<Vec<OsString>>,
+
+    /// Fail on references to undefined variables (`$VAR` or `${VAR}`, i.e.
+    /// without a default value) instead of replacing them with an empty string.
+    pub strict: bool,
+}
+
+impl RenderOptions {
+    #[inline]
+    fn is_substituted(&self, name: &str) -> bool {
+        let Some(vars) = &self.vars else {
+            return true;
+        };
+        vars.iter().any(|var| var == name)
+    }
+}
+
+/// Perform the variable substitution of the given syntax over `text`.
+pub fn render(text: &str, env: &dyn GetEnv, options: &RenderOptions) -> Result<String> {
+    let text = prepare(text, env, options)?;
+
+    match options.syntax {
+        Syntax::Punktum   => punktum::render_punktum(&text, env, &|name| options.is_substituted(name)),
+        Syntax::ComposeGo => composego::expand_text(&text, env),
+        Syntax::Ruby      => Ok(ruby_dotenv::substitute_text(&text, env)),
+    }
+}
+
+/// Returns the name of the variable referenced by the `$` at the start of
+/// `src` and if it is a plain reference without default value or similar.
+fn reference(syntax: Syntax, src: &str) -> Option<(&str, bool)> {
+    let tail = &src[1..];
+    let (brace, tail) = match tail.strip_prefix('{') {
+        Some(tail) => (true, tail),
+        None => (false, tail),
+    };
+
+    let len = match syntax {
+        Syntax::Punktum | Syntax::Ruby => tail.find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_').unwrap_or(tail.len()),
+        Syntax::ComposeGo => {
+            if !tail.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_') {
+                return None;
+            }
+            tail.find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_').unwrap_or(tail.len())
+        }
+    };
+
+    if len == 0 {
+        return None;
+    }
+
+    // the closing brace is optional in the Ruby syntax
+    let plain = !brace || syntax == Syntax::Ruby || tail[len..].starts_with('}');
+
+    Some((&tail[..len], plain))
+}
+
+/// Check for undefined variables and escape references to variables that are
+/// not substituted (the Punktum syntax has no escape for `$`, that is handled
+/// in `render_punktum()`).
+fn prepare<'a>(text: &'a str, env: &dyn GetEnv, options: &RenderOptions) -> Result<Cow<'a, str>> {
+    if !options.strict && (options.vars.is_none() || options.syntax == Syntax::Punktum) {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut buf = String::with_capacity(text.len());
+    let mut lineno = 1;
+    let mut line_start = 0;
+    let mut index = 0;
+
+    while let Some(pos) = text[index..].find(['$', '\\', '\n']) {
+        let pos = index + pos;
+        let tail = &text[pos..];
+        buf.push_str(&text[index..pos]);
+
+        if tail.starts_with('\n') {
+            buf.push('\n');
+            lineno += 1;
+            line_start = pos + 1;
+            index = pos + 1;
+            continue;
+        }
+
+        if let Some(escaped) = tail.strip_prefix('\\') {
+            // a backslash escapes any character in the Ruby syntax
+            let len = match (options.syntax, escaped.chars().next()) {
+                (Syntax::Ruby, Some(ch)) => 1 + ch.len_utf8(),
+                _ => 1,
+            };
+            buf.push_str(&tail[..len]);
+            index = pos + len;
+            continue;
+        }
+
+        if options.syntax == Syntax::ComposeGo && tail[1..].starts_with('$') {
+            buf.push_str("$$");
+            index = pos + 2;
+            continue;
+        }
+
+        index = pos + 1;
+        let Some((name, plain)) = reference(options.syntax, tail) else {
+            buf.push('$');
+            continue;
+        };
+
+        if !options.is_substituted(name) {
+            match options.syntax {
+                Syntax::Punktum   => buf.push('$'),
+                Syntax::ComposeGo => buf.push_str("$$"),
+                Syntax::Ruby      => buf.push_str("\\$"),
+            }
+            continue;
+        }
+
+        if options.strict && plain && env.get(name.as_ref()).is_none() {
+            return Err(Error::new(
+                ErrorKind::SubstitutionError,
+                format!("undefined variable {name}"),
+                SourceLocation::new(lineno, pos - line_start + 1)));
+        }
+
+        buf.push('$');
+    }
+
+    buf.push_str(&text[index..]);
+
+    Ok(Cow::Owned(buf))
+}
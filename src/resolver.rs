@@ -0,0 +1,126 @@
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::env::GetEnv;
+
+/// What a [`Resolver`] gets to see of the file that is being parsed.
+pub struct ResolveContext<'a> {
+    /// The variables defined so far.
+    pub env: &'a dyn GetEnv,
+    /// The parent environment, usually the environment of the process.
+    pub parent: &'a dyn GetEnv,
+    /// Path of the `.env` file, `-` for stdin.
+    pub path: &'a Path,
+}
+
+/// Serves `${scheme:argument}` references in the Punktum dialect.
+///
+/// Resolvers are registered under their scheme with [`register_resolver()`]
+/// and are only used if that scheme is enabled in
+/// [`Options::resolvers`](crate::Options::resolvers). Numeric arguments
+/// like `${file:2}` stay substring expressions and never reach a resolver.
+pub trait Resolver: Send + Sync {
+    /// Return the value for `argument`. Errors are reported as a
+    /// `SubstitutionError` at the location of the reference.
+    fn resolve(&self, argument: &str, context: &ResolveContext<'_>) -> std::result::Result<String, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// `${file:PATH}` reads a file, e.g. a Docker secret, and trims white space
+/// from the start and end of its content. Relative paths are relative to the
+/// directory of the `.env` file.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct FileResolver;
+
+impl Resolver for FileResolver {
+    fn resolve(&self, argument: &str, context: &ResolveContext<'_>) -> std::result::Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let path = Path::new(argument);
+        let path = match context.path.parent() {
+            Some(dir) if path.is_relative() && context.path.as_os_str() != "-" => dir.join(path),
+            _ => path.to_path_buf(),
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Ok(content.trim().to_owned()),
+            Err(err) => Err(format!("{}: {err}", path.to_string_lossy()).into()),
+        }
+    }
+}
+
+/// `${env:NAME}` reads a variable from the parent environment without the
+/// need to import it first. Unset variables are an empty string.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct EnvResolver;
+
+impl Resolver for EnvResolver {
+    fn resolve(&self, argument: &str, context: &ResolveContext<'_>) -> std::result::Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(context.parent.get(argument.as_ref())
+            .map(|value| value.to_string_lossy().into_owned())
+            .unwrap_or_default())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct IllegalResolver();
+
+impl std::fmt::Display for IllegalResolver {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        "illegal resolver".fmt(f)
+    }
+}
+
+impl std::error::Error for IllegalResolver {}
+
+const BUILTIN_SCHEMES: &[&str] = &["file", "env"];
+
+static RESOLVER_REGISTRY: RwLock<Vec<(&'static str, Arc<dyn Resolver>)>> = RwLock::new(Vec::new());
+
+#[inline]
+fn is_scheme(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_alphabetic()) &&
+    name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+/// Register a resolver for `${scheme:argument}` references. Schemes are
+/// matched case-sensitively.
+///
+/// Returns an error if the scheme isn't a valid variable name or is one of
+/// the built-in schemes. Registering a scheme a second time replaces the
+/// resolver.
+pub fn register_resolver(scheme: &'static str, resolver: impl Resolver + 'static) -> Result<(), IllegalResolver> {
+    if !is_scheme(scheme) || BUILTIN_SCHEMES.contains(&scheme) {
+        return Err(IllegalResolver());
+    }
+
+    let mut registry = RESOLVER_REGISTRY.write().unwrap_or_else(|err| err.into_inner());
+    let resolver: Arc<dyn Resolver> = Arc::new(resolver);
+    if let Some(entry) = registry.iter_mut().find(|(other, _)| *other == scheme) {
+        *entry = (scheme, resolver);
+    } else {
+        registry.push((scheme, resolver));
+    }
+
+    Ok(())
+}
+
+/// Remove a custom resolver. Returns `true` if it was registered.
+pub fn unregister_resolver(scheme: &str) -> bool {
+    let mut registry = RESOLVER_REGISTRY.write().unwrap_or_else(|err| err.into_inner());
+    let len = registry.len();
+    registry.retain(|(other, _)| *other != scheme);
+    registry.len() != len
+}
+
+/// Get the built-in or registered resolver of a scheme.
+pub fn get_resolver(scheme: &str) -> Option<Arc<dyn Resolver>> {
+    match scheme {
+        "file" => return Some(Arc::new(FileResolver)),
+        "env"  => return Some(Arc::new(EnvResolver)),
+        _ => {}
+    }
+
+    let registry = RESOLVER_REGISTRY.read().unwrap_or_else(|err| err.into_inner());
+    registry.iter().
+        find(|(other, _)| *other == scheme).
+        map(|(_, resolver)| resolver.clone())
+}
@@ -1,3 +1,10 @@
+// Every test crate uses a different subset of these helpers.
+#![allow(dead_code)]
+
+use std::{collections::HashMap, ffi::{OsStr, OsString}, path::{Path, PathBuf}};
+
+use punktum::{env::GetEnv, options::Builder, Env, Result};
+
 #[macro_export]
 macro_rules! assert_env_eq {
     ($env:ident, $fixture:expr) => {
@@ -10,3 +17,69 @@ macro_rules! assert_env_eq {
         }
     };
 }
+
+/// The value of `key`, if it is defined and valid Unicode.
+pub fn get<'a>(env: &'a HashMap<OsString, OsString>, key: &str) -> Option<&'a str> {
+    env.get(OsStr::new(key)).and_then(|value| value.to_str())
+}
+
+/// The keys of `env` in sorted order.
+pub fn keys(env: &HashMap<OsString, OsString>) -> Vec<&str> {
+    let mut keys: Vec<_> = env.keys().filter_map(|key| key.to_str()).collect();
+    keys.sort();
+    keys
+}
+
+/// Load `src` into a new environment, with an empty parent environment.
+#[inline]
+pub fn config<P: AsRef<Path>>(builder: Builder<P>, src: impl AsRef<[u8]>) -> Result<HashMap<OsString, OsString>> {
+    config_with_parent(builder, src, &HashMap::<OsString, OsString>::new())
+}
+
+/// Load `src` into a new environment.
+#[inline]
+pub fn config_with_parent<P: AsRef<Path>>(builder: Builder<P>, src: impl AsRef<[u8]>, parent: &impl GetEnv) -> Result<HashMap<OsString, OsString>> {
+    builder.config_new_with_reader(src.as_ref(), parent)
+}
+
+/// Load `src` into `env`, with an empty parent environment.
+pub fn config_env<P: AsRef<Path>>(builder: Builder<P>, src: impl AsRef<[u8]>, env: &mut impl Env) -> Result<()> {
+    builder.config_with_reader(src.as_ref(), env, &HashMap::<OsString, OsString>::new())?;
+    Ok(())
+}
+
+/// A fresh directory in the temp directory of the system, which is removed
+/// again when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("punktum-test-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    #[inline]
+    pub fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.0.join(name)
+    }
+
+    /// Create the file `name` in this directory.
+    pub fn write(&self, name: impl AsRef<Path>, content: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
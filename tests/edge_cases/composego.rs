@@ -40,3 +40,19 @@ pub const FIXTURE: &[(&str, &str)] = &[
     ("VAR41", "XFOO\" BAR BAZ\" $ $ FOO\" BAR BAZ\"X"),
     ("VAR42", "Y${VAR35} $ \\$ ${VAR35}Y"),
 ];
+
+// Inputs compose-go rejects and the error message it gives for them.
+pub const COMPAT_ERRORS: &[(&str, &str)] = &[
+    ("FOO=${BAR\n", "Invalid template: \"${BAR\""),
+    ("FOO=${BAR:x}\n", "Invalid template: \"${BAR:x}\""),
+    ("FOO=${BAR:}\n", "Invalid template: \"${BAR:}\""),
+    ("FOO=${}\n", "Invalid template: \"${}\""),
+    ("FOO=${1BAR}\n", "Invalid template: \"${1BAR}\""),
+    ("FOO=\"${BAR:-\n}\"\n", "Invalid template: \"${BAR:-\\n}\""),
+    ("FOO=\"${BAR:-${BAZ}\n}\"\n", "Invalid template: \"${BAZ\""),
+    ("FOO=${BAR?}\n", "required variable BAR is missing a value"),
+    ("FOO=${BAR:?must be set}\n", "required variable BAR is missing a value: must be set"),
+    ("FO!O=bar\n", "line 1: unexpected character \"!\" in variable name \"FO!O=bar\""),
+    ("FOO BAR=baz\n", "line 1: key cannot contain a space"),
+    ("A=a\nFOO=\"bar\n", "line 2: unterminated quoted value \"bar"),
+];
@@ -0,0 +1,25 @@
+pub const FIXTURE: &[(&str, &str)] = &[
+    ("VAR1", ""),
+    ("VAR2", "spaces around"),
+    ("VAR3", "value"),
+    ("VAR4", "value#notcomment"),
+    ("VAR5", "multiple   inner   spaces"),
+    ("VAR6", "single quoted $VAR3 \\n"),
+    ("VAR7", "double quoted value value#notcomment"),
+    ("VAR8", "escapes: \\ \" ' \u{7}\u{8}\u{c}\n\r\t\u{b} $VAR3 \\q"),
+    ("VAR9", "octal: AB hex: CD utf8: ä"),
+    ("VAR10", "unicode: ä \u{1f600} \u{1f600}"),
+    ("VAR11", "multi\nline"),
+    ("VAR12", "multi\nline"),
+    ("VAR13", ""),
+    ("VAR14", "prevaluepost"),
+    ("VAR15", ""),
+    ("VAR16", "exported"),
+    ("VAR18", "indented"),
+    ("VAR19", "unquoted \\n backslash"),
+    ("VAR20", "#not a comment"),
+    ("VAR22", "after error"),
+    ("VAR25", "ok"),
+    ("PRE_DEFINED", "not override"),
+    ("VAR_26.DOT", "dotted"),
+];
@@ -0,0 +1,22 @@
+pub const FIXTURE: &[(&str, &str)] = &[
+    ("VAR1", ""),
+    ("VAR2", "spaces around"),
+    ("VAR3", "value"),
+    ("VAR4", "value#notcomment"),
+    ("VAR5", "multiple   inner   spaces"),
+    ("VAR6", "single quoted ${VAR3} \\n"),
+    ("VAR7", "double quoted value $VAR4"),
+    ("VAR8", "escapes: \\ \" ' \n\r\t\u{c}\u{8} ${VAR3} \\q"),
+    ("VAR9", "multi\nline"),
+    ("VAR10", "heredoc value\n  second line \t tab\n"),
+    ("VAR11", "raw heredoc ${VAR3} \\n\n"),
+    ("VAR12", "$VAR3 value ."),
+    ("VAR13", "exported"),
+    ("VAR15", "indented"),
+    ("VAR16", "unquoted \\n \"quotes\" 'kept'"),
+    ("VAR17", "#not a comment"),
+    ("VAR19", "after error"),
+    ("VAR21", "ok"),
+    ("PRE_DEFINED", "not override"),
+    ("VAR_22.DOT", "dotted"),
+];
@@ -3,8 +3,10 @@ pub mod godotenv;
 pub mod java;
 pub mod javascript;
 pub mod nodejs;
+pub mod nodejs20;
 pub mod punktum;
 pub mod python_cli;
 pub mod python;
 pub mod ruby_legacy;
 pub mod ruby;
+pub mod ruby2;
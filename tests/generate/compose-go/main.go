@@ -7,11 +7,76 @@ import (
 	"errors"
 	"os/exec"
 	"log"
+	"strconv"
 	"strings"
 	"github.com/compose-spec/compose-go/v2/dotenv"
 )
 
+// Quote a string as a Rust string literal.
+func rustQuote(str string) string {
+	var buf strings.Builder
+	buf.WriteByte('"')
+	for _, ch := range str {
+		switch ch {
+		case '\n':
+			buf.WriteString("\\n")
+		case '\r':
+			buf.WriteString("\\r")
+		case '\t':
+			buf.WriteString("\\t")
+		case '"':
+			buf.WriteString("\\\"")
+		case '\\':
+			buf.WriteString("\\\\")
+		default:
+			if ch < 0x20 || ch == 0x7F {
+				fmt.Fprintf(&buf, "\\u{%x}", ch)
+			} else {
+				buf.WriteRune(ch)
+			}
+		}
+	}
+	buf.WriteByte('"')
+	return buf.String()
+}
+
+// Print the error message for every input in path as Rust source. Every line
+// of the file is an input as a quoted Go string.
+func dumpCompatErrors(path string) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		log.Fatal(err)
+	}
+
+	fmt.Println()
+	fmt.Println("// Inputs compose-go rejects and the error message it gives for them.")
+	fmt.Println("pub const COMPAT_ERRORS: &[(&str, &str)] = &[")
+	for _, line := range strings.Split(string(data), "\n") {
+		if line == "" {
+			continue
+		}
+
+		src, err := strconv.Unquote(line)
+		if err != nil {
+			log.Fatal(err)
+		}
+
+		_, err = dotenv.UnmarshalBytesWithLookup([]byte(src), os.LookupEnv)
+		if err == nil {
+			log.Fatalf("input was accepted: %s", line)
+		}
+
+		fmt.Printf("    (%s, %s),\n", rustQuote(src), rustQuote(err.Error()))
+	}
+	fmt.Println("];")
+}
+
 func main() {
+	if len(os.Args) == 3 && os.Args[1] == "--compat-errors" {
+		dumpCompatErrors(os.Args[2])
+		return
+	}
+
 	path := os.Getenv("DOTENV_CONFIG_PATH")
 	if path == "" {
 		path = ".env"
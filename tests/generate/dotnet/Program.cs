@@ -0,0 +1,54 @@
+using System.Diagnostics;
+using Sprache;
+
+var path = Environment.GetEnvironmentVariable("DOTENV_CONFIG_PATH");
+if (string.IsNullOrEmpty(path)) {
+    path = ".env";
+}
+
+int index = 0;
+for (; index < args.Length; ++ index) {
+    var arg = args[index];
+    if (arg == "--") {
+        ++ index;
+        break;
+    }
+
+    if (arg == "--file" || arg == "-f") {
+        path = args[index + 1];
+        ++ index;
+    } else if (arg.StartsWith('-')) {
+        throw new ArgumentException("illegal argument: " + arg);
+    } else {
+        break;
+    }
+}
+
+// DotNetEnv rejects the whole file if there is any syntax error. Like with
+// ignoreIfMalformed() of dotenv-java the line of the error is removed instead
+// and the file is parsed again. Already set variables are not clobbered, so
+// they keep the value of the first attempt.
+var lines = File.ReadAllText(path).Split('\n');
+while (true) {
+    try {
+        DotNetEnv.Env.NoClobber().LoadContents(string.Join('\n', lines));
+        break;
+    } catch (ParseException error) {
+        var line = error.Position.Line - 1;
+        if (line >= lines.Length || lines[line].Length == 0) {
+            throw;
+        }
+        lines[line] = "";
+    }
+}
+
+var startInfo = new ProcessStartInfo(args[index]) {
+    UseShellExecute = false,
+};
+foreach (var arg in args[(index + 1)..]) {
+    startInfo.ArgumentList.Add(arg);
+}
+
+using var process = Process.Start(startInfo)!;
+process.WaitForExit();
+return process.ExitCode;
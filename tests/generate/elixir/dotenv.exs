@@ -0,0 +1,62 @@
+Mix.install([{:dotenvy, "0.8.0"}])
+
+defmodule Dotenv do
+  def main(args) do
+    path =
+      case System.get_env("DOTENV_CONFIG_PATH") do
+        nil -> ".env"
+        "" -> ".env"
+        path -> path
+      end
+
+    {path, [prog | prog_args]} = parse_args(args, path)
+
+    lines = path |> File.read!() |> String.split("\n")
+
+    # like source!([path, System.get_env()]), the environment wins
+    env = Map.merge(load(lines), System.get_env())
+
+    {_, status} =
+      System.cmd(System.find_executable(prog), prog_args,
+        env: Map.to_list(env),
+        into: IO.stream(:stdio, :line)
+      )
+
+    System.halt(status)
+  end
+
+  defp parse_args(["--" | rest], path), do: {path, rest}
+  defp parse_args(["--file", path | rest], _path), do: parse_args(rest, path)
+  defp parse_args(["-f", path | rest], _path), do: parse_args(rest, path)
+  defp parse_args(["-" <> _ = arg | _], _path), do: raise(ArgumentError, "illegal argument: #{arg}")
+  defp parse_args(rest, path), do: {path, rest}
+
+  # Dotenvy rejects the whole file if there is any syntax error and doesn't
+  # say where. Like with ignoreIfMalformed() of dotenv-java the line after the
+  # longest prefix of the file that can be parsed is removed instead and the
+  # file is parsed again.
+  defp load(lines) do
+    case parse(lines) do
+      {:ok, vars} ->
+        vars
+
+      {:error, reason} ->
+        count =
+          Enum.find((length(lines) - 1)..0//-1, fn count ->
+            match?({:ok, _}, parse(Enum.take(lines, count)))
+          end)
+
+        if Enum.at(lines, count) == "" do
+          raise RuntimeError, inspect(reason)
+        end
+
+        load(List.replace_at(lines, count, ""))
+    end
+  end
+
+  defp parse(lines) do
+    Dotenvy.Parser.parse(Enum.join(lines, "\n"), System.get_env())
+  end
+end
+
+Dotenv.main(System.argv())
@@ -14,15 +14,22 @@ export PRE_DEFINED="not override"
 PYTHON_DOTENV_CLI=${PYTHON_DOTENV_CLI:-~/.local/bin/dotenv}
 RUBY_DOTENV=${RUBY_DOTENV:-~/.rvm/gems/ruby-3.3.2/bin/dotenv}
 GO_DOTENV=${GO_DOTENV:-godotenv}
+NODE20=${NODE20:-~/.nvm/versions/node/v20.11.1/bin/node}
+RUBY2_DOTENV=${RUBY2_DOTENV:-~/.rvm/gems/ruby-2.7.8/bin/dotenv}
 
 pushd compose-go
 go build -o dotenv
 popd
 
+pushd dotnet
+dotnet build -c Release -o out
+popd
+
 # edge-cases.env
 # ==============
 
 node --env-file=edge-cases.env gen_dotenv.js > ../edge_cases/nodejs.rs
+"$NODE20" --env-file=edge-cases.env gen_dotenv.js > ../edge_cases/nodejs20.rs
 DOTENV_CONFIG_PATH=edge-cases.env node gen_dotenv.js > ../edge_cases/javascript.rs
 
 "$PYTHON_DOTENV_CLI" --dotenv edge-cases.env node dumpenv.js > ../edge_cases/python_cli.rs
@@ -31,12 +38,20 @@ python -m dotenv --file edge-cases.env run --no-override node dumpenv.js > ../ed
 
 "$RUBY_DOTENV" -f edge-cases.env node dumpenv.js > ../edge_cases/ruby.rs
 DOTENV_LINEBREAK_MODE=legacy "$RUBY_DOTENV" -f edge-cases.env node dumpenv.js > ../edge_cases/ruby_legacy.rs
+"$RUBY2_DOTENV" -f edge-cases.env node dumpenv.js > ../edge_cases/ruby2.rs
 
 "$GO_DOTENV" -f edge-cases-godotenv.env node dumpenv.js > ../edge_cases/godotenv.rs
 INHERIT=inherited compose-go/dotenv --file edge-cases-composego.env node dumpenv.js > ../edge_cases/composego.rs
+INHERIT=inherited compose-go/dotenv --compat-errors compat-errors-composego.txt >> ../edge_cases/composego.rs
 
 #dotenvy --file=dotenvy.env node dumpenv.js > ../edge_cases/dotenvy.rs
 
+keys=(VAR1 VAR2 VAR3 VAR4 VAR5 VAR6 VAR7 VAR8 VAR9 VAR10 VAR11 VAR12 VAR13 VAR14 VAR15 VAR16 VAR17 VAR18 VAR19 VAR20 VAR21 VAR22 VAR23 VAR24 VAR25 PRE_DEFINED VAR_26.DOT)
+dotnet/out/dotenv --file edge-cases-dotnet.env node dumpenv.js "${keys[@]}" > ../edge_cases/dotnet.rs
+
+keys=(VAR1 VAR2 VAR3 VAR4 VAR5 VAR6 VAR7 VAR8 VAR9 VAR10 VAR11 VAR12 VAR13 VAR14 VAR15 VAR16 VAR17 VAR18 VAR19 VAR20 VAR21 PRE_DEFINED VAR_22.DOT)
+MIX_QUIET=1 elixir elixir/dotenv.exs --file edge-cases-elixir.env node dumpenv.js "${keys[@]}" > ../edge_cases/elixir.rs
+
 pushd java
 gradle -q run --args="--file ../edge-cases-java.env node ../dumpenv.js" > ../../edge_cases/java.rs
 popd
@@ -74,6 +89,7 @@ popd
 keys=(BASIC BACKSLASH QUOTES SINGLE_QUOTED1 SINGLE_QUOTED2 INVALID_OCT OCT1 OCT2 OCT3 OCT4 HEX UTF16 UTF16_PAIR UTF32_6 UTF32_8 NAMED1 NAMED2 NAMED3 UNKNOWN ESCAPED_NEWLINE)
 
 node --env-file=escapes.env gen_dotenv.js "${keys[@]}" > ../escapes/nodejs.rs
+"$NODE20" --env-file=escapes.env gen_dotenv.js "${keys[@]}" > ../escapes/nodejs20.rs
 
 DOTENV_CONFIG_PATH=escapes.env node gen_dotenv.js "${keys[@]}" > ../escapes/javascript.rs
 "$PYTHON_DOTENV_CLI" --dotenv escapes-python-cli.env node dumpenv.js "${keys[@]}" > ../escapes/python_cli.rs
@@ -82,6 +98,7 @@ python -m dotenv --file escapes.env run --no-override node dumpenv.js "${keys[@]
 
 "$RUBY_DOTENV" -f escapes.env node dumpenv.js "${keys[@]}" > ../escapes/ruby.rs
 DOTENV_LINEBREAK_MODE=legacy "$RUBY_DOTENV" -f escapes.env node dumpenv.js "${keys[@]}" > ../escapes/ruby_legacy.rs
+"$RUBY2_DOTENV" -f escapes.env node dumpenv.js "${keys[@]}" > ../escapes/ruby2.rs
 
 "$GO_DOTENV" -f escapes-godotenv.env node dumpenv.js "${keys[@]}" > ../escapes/godotenv.rs
 compose-go/dotenv --file escapes.env node dumpenv.js "${keys[@]}" > ../escapes/composego.rs
@@ -0,0 +1,81 @@
+mod common;
+
+use std::{collections::HashMap, ffi::OsString};
+
+use common::get;
+use punktum::{build, error::SourceLocation, Dialect, ErrorKind, Result};
+
+fn config(src: &str, override_env: bool) -> Result<HashMap<OsString, OsString>> {
+    let mut parent = HashMap::<OsString, OsString>::new();
+    parent.insert("PATH".into(), "/usr/bin".into());
+    parent.insert("HOME".into(), "/home/user".into());
+
+    let mut env = HashMap::new();
+    env.insert(OsString::from("DEFINED"), OsString::from("old"));
+
+    build().
+        dialect(Dialect::Punktum).
+        override_env(override_env).
+        list_separator(":").
+        config_with_reader(src.as_bytes(), &mut env, &parent)?;
+
+    Ok(env)
+}
+
+#[test]
+fn test_assign_ops() -> Result<()> {
+    let env = config("\
+PATH+=./bin
+PATH ^= /opt/bin
+EMPTY+=
+EMPTY^=x
+NEW+=a
+HOME?=/root
+DEFAULT?=default
+DEFAULT?=other
+A=1
+A+=2
+", true)?;
+
+    assert_eq!(get(&env, "PATH"), Some("/opt/bin:/usr/bin:./bin"));
+    assert_eq!(get(&env, "EMPTY"), Some("x"));
+    assert_eq!(get(&env, "NEW"), Some("a"));
+    assert_eq!(get(&env, "HOME"), None);
+    assert_eq!(get(&env, "DEFAULT"), Some("default"));
+    assert_eq!(get(&env, "A"), Some("1:2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_assign_ops_no_override() -> Result<()> {
+    let env = config("\
+PATH+=./bin
+DEFINED+=new
+A=1
+A+=2
+unset DEFINED A
+", false)?;
+
+    assert_eq!(get(&env, "PATH"), Some("/usr/bin:./bin"));
+    assert_eq!(get(&env, "DEFINED"), Some("old"));
+    assert_eq!(get(&env, "A"), Some("1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_unset() -> Result<()> {
+    let env = config("A=1\nB=2\nC=3\nunset A  DEFINED # comment\nunset=4\n", true)?;
+
+    assert_eq!(get(&env, "A"), None);
+    assert_eq!(get(&env, "B"), Some("2"));
+    assert_eq!(get(&env, "DEFINED"), None);
+    assert_eq!(get(&env, "unset"), Some("4"));
+
+    let error = config("unset A, B\n", true).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(1, 8)));
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::{collections::HashMap, ffi::{OsStr, OsString}, io::{BufRead, Write}, path::Path};
+
+use punktum::{build, build_from, dialect::{get_dialect_parser, register_dialect, unregister_dialect, IllegalDialect}, env::GetEnv, Dialect, DialectParser, Env, Error, Options, Result};
+
+/// Lines of the form `KEY -> VALUE`.
+struct ArrowDialect;
+
+impl DialectParser for ArrowDialect {
+    fn parse(&self, reader: &mut dyn BufRead, env: &mut dyn Env, _parent: &dyn GetEnv, options: &Options<&Path>) -> Result<()> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once("->") else {
+                if options.strict {
+                    return Err(Error::syntax_error(index + 1, 1));
+                }
+                continue;
+            };
+            env.set(key.trim().as_ref(), value.trim().as_ref());
+        }
+        Ok(())
+    }
+
+    fn serialize(&self, writer: &mut dyn Write, key: &str, value: &str) -> std::io::Result<()> {
+        writeln!(writer, "{key} -> {value}")
+    }
+}
+
+struct ParseOnlyDialect;
+
+impl DialectParser for ParseOnlyDialect {
+    fn parse(&self, _reader: &mut dyn BufRead, _env: &mut dyn Env, _parent: &dyn GetEnv, _options: &Options<&Path>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_custom_dialect() -> Result<()> {
+    let dialect = register_dialect("arrow", ArrowDialect).unwrap();
+    assert_eq!(dialect, Dialect::Custom("arrow"));
+    assert_eq!(Dialect::try_from(OsStr::new("ARROW")), Ok(dialect));
+    assert_eq!(Dialect::Custom("Arrow"), dialect);
+    assert_ne!(Dialect::Custom("arrow2"), dialect);
+    assert_eq!(dialect.to_string(), "arrow");
+
+    let mut env = HashMap::<OsString, OsString>::new();
+    build().
+        dialect(dialect).
+        config_with_reader(&b"FOO -> bar baz\nEMPTY ->\n"[..], &mut env, &HashMap::<OsString, OsString>::new())?;
+
+    assert_eq!(env.get(OsStr::new("FOO")).map(|value| value.as_os_str()), Some(OsStr::new("bar baz")));
+    assert_eq!(env.get(OsStr::new("EMPTY")).map(|value| value.as_os_str()), Some(OsStr::new("")));
+
+    let mut buf = Vec::new();
+    dialect.serialize(&mut buf, "FOO", "bar")?;
+    assert_eq!(buf, b"FOO -> bar\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_dialect_from_env() -> Result<()> {
+    register_dialect("arrow-from-env", ArrowDialect).unwrap();
+
+    let mut config = HashMap::<OsString, OsString>::new();
+    config.insert("DOTENV_CONFIG_DIALECT".into(), "Arrow-From-Env".into());
+
+    let builder = build_from(&config)?;
+    assert_eq!(builder.options().dialect, Dialect::Custom("arrow-from-env"));
+
+    let mut env = HashMap::<OsString, OsString>::new();
+    let res = builder.config_with_reader(&b"no arrow here\n"[..], &mut env, &config);
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_dialect_registry() {
+    assert_eq!(register_dialect("punktum", ArrowDialect), Err(IllegalDialect()));
+    assert_eq!(register_dialect("nodejs@20", ArrowDialect), Err(IllegalDialect()));
+    assert_eq!(register_dialect("", ArrowDialect), Err(IllegalDialect()));
+
+    let dialect = register_dialect("parse-only", ParseOnlyDialect).unwrap();
+    let err = dialect.serialize(&mut Vec::new(), "FOO", "bar").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+
+    assert!(get_dialect_parser("parse-only").is_some());
+    assert!(unregister_dialect("Parse-Only"));
+    assert!(get_dialect_parser("parse-only").is_none());
+    assert!(Dialect::try_from(OsStr::new("parse-only")).is_err());
+    assert!(!unregister_dialect("parse-only"));
+
+    let mut buf = Vec::new();
+    Dialect::Punktum.serialize(&mut buf, "FOO", "it's").unwrap();
+    assert_eq!(buf, b"FOO='it'\"'\"'s'\n");
+}
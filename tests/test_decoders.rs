@@ -0,0 +1,68 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::{config, get, TempDir};
+use punktum::{build, decode::{decode_base64, decode_hex, Decoders}, options::Builder, Dialect, ErrorKind, Result};
+
+fn builder(dialect: Dialect, dir: &Path, strict: bool) -> Builder<PathBuf> {
+    build().
+        dialect(dialect).
+        decoders(Decoders::ALL).
+        strict(strict).
+        path(dir.join(".env"))
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(decode_base64("SGVsbG8="), Ok(b"Hello".to_vec()));
+    assert_eq!(decode_base64("SGVsbG8"), Ok(b"Hello".to_vec()));
+    assert_eq!(decode_base64("SGVs\n bG8h"), Ok(b"Hello!".to_vec()));
+    assert_eq!(decode_base64("-_8="), Ok(vec![0xfb, 0xff]));
+    assert_eq!(decode_base64(""), Ok(vec![]));
+    assert!(decode_base64("SGVsbG8h=").is_err());
+    assert!(decode_base64("SGVsb").is_err());
+    assert!(decode_base64("SG*s").is_err());
+
+    assert_eq!(decode_hex("48656C 6c6f"), Ok(b"Hello".to_vec()));
+    assert!(decode_hex("486").is_err());
+    assert!(decode_hex("+f").is_err());
+}
+
+#[test]
+fn test_decoders() -> Result<()> {
+    let dir = TempDir::new("decoders");
+    dir.write("cert.pem", "-----BEGIN CERTIFICATE-----\n");
+
+    let src = "A=base64:SGVsbG8=\nB=hex:48656c6c6f\nC=@cert.pem\nD=plain\n";
+    for dialect in [Dialect::Punktum, Dialect::NodeJS, Dialect::PythonDotenv] {
+        let env = config(builder(dialect, dir.path(), true), src)?;
+        assert_eq!(get(&env, "A"), Some("Hello"));
+        assert_eq!(get(&env, "B"), Some("Hello"));
+        assert_eq!(get(&env, "C"), Some("-----BEGIN CERTIFICATE-----\n"));
+        assert_eq!(get(&env, "D"), Some("plain"));
+    }
+
+    let missing = config(builder(Dialect::Punktum, dir.path(), true), "A=@missing.pem\n");
+    assert_eq!(missing.unwrap_err().kind(), ErrorKind::IOError);
+
+    Ok(())
+}
+
+#[test]
+fn test_decoder_errors() -> Result<()> {
+    let dir = Path::new("");
+
+    let error = config(builder(Dialect::Punktum, dir, true), "A=hex:480065\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::DecodeError);
+    assert!(error.to_string().ends_with(": A: decoded value contains a NUL byte at offset 1"), "{error}");
+
+    let error = config(builder(Dialect::Punktum, dir, true), "A=base64:!!!!\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::DecodeError);
+
+    let env = config(builder(Dialect::Punktum, dir, false), "A=hex:xyz\nB=hex:41\n")?;
+    assert_eq!(get(&env, "A"), Some("hex:xyz"));
+    assert_eq!(get(&env, "B"), Some("A"));
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{config, get, TempDir};
+use punktum::{build, error::SourceLocation, resolver::{register_resolver, ResolveContext, Resolver}, Dialect, ErrorKind, Result};
+
+#[test]
+fn test_forward_references() -> Result<()> {
+    let src = "A=${B}-a\nB=${C}-b\nC=c\nSELF=${SELF}x\nD=${A}\n";
+
+    for dialect in [Dialect::Punktum, Dialect::PythonDotenv, Dialect::ComposeGo, Dialect::RubyDotenv] {
+        let env = config(build().dialect(dialect).deferred_substitution(true), src)?;
+        assert_eq!(get(&env, "A"), Some("c-b-a"), "{dialect}");
+        assert_eq!(get(&env, "B"), Some("c-b"), "{dialect}");
+        assert_eq!(get(&env, "C"), Some("c"), "{dialect}");
+        assert_eq!(get(&env, "SELF"), Some("x"), "{dialect}");
+        assert_eq!(get(&env, "D"), Some("c-b-a"), "{dialect}");
+    }
+
+    // without deferred substitution forward references are empty
+    let env = config(build(), src)?;
+    assert_eq!(get(&env, "A"), Some("-a"));
+
+    Ok(())
+}
+
+#[test]
+fn test_conditional_references() -> Result<()> {
+    // B only references D as long as C is unresolved, so this is no cycle
+    let env = config(build().dialect(Dialect::Punktum).deferred_substitution(true), "B=${C:-$D}\nC=c\nD=$B\n")?;
+    assert_eq!(get(&env, "B"), Some("c"));
+    assert_eq!(get(&env, "D"), Some("c"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cycles() {
+    let src = "X=1\nA=${B}-a\n\n  B=\"$C-b\"\nC=$A\n";
+    let error = config(build().dialect(Dialect::Punktum).deferred_substitution(true), src).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(2, 1)));
+    assert!(error.to_string().ends_with(": cyclic variable references: A (.env:2:1) -> B (.env:4:3) -> C (.env:5:1) -> A (.env:2:1)"), "{error}");
+
+    let error = config(build().dialect(Dialect::ComposeGo).deferred_substitution(true), "A=${B}\nB=${A}\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert!(error.to_string().ends_with(": cyclic variable references: A (.env:1:1) -> B (.env:2:1) -> A (.env:1:1)"), "{error}");
+
+    // lines in multiline values that look like definitions aren't mistaken
+    // for the location of a definition
+    let src = "A=\"\nB=x\n\"\nK<<EOF\nC=y\nEOF\nB=${C}\nC=${B}\n";
+    let error = config(build().dialect(Dialect::Punktum).deferred_substitution(true), src).unwrap_err();
+    assert!(error.to_string().ends_with(": cyclic variable references: B (.env:7:1) -> C (.env:8:1) -> B (.env:7:1)"), "{error}");
+}
+
+#[test]
+fn test_required_forward_references() -> Result<()> {
+    // required variables are only checked once all forward references are resolved
+    for dialect in [Dialect::Punktum, Dialect::ComposeGo] {
+        let builder = || build().dialect(dialect).strict(true).deferred_substitution(true);
+
+        let env = config(builder(), "A=${B:?required}\nB=1\n")?;
+        assert_eq!(get(&env, "A"), Some("1"), "{dialect}");
+
+        let error = config(builder(), "A=${B:?required}\nC=1\n").unwrap_err();
+        assert_eq!(error.location().map(|location| location.lineno()), Some(1), "{dialect}");
+    }
+
+    Ok(())
+}
+
+static RESOLVE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+impl Resolver for Counting {
+    fn resolve(&self, argument: &str, _context: &ResolveContext<'_>) -> std::result::Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        RESOLVE_COUNT.fetch_add(1, Ordering::SeqCst);
+        Ok(argument.to_owned())
+    }
+}
+
+#[test]
+fn test_substitutions_run_once() -> Result<()> {
+    // the file needs multiple passes, but resolvers and commands run only once
+    register_resolver("counting", Counting).unwrap();
+    let builder = build().dialect(Dialect::Punktum).resolvers(["counting"]).debug(true).deferred_substitution(true);
+    let env = config(builder, "A=${counting:hi}\nB=$C\nC=$D\nD=1\n")?;
+    assert_eq!(get(&env, "A"), Some("hi"));
+    assert_eq!(get(&env, "B"), Some("1"));
+    assert_eq!(RESOLVE_COUNT.load(Ordering::SeqCst), 1);
+
+    let dir = TempDir::new("deferred-commands");
+    let log = dir.join("log");
+    let src = format!("A=$(sh -c 'echo x >> {}; echo hi')\nB=$C\nC=$D\nD=1\n", log.display());
+    let builder = build().dialect(Dialect::RubyDotenv).ruby_allow_commands(["sh"]).debug(true).deferred_substitution(true);
+    let env = config(builder, src)?;
+    assert_eq!(get(&env, "A"), Some("hi"));
+    assert_eq!(get(&env, "B"), Some("1"));
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), "x\n");
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use std::{collections::HashMap, ffi::OsString};
+
+use common::{config_with_parent, get};
+use punktum::{build, build_from, options::{Builder, DialectOptions}, Dialect, ErrorKind, Result};
+
+fn config(builder: Builder, src: &str) -> Result<HashMap<OsString, OsString>> {
+    let parent = HashMap::from([(OsString::from("INHERIT"), OsString::from("inherited"))]);
+    config_with_parent(builder, src, &parent)
+}
+
+#[test]
+fn test_dialect_options_from_env() -> Result<()> {
+    let empty = HashMap::<OsString, OsString>::new();
+    let builder = build_from(&empty)?;
+    assert_eq!(builder.options().dialect_options, DialectOptions::default());
+    assert!(builder.options().dialect_options.python.interpolate);
+    assert!(builder.options().dialect_options.javascript.multiline);
+
+    let mut config_env = HashMap::<OsString, OsString>::new();
+    config_env.insert("DOTENV_CONFIG_RUBY_LEGACY_LINEBREAKS".into(), "true".into());
+    config_env.insert("DOTENV_CONFIG_COMPOSEGO_STRICT_SUBSTITUTION".into(), "1".into());
+    config_env.insert("DOTENV_CONFIG_PYTHON_INTERPOLATE".into(), "false".into());
+    config_env.insert("DOTENV_CONFIG_JAVASCRIPT_MULTILINE".into(), "0".into());
+
+    let builder = build_from(&config_env)?;
+    let dialect_options = &builder.options().dialect_options;
+    assert!(dialect_options.ruby.legacy_linebreaks);
+    assert!(dialect_options.composego.strict_substitution);
+    assert!(!dialect_options.python.interpolate);
+    assert!(!dialect_options.javascript.multiline);
+
+    config_env.insert("DOTENV_CONFIG_PYTHON_INTERPOLATE".into(), "nope".into());
+    let err = build_from(&config_env).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OptionsParseError);
+
+    Ok(())
+}
+
+#[test]
+fn test_python_interpolate() -> Result<()> {
+    let src = "FOO=foo\nBAR=${FOO}-${UNSET:-default}\n";
+
+    let env = config(build().dialect(Dialect::PythonDotenv), src)?;
+    assert_eq!(get(&env, "BAR"), Some("foo-default"));
+
+    let env = config(build().dialect(Dialect::PythonDotenv).python_interpolate(false), src)?;
+    assert_eq!(get(&env, "BAR"), Some("${FOO}-${UNSET:-default}"));
+
+    Ok(())
+}
+
+#[test]
+fn test_javascript_multiline() -> Result<()> {
+    let src = "FOO=\"multi\nline\"\nBAR=bar\n";
+
+    let env = config(build().dialect(Dialect::JavaScriptDotenv), src)?;
+    assert_eq!(get(&env, "FOO"), Some("multi\nline"));
+    assert_eq!(get(&env, "BAR"), Some("bar"));
+
+    let env = config(build().dialect(Dialect::JavaScriptDotenv).strict(false).javascript_multiline(false), src)?;
+    assert_eq!(get(&env, "FOO"), Some("\"multi"));
+    assert_eq!(get(&env, "BAR"), Some("bar"));
+
+    Ok(())
+}
+
+#[test]
+fn test_composego_strict_substitution() -> Result<()> {
+    let src = "FOO=${BAR\nBAZ=baz\n";
+
+    let env = config(build().dialect(Dialect::ComposeGo).strict(false), src)?;
+    assert_eq!(get(&env, "FOO"), Some("${BAR"));
+    assert_eq!(get(&env, "BAZ"), Some("baz"));
+
+    let env = config(build().dialect(Dialect::ComposeGo).strict(false).composego_strict_substitution(true), src)?;
+    assert_eq!(get(&env, "FOO"), None);
+    assert_eq!(get(&env, "BAZ"), Some("baz"));
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::ffi::OsStr;
+
+use common::{config, get};
+use punktum::{build, Dialect, Result};
+
+// There are no generated fixtures for the old versions, the expected values
+// are derived from the sources of NodeJS v20.6.0 (src/node_dotenv.cc) and
+// the dotenv gem v2.7.6 (lib/dotenv/parser.rb).
+
+#[test]
+fn test_dialect_versions() {
+    let dialect = |name: &str| Dialect::try_from(OsStr::new(name)).ok();
+
+    assert_eq!(dialect("nodejs@22"),          Some(Dialect::NodeJS));
+    assert_eq!(dialect("nodejs@20"),          Some(Dialect::NodeJS20));
+    assert_eq!(dialect("NodeJS@20.11.1"),     Some(Dialect::NodeJS20));
+    assert_eq!(dialect("nodejs@20.6"),        Some(Dialect::NodeJS20));
+    assert_eq!(dialect("nodejs@20.12"),       Some(Dialect::NodeJS));
+    assert_eq!(dialect("nodejs@21"),          Some(Dialect::NodeJS20));
+    assert_eq!(dialect("nodejs@21.6"),        Some(Dialect::NodeJS20));
+    assert_eq!(dialect("nodejs@v21.7.0"),     Some(Dialect::NodeJS));
+    assert_eq!(dialect("nodejs@18"),          None);
+    assert_eq!(dialect("nodejs@20.0"),        None);
+    assert_eq!(dialect("nodejs@20.5.1"),      None);
+    assert_eq!(dialect("ruby-dotenv@2"),      Some(Dialect::RubyDotenv2));
+    assert_eq!(dialect("ruby-dotenv@2.7"),    Some(Dialect::RubyDotenv2));
+    assert_eq!(dialect("ruby-dotenv@2.8"),    Some(Dialect::RubyDotenv));
+    assert_eq!(dialect("ruby-dotenv@3"),      Some(Dialect::RubyDotenv));
+    assert_eq!(dialect("ruby-dotenv@4"),      None);
+    assert_eq!(dialect("NodeJS20"),           Some(Dialect::NodeJS20));
+    assert_eq!(dialect("RubyDotenv2"),        Some(Dialect::RubyDotenv2));
+    assert_eq!(dialect("python-dotenv@1"),    None);
+    assert_eq!(dialect("punktum@1"),          None);
+    assert_eq!(dialect("nodejs@"),            None);
+    assert_eq!(dialect("nodejs@20.x"),        None);
+}
+
+#[test]
+fn test_nodejs20_quotes() -> Result<()> {
+    // NodeJS 20 removes any quote from the start and end of a value, even if
+    // they don't match. The dotenv npm package only removes matching quotes.
+    let src = "C='abc'\nD=  abc\t\nA=`abc'\nB='abc\"  \n";
+
+    let env = config(build().dialect(Dialect::NodeJS20), src)?;
+    assert_eq!(get(&env, "A"), Some("abc"));
+    assert_eq!(get(&env, "B"), Some("abc"));
+    assert_eq!(get(&env, "C"), Some("abc"));
+    assert_eq!(get(&env, "D"), Some("abc"));
+
+    let env = config(build().dialect(Dialect::JavaScriptDotenv), src)?;
+    assert_eq!(get(&env, "A"), Some("`abc'"));
+    assert_eq!(get(&env, "B"), Some("'abc\""));
+    assert_eq!(get(&env, "C"), Some("abc"));
+    assert_eq!(get(&env, "D"), Some("abc"));
+
+    Ok(())
+}
+
+#[test]
+fn test_ruby_dotenv2_linebreaks() -> Result<()> {
+    // Before 2.8 the dotenv gem always expanded "\n" and "\r" in double quoted
+    // values, no matter what DOTENV_LINEBREAK_MODE says.
+    let src = "DOTENV_LINEBREAK_MODE=strict\nA=\"a\\nb\\rc\"\nB='a\\nb'\n";
+
+    let env = config(build().dialect(Dialect::RubyDotenv2), src)?;
+    assert_eq!(get(&env, "A"), Some("a\nb\rc"));
+    assert_eq!(get(&env, "B"), Some("a\\nb"));
+
+    let env = config(build().dialect(Dialect::RubyDotenv), src)?;
+    assert_eq!(get(&env, "A"), Some("a\\nb\\rc"));
+    assert_eq!(get(&env, "B"), Some("a\\nb"));
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ mod common;
 
 use std::{collections::HashMap, ffi::{OsStr, OsString}};
 
-use punktum::{self, build, Dialect, Result};
+use punktum::{self, build, Dialect, ErrorKind, Result};
 
 const EDGE_CASES_PATH: &str = "tests/generate/edge-cases.env";
 
@@ -57,6 +57,111 @@ fn test_edge_cases_composego() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_composego_compat_errors() -> Result<()> {
+    // everything compose-go accepts is still accepted
+    let mut env = HashMap::<OsString, OsString>::new();
+    env.insert(OsString::from("PRE_DEFINED"), OsString::from("not override"));
+    let mut parent = HashMap::new();
+    parent.insert(OsString::from("INHERIT"), OsString::from("inherited"));
+
+    build().
+        strict(false).
+        composego_compat_errors(true).
+        dialect(Dialect::ComposeGo).
+        path("tests/generate/edge-cases-composego.env").
+        config_with_parent(&mut env, &parent)?;
+
+    assert_env_eq!(env, edge_cases::composego::FIXTURE);
+
+    for (src, message) in edge_cases::composego::COMPAT_ERRORS {
+        let error = build().
+            strict(false).
+            composego_compat_errors(true).
+            dialect(Dialect::ComposeGo).
+            config_new_with_reader(src.as_bytes(), &parent).
+            expect_err(src);
+
+        let error = error.to_string();
+        assert!(error.ends_with(&format!(": {message}")), "{src:?}: {error}");
+    }
+
+    // without compat errors malformed substitutions stay syntax errors in strict mode
+    for src in ["FOO=${BAR:x}\n", "FOO=${BAR?}\n"] {
+        let error = build().
+            strict(true).
+            dialect(Dialect::ComposeGo).
+            config_new_with_reader(src.as_bytes(), &parent).
+            expect_err(src);
+        assert_eq!(error.kind(), ErrorKind::SyntaxError, "{src:?}");
+
+        let error = build().
+            strict(true).
+            composego_compat_errors(true).
+            dialect(Dialect::ComposeGo).
+            config_new_with_reader(src.as_bytes(), &parent).
+            expect_err(src);
+        assert_eq!(error.kind(), ErrorKind::SubstitutionError, "{src:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_edge_cases_dotnet() -> Result<()> {
+    // DotNetEnv fails loudly with a syntax error in most cases of edge-cases.env, so I use a different file.
+    assert_edge_cases!(edge_cases::dotnet::FIXTURE, Dialect::DotNetEnv, "tests/generate/edge-cases-dotnet.env");
+    Ok(())
+}
+
+#[test]
+fn test_edge_cases_dotnet_strict() {
+    let mut env = HashMap::<OsString, OsString>::new();
+    let error = build().
+        dialect(Dialect::DotNetEnv).
+        path("tests/generate/edge-cases-dotnet.env").
+        config_env(&mut env).
+        expect_err("quote in unquoted value is expected to be a syntax error");
+
+    assert_eq!(error.kind(), punktum::ErrorKind::SyntaxError);
+    assert_eq!(*error.location(), Some(punktum::error::SourceLocation::new(23, 8)));
+}
+
+#[test]
+fn test_edge_cases_elixir() -> Result<()> {
+    // Dotenvy fails loudly with a syntax error in most cases of edge-cases.env, so I use a different file.
+    assert_edge_cases!(edge_cases::elixir::FIXTURE, Dialect::ElixirDotenvy, "tests/generate/edge-cases-elixir.env");
+    Ok(())
+}
+
+#[test]
+fn test_edge_cases_elixir_strict() {
+    let mut env = HashMap::<OsString, OsString>::new();
+    let error = build().
+        dialect(Dialect::ElixirDotenvy).
+        path("tests/generate/edge-cases-elixir.env").
+        config_env(&mut env).
+        expect_err("unterminated variable reference is expected to be a syntax error");
+
+    assert_eq!(error.kind(), punktum::ErrorKind::SyntaxError);
+    assert_eq!(*error.location(), Some(punktum::error::SourceLocation::new(24, 8)));
+}
+
+#[test]
+fn test_dotnet_elixir_parent() -> Result<()> {
+    let parent = HashMap::from([(OsString::from("INHERIT"), OsString::from("inherited"))]);
+
+    let env = common::config_with_parent(build().dialect(Dialect::DotNetEnv).strict(true), "A=$INHERIT\nINHERIT=own\nB=${INHERIT}\n", &parent)?;
+    assert_eq!(common::get(&env, "A"), Some("inherited"));
+    assert_eq!(common::get(&env, "B"), Some("own"));
+
+    let env = common::config_with_parent(build().dialect(Dialect::ElixirDotenvy).strict(true), "A=${INHERIT}\nINHERIT=own\nB=${INHERIT}\n", &parent)?;
+    assert_eq!(common::get(&env, "A"), Some("inherited"));
+    assert_eq!(common::get(&env, "B"), Some("own"));
+
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_java() -> Result<()> {
     // Java dotenv crashes (StringIndexOutOfBoundsException) in some cases of edge-cases.env, so I use a more limited version.
@@ -76,6 +181,14 @@ fn test_edge_cases_nodejs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_nodejs20() -> Result<()> {
+    let dialect = Dialect::try_from(OsStr::new("nodejs@20")).expect("nodejs@20 is a valid dialect");
+    assert_eq!(dialect, Dialect::NodeJS20);
+    assert_edge_cases!(edge_cases::nodejs20::FIXTURE, dialect);
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_punktum() -> Result<()> {
     assert_edge_cases!(edge_cases::punktum::FIXTURE, Dialect::Punktum);
@@ -103,6 +216,33 @@ fn test_edge_cases_ruby_legacy() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_ruby_legacy_option() -> Result<()> {
+    let mut env = HashMap::<OsString, OsString>::new();
+    env.insert(OsString::from("PRE_DEFINED"), OsString::from("not override"));
+
+    let mut parent = HashMap::new();
+    parent.insert(OsString::from("INHERIT"), OsString::from("inherited"));
+
+    build().
+        strict(false).
+        dialect(Dialect::RubyDotenv).
+        ruby_legacy_linebreaks(true).
+        path(EDGE_CASES_PATH).
+        config_with_parent(&mut env, &parent)?;
+
+    assert_env_eq!(env, edge_cases::ruby_legacy::FIXTURE);
+    Ok(())
+}
+
+#[test]
+fn test_edge_cases_ruby2() -> Result<()> {
+    let dialect = Dialect::try_from(OsStr::new("ruby-dotenv@2")).expect("ruby-dotenv@2 is a valid dialect");
+    assert_eq!(dialect, Dialect::RubyDotenv2);
+    assert_edge_cases!(edge_cases::ruby2::FIXTURE, dialect);
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_ruby() -> Result<()> {
     assert_edge_cases!(edge_cases::ruby::FIXTURE, Dialect::RubyDotenv);
@@ -0,0 +1,116 @@
+mod common;
+
+use std::{collections::HashMap, ffi::{OsStr, OsString}, io::{BufRead, Read}, path::Path};
+
+use common::{config, get};
+use punktum::{build, dialects::punktum::config_punktum, env::EmptyEnv, options::Builder, Dialect, Encoding, Result};
+
+fn utf16le(src: &str) -> Vec<u8> {
+    src.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn utf16be(src: &str) -> Vec<u8> {
+    src.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+fn utf32le(src: &str) -> Vec<u8> {
+    src.chars().flat_map(|ch| (ch as u32).to_le_bytes()).collect()
+}
+
+fn with_bom(bom: &[u8], bytes: Vec<u8>) -> Vec<u8> {
+    [bom.to_vec(), bytes].concat()
+}
+
+fn builder(dialect: Dialect, encoding: Encoding) -> Builder {
+    build().dialect(dialect).encoding(encoding).strict(true)
+}
+
+const SRC: &str = "FOO=bär\nBAR=${FOO}2\n";
+
+#[test]
+fn test_encoding_option() {
+    assert_eq!(Encoding::try_from(OsStr::new("AUTO")).unwrap(), Encoding::Auto);
+    assert_eq!(Encoding::Auto.to_string(), "auto");
+    assert_eq!(Encoding::UTF16LE.to_string(), "UTF-16LE");
+    assert_eq!("utf-32be".parse::<Encoding>().unwrap(), Encoding::UTF32BE);
+}
+
+#[test]
+fn test_sniff() {
+    assert_eq!(Encoding::sniff(b""), Encoding::UTF8);
+    assert_eq!(Encoding::sniff(SRC.as_bytes()), Encoding::UTF8);
+    assert_eq!(Encoding::sniff(&with_bom(&[0xEF, 0xBB, 0xBF], SRC.into())), Encoding::UTF8);
+    assert_eq!(Encoding::sniff(&with_bom(&[0xFF, 0xFE], utf16le(SRC))), Encoding::UTF16LE);
+    assert_eq!(Encoding::sniff(&with_bom(&[0xFE, 0xFF], utf16be(SRC))), Encoding::UTF16BE);
+    assert_eq!(Encoding::sniff(&with_bom(&[0xFF, 0xFE, 0, 0], utf32le(SRC))), Encoding::UTF32LE);
+    assert_eq!(Encoding::sniff(&[0, 0, 0xFE, 0xFF, 0, 0, 0, b'A']), Encoding::UTF32BE);
+
+    // without a BOM
+    assert_eq!(Encoding::sniff(&utf16le(SRC)), Encoding::UTF16LE);
+    assert_eq!(Encoding::sniff(&utf16be(SRC)), Encoding::UTF16BE);
+
+    // a few null bytes don't make it UTF-16, e.g. the binary dialect
+    assert_eq!(Encoding::sniff(b"FOO=bar\0BAZ=egg\0"), Encoding::UTF8);
+}
+
+#[test]
+fn test_resolve() -> std::io::Result<()> {
+    let bytes = with_bom(&[0xFF, 0xFE], utf16le("A=1\n"));
+    let mut reader = &bytes[..];
+    assert_eq!(Encoding::detect(&mut reader)?, Encoding::UTF16LE);
+    assert_eq!(reader, &utf16le("A=1\n")[..]);
+
+    // an explicit encoding only skips its own BOM
+    let mut reader = &bytes[..];
+    assert_eq!(Encoding::UTF8.resolve(&mut reader)?, Encoding::UTF8);
+    assert_eq!(reader.fill_buf()?.len(), bytes.len());
+
+    let mut reader = &b"\xEF\xBB\xBFA=1\n"[..];
+    assert_eq!(Encoding::UTF8.resolve(&mut reader)?, Encoding::UTF8);
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest)?;
+    assert_eq!(rest, "A=1\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_auto() -> Result<()> {
+    let sources = [
+        with_bom(&[0xEF, 0xBB, 0xBF], SRC.into()),
+        SRC.into(),
+        with_bom(&[0xFF, 0xFE], utf16le(SRC)),
+        utf16le(SRC),
+        utf16be(SRC),
+        with_bom(&[0xFF, 0xFE, 0, 0], utf32le(SRC)),
+    ];
+
+    for src in &sources {
+        for dialect in [Dialect::Punktum, Dialect::JavaScriptDotenv, Dialect::PythonDotenv] {
+            let env = config(builder(dialect, Encoding::Auto), src)?;
+            assert_eq!(get(&env, "FOO"), Some("bär"), "{dialect} {src:?}");
+        }
+        let env = config(builder(Dialect::Punktum, Encoding::Auto), src)?;
+        assert_eq!(get(&env, "BAR"), Some("bär2"));
+
+        // calling the dialect directly detects the encoding too
+        let options = builder(Dialect::Punktum, Encoding::Auto).path(Path::new("-")).into_options();
+        let mut env = HashMap::<OsString, OsString>::new();
+        config_punktum(&mut &src[..], &mut env, &EmptyEnv::new(), &options)?;
+        assert_eq!(get(&env, "FOO"), Some("bär"), "{src:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_explicit_strips_bom() -> Result<()> {
+    let env = config(builder(Dialect::Punktum, Encoding::UTF8), with_bom(&[0xEF, 0xBB, 0xBF], SRC.into()))?;
+    assert_eq!(env.len(), 2);
+    assert_eq!(get(&env, "FOO"), Some("bär"));
+
+    let env = config(builder(Dialect::RubyDotenv, Encoding::UTF16LE), with_bom(&[0xFF, 0xFE], utf16le(SRC)))?;
+    assert_eq!(get(&env, "FOO"), Some("bär"));
+
+    Ok(())
+}
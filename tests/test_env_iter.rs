@@ -0,0 +1,65 @@
+use std::{collections::{BTreeMap, HashMap}, ffi::{OsStr, OsString}};
+
+use punktum::{env::{AllowListEnv, DenyListEnv, EmptyEnv, GetEnv}, system_env, EnvWrite};
+
+fn collect(env: &dyn GetEnv) -> BTreeMap<OsString, OsString> {
+    let mut vars = BTreeMap::new();
+    env.for_each(&mut |key, value| {
+        assert_eq!(env.get(key).as_deref(), Some(value));
+        vars.insert(key.to_owned(), value.to_owned());
+    });
+    vars
+}
+
+#[test]
+fn test_for_each() {
+    let mut env = HashMap::<OsString, OsString>::new();
+    env.insert("A".into(), "1".into());
+    env.insert("B".into(), "2".into());
+
+    let expected: BTreeMap<_, _> = env.clone().into_iter().collect();
+    assert_eq!(collect(&env), expected);
+    assert_eq!(collect(&&env), expected);
+    assert_eq!(collect(&AllowListEnv::from_slice(&env, &["A"])), expected);
+    assert_eq!(collect(&DenyListEnv::from_slice(&env, &["A"])), expected);
+
+    let mut lossy = HashMap::<String, String>::new();
+    lossy.insert("A".into(), "1".into());
+    lossy.insert("B".into(), "2".into());
+    assert_eq!(collect(&lossy), expected);
+
+    assert!(collect(&EmptyEnv::new()).is_empty());
+
+    let system: BTreeMap<_, _> = std::env::vars_os().collect();
+    assert_eq!(collect(&system_env()).len(), system.len());
+}
+
+#[test]
+fn test_write_env() {
+    let mut env = HashMap::<OsString, OsString>::new();
+    env.insert("A".into(), "it's".into());
+
+    let mut out = Vec::new();
+    (&env as &dyn GetEnv).write_env(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "A='it'\"'\"'s'\n");
+
+    let mut out = Vec::new();
+    let allow_list = [OsStr::new("A")];
+    AllowListEnv::from_slice(&env, &allow_list).write_env(&mut out).unwrap();
+    assert_eq!(out, b"A='it'\"'\"'s'\n");
+
+    let mut out = Vec::new();
+    punktum::write_get_env(&mut out, &env).unwrap();
+    assert_eq!(out, b"A='it'\"'\"'s'\n");
+
+    // maps that aren't a GetEnv
+    let mut out = Vec::new();
+    let env = HashMap::from([("A", "it's")]);
+    env.write_env(&mut out).unwrap();
+    assert_eq!(out, b"A='it'\"'\"'s'\n");
+
+    let mut out = Vec::new();
+    let env = HashMap::from([("A".to_string(), "it's".to_string())]);
+    env.write_env(&mut out).unwrap();
+    assert_eq!(out, b"A='it'\"'\"'s'\n");
+}
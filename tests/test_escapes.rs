@@ -71,6 +71,12 @@ fn test_edge_cases_nodejs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_nodejs20() -> Result<()> {
+    assert_escapes!(escapes::nodejs20::FIXTURE, Dialect::NodeJS20);
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_punktum() -> Result<()> {
     assert_escapes!(escapes::punktum::FIXTURE, Dialect::Punktum);
@@ -97,6 +103,12 @@ fn test_edge_cases_ruby_legacy() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_edge_cases_ruby2() -> Result<()> {
+    assert_escapes!(escapes::ruby2::FIXTURE, Dialect::RubyDotenv2);
+    Ok(())
+}
+
 #[test]
 fn test_edge_cases_ruby() -> Result<()> {
     assert_escapes!(escapes::ruby::FIXTURE, Dialect::RubyDotenv);
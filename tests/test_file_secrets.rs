@@ -0,0 +1,75 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{config, get, TempDir};
+use punktum::{build, file_secrets::FileSecrets, options::Builder, Dialect, ErrorKind, Result};
+
+fn builder(dir: &TempDir, file_secrets: FileSecrets, strict: bool) -> Builder<PathBuf> {
+    build().
+        dialect(Dialect::Punktum).
+        file_secrets(file_secrets).
+        strict(strict).
+        path(dir.join(".env"))
+}
+
+fn temp_dir(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    dir.write("secret", " s3cr3t \n");
+    dir
+}
+
+#[test]
+fn test_file_secrets() -> Result<()> {
+    let dir = temp_dir("file-secrets");
+    let src = "DB_PASSWORD_FILE=secret\nOTHER=x\n";
+
+    let env = config(builder(&dir, FileSecrets::Raw, true), src)?;
+    let env_newline = config(builder(&dir, FileSecrets::TrimNewline, true), src);
+    let env_trim = config(builder(&dir, FileSecrets::Trim, true), src);
+    let env_disabled = config(builder(&dir, FileSecrets::Disabled, true), src);
+
+    assert_eq!(get(&env, "DB_PASSWORD"), Some(" s3cr3t \n"));
+    assert_eq!(get(&env, "DB_PASSWORD_FILE"), Some("secret"));
+    assert_eq!(get(&env_newline?, "DB_PASSWORD"), Some(" s3cr3t "));
+    assert_eq!(get(&env_trim?, "DB_PASSWORD"), Some("s3cr3t"));
+    assert_eq!(get(&env_disabled?, "DB_PASSWORD"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_file_secrets_errors() -> Result<()> {
+    let dir = temp_dir("file-secrets-errors");
+
+    let explicit = config(builder(&dir, FileSecrets::Trim, true), "DB_PASSWORD=explicit\nDB_PASSWORD_FILE=secret\n");
+    let explicit_non_strict = config(builder(&dir, FileSecrets::Trim, false), "DB_PASSWORD=explicit\nDB_PASSWORD_FILE=secret\n");
+    let missing = config(builder(&dir, FileSecrets::Trim, true), "DB_PASSWORD_FILE=missing\n");
+    let missing_non_strict = config(builder(&dir, FileSecrets::Trim, false), "DB_PASSWORD_FILE=missing\nOTHER=x\n");
+
+    #[cfg(unix)]
+    let insecure = {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("secret");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o666)).unwrap();
+        config(builder(&dir, FileSecrets::Trim, true), "DB_PASSWORD_FILE=secret\n")
+    };
+
+    // an explicit definition is no error, even in strict mode
+    assert_eq!(get(&explicit?, "DB_PASSWORD"), Some("explicit"));
+    assert_eq!(get(&explicit_non_strict?, "DB_PASSWORD"), Some("explicit"));
+
+    assert_eq!(missing.unwrap_err().kind(), ErrorKind::IOError);
+    let env = missing_non_strict?;
+    assert_eq!(get(&env, "DB_PASSWORD"), None);
+    assert_eq!(get(&env, "OTHER"), Some("x"));
+
+    #[cfg(unix)]
+    {
+        let error = insecure.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::IOError);
+        assert!(error.to_string().contains("writable by group or others"), "{error}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{config, get};
+use punktum::{build, error::SourceLocation, Dialect, ErrorKind, Result};
+
+#[test]
+fn test_heredoc() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), "\
+NAME=world
+A<<EOF
+hello $NAME
+  \\$NAME \\\\ \\n ${NAME^}
+
+EOF
+B<<'EOF'
+hello $NAME \\$
+EOF
+C<<\"EOF\" # comment
+EOF
+D = after
+")?;
+
+    assert_eq!(get(&env, "A"), Some("hello world\n  $NAME \\ \\n World\n"));
+    assert_eq!(get(&env, "B"), Some("hello $NAME \\$"));
+    assert_eq!(get(&env, "C"), Some(""));
+    assert_eq!(get(&env, "D"), Some("after"));
+
+    Ok(())
+}
+
+#[test]
+fn test_heredoc_strip_indent() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), "\
+NAME=world
+A<<-EOF
+    hello
+      $NAME
+
+    end
+    EOF
+B <<- 'EOF'
+\t\tfoo
+\tbar
+\t\tEOF
+")?;
+
+    assert_eq!(get(&env, "A"), Some("hello\n  world\n\nend"));
+    assert_eq!(get(&env, "B"), Some("\tfoo\nbar"));
+
+    Ok(())
+}
+
+#[test]
+fn test_heredoc_errors() {
+    let error = config(build().dialect(Dialect::Punktum), "A=1\n\nB  <<EOF\nfoo\nEOFX\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(3, 4)));
+    assert!(error.to_string().ends_with(": unterminated heredoc, expected EOF"), "{error}");
+
+    let error = config(build().dialect(Dialect::Punktum), "A<<EOF junk\nEOF\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(1, 8)));
+
+    let error = config(build().dialect(Dialect::Punktum), "A<<'EOF\nEOF\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+
+    // substitutions don't read past their line, errors are on that line
+    let error = config(build().dialect(Dialect::Punktum).strict(true), "K<<EOF\n${X:-\"a\nb\"}\nEOF\nL=1\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+    assert_eq!(error.location().map(|location| location.lineno()), Some(2));
+
+    let error = config(build().dialect(Dialect::Punktum).strict(true), "K<<EOF\n${X:-\"a}\nEOF\nL=1\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+    assert_eq!(error.location().map(|location| location.lineno()), Some(2));
+}
+
+#[test]
+fn test_heredoc_errors_non_strict() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum).strict(false), "K<<EOF\n${X:-\"a\nb\"}\nEOF\nL=1\n")?;
+    assert_eq!(get(&env, "K"), Some("a\n\nb\"}"));
+    assert_eq!(get(&env, "L"), Some("1"));
+
+    let env = config(build().dialect(Dialect::Punktum).strict(false), "K<<EOF\n${X:-\"a}\nEOF\nL=1\n")?;
+    assert_eq!(get(&env, "K"), Some("a}\n"));
+    assert_eq!(get(&env, "L"), Some("1"));
+
+    // only the line of an unterminated heredoc is skipped
+    let env = config(build().dialect(Dialect::Punktum).strict(false), "A=1\nK<<EOF\nabc\nL=1\nM<<END\n$L\nEND\n")?;
+    assert_eq!(get(&env, "A"), Some("1"));
+    assert_eq!(get(&env, "K"), None);
+    assert_eq!(get(&env, "L"), Some("1"));
+    assert_eq!(get(&env, "M"), Some("1"));
+
+    Ok(())
+}
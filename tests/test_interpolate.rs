@@ -0,0 +1,59 @@
+use std::{collections::HashMap, ffi::OsString};
+
+use punktum::{dialects::composego::{interpolate, interpolate_with_unresolved}, ErrorKind, Result};
+
+fn env() -> HashMap<OsString, OsString> {
+    let mut env = HashMap::new();
+    env.insert(OsString::from("TAG"), OsString::from("1.2"));
+    env.insert(OsString::from("EMPTY"), OsString::from(""));
+    env
+}
+
+#[test]
+fn test_interpolate() -> Result<()> {
+    let env = env();
+
+    assert_eq!(interpolate("image: nginx:${TAG:-latest}\n", &env)?, "image: nginx:1.2\n");
+    assert_eq!(interpolate("x: $$TAG $$$TAG\r\n", &env)?, "x: $TAG $1.2\r\n");
+    assert_eq!(interpolate("x: ${EMPTY:-${UNSET-${TAG}}}", &env)?, "x: 1.2");
+    assert_eq!(interpolate("x: ${EMPTY-default} ${EMPTY:+set} ${TAG:+set}", &env)?, "x:   set");
+    assert_eq!(interpolate("  # ${UNSET:?not in comments}\n", &env)?, "  # ${UNSET:?not in comments}\n");
+    assert_eq!(interpolate("image: nginx:$TAG # ${UNSET:?not in comments}\n", &env)?, "image: nginx:1.2 # ${UNSET:?not in comments}\n");
+    assert_eq!(interpolate("- \"a # ${TAG}\" # ${UNSET:?}\n", &env)?, "- \"a # 1.2\" # ${UNSET:?}\n");
+    assert_eq!(interpolate("'${TAG}': '${TAG}'\n", &env)?, "'${TAG}': '1.2'\n");
+    assert_eq!(interpolate("x: \"a\n  ${TAG}\"\n", &env)?, "x: \"a\n  1.2\"\n");
+
+    // lines of block scalars are content, not comments or keys
+    let src = "command: | # ${UNSET:?}\n  # $TAG\n  key: $TAG\n\n  x\nnext: $TAG\n";
+    assert_eq!(interpolate(src, &env)?, "command: | # ${UNSET:?}\n  # 1.2\n  key: 1.2\n\n  x\nnext: 1.2\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_interpolate_unresolved() -> Result<()> {
+    let env = env();
+
+    let (text, unresolved) = interpolate_with_unresolved("a: $TAG\nb: ${UNSET} $OTHER\nc: ${DEFAULT:-x}\n", &env)?;
+    assert_eq!(text, "a: 1.2\nb:  \nc: x\n");
+    assert_eq!(unresolved, [("UNSET".to_owned(), 2), ("OTHER".to_owned(), 2)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_interpolate_errors() {
+    let env = env();
+
+    let error = interpolate("a: b\nimage: ${EMPTY:?must be set}\n", &env).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert_eq!(*error.location(), Some(punktum::error::SourceLocation::new(2, 1)));
+    assert!(error.to_string().ends_with(": required variable EMPTY is missing a value: must be set"), "{error}");
+
+    let error = interpolate("image: ${TAG\n", &env).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert!(error.to_string().ends_with(": Invalid template: \"${TAG\""), "{error}");
+
+    let error = interpolate("- a: \"x ${TAG:x} y\" # comment\n", &env).unwrap_err();
+    assert!(error.to_string().ends_with(": Invalid template: \"x ${TAG:x} y\""), "{error}");
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::{collections::HashMap, ffi::{OsStr, OsString}};
+
+use common::{config_env, get};
+use punktum::{build, env::{case_conflicts, AllowListEnv, CaseInsensitiveMap, DenyListEnv, GetEnv, KeyCaseEnv, KeyPattern}, options::{Builder, KeyCase}, Dialect, Env, Result};
+
+fn builder(key_case: KeyCase) -> Builder {
+    build().dialect(Dialect::Punktum).override_env(true).key_case(key_case)
+}
+
+fn os(key: &str) -> OsString {
+    key.into()
+}
+
+#[test]
+fn test_key_case_option() {
+    assert_eq!(KeyCase::try_from(OsStr::new("Insensitive")).unwrap(), KeyCase::Insensitive);
+    assert_eq!(KeyCase::try_from(OsStr::new("uppercase")).unwrap(), KeyCase::Uppercase);
+    assert!(KeyCase::try_from(OsStr::new("lower")).is_err());
+
+    let env = HashMap::from([(os("DOTENV_CONFIG_KEY_CASE"), os("insensitive"))]);
+    assert_eq!(env.get_key_case().unwrap(), KeyCase::Insensitive);
+}
+
+#[test]
+fn test_uppercase() -> Result<()> {
+    let mut env = HashMap::new();
+    config_env(builder(KeyCase::Uppercase), "Path=/bin\nfoo=1\nBAR=$foo\n", &mut env)?;
+
+    assert_eq!(env.len(), 3);
+    assert_eq!(get(&env, "PATH"), Some("/bin"));
+    assert_eq!(get(&env, "FOO"), Some("1"));
+    assert_eq!(get(&env, "BAR"), Some("1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_insensitive() -> Result<()> {
+    let mut env = HashMap::from([(os("Path"), os("/bin"))]);
+    config_env(builder(KeyCase::Insensitive), "PATH=$path:/usr/bin\nfoo=1\nFOO=2\n", &mut env)?;
+
+    assert_eq!(env.len(), 2);
+    assert_eq!(get(&env, "Path"), Some("/bin:/usr/bin"));
+    assert_eq!(get(&env, "foo"), Some("2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_mismatches() {
+    let mut env = HashMap::from([(os("Path"), os("/bin")), (os("PATH"), os("/usr/bin"))]);
+    assert_eq!(case_conflicts(&env), [[os("PATH"), os("Path")]]);
+
+    let mut key_case_env = KeyCaseEnv::new(&mut env, KeyCase::Insensitive);
+
+    // the smallest spelling wins, independent of the order of the HashMap
+    assert_eq!(key_case_env.get(OsStr::new("path")).as_deref(), Some(OsStr::new("/usr/bin")));
+
+    key_case_env.set(OsStr::new("Foo"), OsStr::new("1"));
+    key_case_env.set(OsStr::new("FOO"), OsStr::new("2"));
+    key_case_env.set(OsStr::new("Foo"), OsStr::new("3"));
+
+    assert_eq!(key_case_env.mismatches(), [
+        (os("path"), os("PATH")),
+        (os("FOO"), os("Foo")),
+    ]);
+    assert_eq!(key_case_env.conflicts(), [[os("PATH"), os("Path")]]);
+
+    // the next spelling is used once the smallest one is removed
+    key_case_env.remove(OsStr::new("path"));
+    assert_eq!(key_case_env.get(OsStr::new("PATH")).as_deref(), Some(OsStr::new("/bin")));
+    assert_eq!(get(&env, "Foo"), Some("3"));
+    assert_eq!(env.get(OsStr::new("FOO")), None);
+    assert_eq!(env.get(OsStr::new("PATH")), None);
+
+    let mut env = HashMap::new();
+    let mut key_case_env = KeyCaseEnv::new(&mut env, KeyCase::Uppercase);
+    key_case_env.set(OsStr::new("Foo"), OsStr::new("1"));
+    key_case_env.set(OsStr::new("FOO"), OsStr::new("2"));
+    assert_eq!(key_case_env.mismatches(), [(os("FOO"), os("Foo"))]);
+    assert_eq!(get(&env, "FOO"), Some("2"));
+}
+
+#[test]
+fn test_case_insensitive_map() -> Result<()> {
+    let map = HashMap::from([(os("Path"), os("a")), (os("PATH"), os("b")), (os("path"), os("c"))]);
+    let mut env = CaseInsensitiveMap::from(map);
+    assert_eq!(env.len(), 1);
+    assert_eq!(env.get_key(OsStr::new("pAtH")), Some(OsStr::new("PATH")));
+    assert_eq!(env.get(OsStr::new("path")).as_deref(), Some(OsStr::new("b")));
+
+    config_env(builder(KeyCase::Preserve), "Foo=1\nFOO=$foo$PATH\n", &mut env)?;
+    assert_eq!(env.len(), 2);
+    assert_eq!(env.get_key(OsStr::new("FOO")), Some(OsStr::new("Foo")));
+    assert_eq!(env.get(OsStr::new("foo")).as_deref(), Some(OsStr::new("1b")));
+
+    env.remove(OsStr::new("foo"));
+    assert_eq!(env.into_hash_map(), HashMap::from([(os("PATH"), os("b"))]));
+
+    Ok(())
+}
+
+#[test]
+fn test_filters_ignore_case() -> Result<()> {
+    let mut env = HashMap::new();
+    let patterns = KeyPattern::parse_all(["path", "App_*"])?;
+    let mut allowed = AllowListEnv::from_patterns(&mut env, patterns).with_ignore_case(true);
+    config_env(builder(KeyCase::Preserve), "PATH=/bin\nAPP_HOST=a\nOTHER=b\n", &mut allowed)?;
+    assert_eq!(env.len(), 2);
+    assert_eq!(get(&env, "PATH"), Some("/bin"));
+    assert_eq!(get(&env, "APP_HOST"), Some("a"));
+
+    let mut env = HashMap::new();
+    let deny_list = ["secret"];
+    let mut denied = DenyListEnv::from_slice(&mut env, &deny_list).with_ignore_case(true);
+    config_env(builder(KeyCase::Preserve), "SECRET=x\nSecret=y\nPUBLIC=z\n", &mut denied)?;
+    assert_eq!(env.len(), 1);
+    assert_eq!(get(&env, "PUBLIC"), Some("z"));
+
+    Ok(())
+}
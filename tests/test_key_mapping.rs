@@ -0,0 +1,65 @@
+mod common;
+
+use std::{collections::{BTreeMap, HashMap}, ffi::{OsStr, OsString}};
+
+use common::{config_env, get};
+use punktum::{build, env::{GetEnv, PrefixEnv, RenameEnv, StripPrefixEnv}, Dialect, Result};
+
+fn collect(env: &dyn GetEnv) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    env.for_each(&mut |key, value| {
+        assert_eq!(env.get(key).as_deref(), Some(value), "{key:?}");
+        vars.insert(key.to_string_lossy().into_owned(), value.to_string_lossy().into_owned());
+    });
+    vars
+}
+
+#[test]
+fn test_prefix_env() -> Result<()> {
+    let mut env = HashMap::new();
+    env.insert(OsString::from("HOST"), OsString::from("unprefixed"));
+
+    let mut prefixed = PrefixEnv::new(&mut env, OsStr::new("APP_"));
+    config_env(build().dialect(Dialect::Punktum), "HOST=localhost\nURL=http://$HOST/\n", &mut prefixed)?;
+    assert_eq!(collect(&prefixed).len(), 2);
+
+    assert_eq!(get(&env, "HOST"), Some("unprefixed"));
+    assert_eq!(get(&env, "APP_HOST"), Some("localhost"));
+    assert_eq!(get(&env, "APP_URL"), Some("http://localhost/"));
+
+    Ok(())
+}
+
+#[test]
+fn test_strip_prefix_env() -> Result<()> {
+    let mut env = HashMap::new();
+
+    let mut stripped = StripPrefixEnv::new(&mut env, OsStr::new("APP_"));
+    config_env(build().dialect(Dialect::Punktum), "APP_HOST=localhost\nAPP_URL=http://$APP_HOST/\nOTHER=x\n", &mut stripped)?;
+    assert_eq!(collect(&stripped).len(), 4);
+
+    assert_eq!(env.len(), 2);
+    assert_eq!(get(&env, "HOST"), Some("localhost"));
+    assert_eq!(get(&env, "URL"), Some("http://localhost/"));
+
+    Ok(())
+}
+
+#[test]
+fn test_rename_env() -> Result<()> {
+    let mut env = HashMap::new();
+
+    let renames = [("DB_HOST", "DATABASE_HOST"), ("DATABASE_HOST", "UNUSED")];
+    let mut renamed = RenameEnv::from_slice(&mut env, &renames);
+    config_env(build().dialect(Dialect::Punktum), "DB_HOST=db\nURL=postgres://$DB_HOST/\n", &mut renamed)?;
+    assert_eq!(collect(&renamed), BTreeMap::from([
+        ("DB_HOST".to_owned(), "db".to_owned()),
+        ("URL".to_owned(), "postgres://db/".to_owned()),
+    ]));
+
+    assert_eq!(env.len(), 2);
+    assert_eq!(get(&env, "DATABASE_HOST"), Some("db"));
+    assert_eq!(get(&env, "URL"), Some("postgres://db/"));
+
+    Ok(())
+}
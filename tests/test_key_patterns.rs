@@ -0,0 +1,79 @@
+mod common;
+
+use std::{collections::HashMap, ffi::OsStr};
+
+use common::{config_env, keys};
+use punktum::{build, env::{AllowListEnv, DenyListEnv, KeyPattern}, Dialect, Env, ErrorKind, Result};
+
+const SRC: &str = "APP_HOST=a\nAPP_PORT=b\nDB_PASSWORD=c\nOTHER=e\n";
+
+#[test]
+fn test_parse_key_pattern() -> Result<()> {
+    assert!(KeyPattern::parse(OsStr::new("FOO"))?.is_literal());
+    assert!(!KeyPattern::parse(OsStr::new("FOO_*"))?.is_literal());
+    assert!(!KeyPattern::parse(OsStr::new("glob:FOO"))?.is_literal());
+
+    let pattern = KeyPattern::parse(OsStr::new("literal:FOO_*"))?;
+    assert!(pattern.is_literal());
+    assert_eq!(pattern.source(), "FOO_*");
+    assert!(pattern.matches(OsStr::new("FOO_*")));
+    assert!(!pattern.matches(OsStr::new("FOO_BAR")));
+
+    let pattern = KeyPattern::parse(OsStr::new("F[A-Z]O_?"))?;
+    assert!(pattern.matches(OsStr::new("FOO_1")));
+    assert!(!pattern.matches(OsStr::new("FOO_12")));
+    assert!(!pattern.matches(OsStr::new("F0O_1")));
+    assert_eq!(pattern.to_string(), r#"glob "F[A-Z]O_?""#);
+
+    Ok(())
+}
+
+#[test]
+fn test_allow_list_patterns() -> Result<()> {
+    let mut env = HashMap::new();
+    let patterns = KeyPattern::parse_all(["APP_*", "literal:WEIRD*NAME"])?;
+    let mut allowed = AllowListEnv::from_patterns(&mut env, patterns);
+    config_env(build().dialect(Dialect::Punktum), SRC, &mut allowed)?;
+    allowed.set(OsStr::new("WEIRD*NAME"), OsStr::new("d"));
+    allowed.set(OsStr::new("WEIRD_NAME"), OsStr::new("f"));
+
+    assert_eq!(keys(&env), ["APP_HOST", "APP_PORT", "WEIRD*NAME"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_deny_list_patterns() -> Result<()> {
+    let mut env = HashMap::new();
+    let patterns = KeyPattern::parse_all(["*_PASSWORD", "glob:APP_PO*"])?;
+    let mut denied = DenyListEnv::from_patterns(&mut env, patterns).with_debug(true);
+    config_env(build().dialect(Dialect::Punktum), SRC, &mut denied)?;
+
+    assert_eq!(keys(&env), ["APP_HOST", "OTHER"]);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "regex"))]
+#[test]
+fn test_regex_without_feature() {
+    let err = KeyPattern::parse(OsStr::new("regex:^APP_")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OptionsParseError);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex_patterns() -> Result<()> {
+    let err = KeyPattern::parse(OsStr::new("regex:(")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OptionsParseError);
+
+    let mut env = HashMap::new();
+    let patterns = KeyPattern::parse_all(["regex:(APP|DB)_.*", "regex:OTH"])?;
+    let mut denied = DenyListEnv::from_patterns(&mut env, patterns);
+    config_env(build().dialect(Dialect::Punktum), SRC, &mut denied)?;
+
+    // regular expressions have to match the whole key
+    assert_eq!(keys(&env), ["OTHER"]);
+
+    Ok(())
+}
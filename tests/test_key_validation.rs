@@ -0,0 +1,80 @@
+mod common;
+
+use std::{collections::HashMap, ffi::{OsStr, OsString}};
+
+use common::{config, keys};
+use punktum::{build, env::GetEnv, options::{Builder, KeyValidation}, Dialect, ErrorKind, Result};
+
+fn builder(dialect: Dialect, key_validation: KeyValidation, strict: bool) -> Builder {
+    build().dialect(dialect).key_validation(key_validation).strict(strict)
+}
+
+const SRC: &str = "FOO=1\nfoo-bar=2\n1A=3\n  foo.baz=4\n_OK_2=5\n";
+
+#[test]
+fn test_key_validation_option() {
+    assert_eq!(KeyValidation::try_from(OsStr::new("POSIX")).unwrap(), KeyValidation::Posix);
+    assert_eq!(KeyValidation::try_from(OsStr::new("any")).unwrap(), KeyValidation::Any);
+    assert!(KeyValidation::try_from(OsStr::new("strict")).is_err());
+
+    let env = HashMap::from([(OsString::from("DOTENV_CONFIG_KEY_VALIDATION"), OsString::from("posix"))]);
+    assert_eq!(env.get_key_validation().unwrap(), KeyValidation::Posix);
+
+    assert!(KeyValidation::Posix.check("_FOO_1").is_ok());
+    assert!(KeyValidation::Posix.check("1FOO").is_err());
+    assert!(KeyValidation::Posix.check("").is_err());
+    assert!(KeyValidation::Any.check("foo bar.baz").is_ok());
+    assert!(KeyValidation::Any.check("a=b").is_err());
+    assert!(KeyValidation::Any.check("a\0b").is_err());
+}
+
+#[test]
+fn test_dialect() -> Result<()> {
+    let env = config(builder(Dialect::JavaScriptDotenv, KeyValidation::Dialect, true), SRC)?;
+    assert_eq!(keys(&env), ["1A", "FOO", "_OK_2", "foo-bar", "foo.baz"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_posix_skips() -> Result<()> {
+    let env = config(builder(Dialect::JavaScriptDotenv, KeyValidation::Posix, false), SRC)?;
+    assert_eq!(keys(&env), ["FOO", "_OK_2"]);
+
+    let env = config(builder(Dialect::JavaDotenv, KeyValidation::Posix, false), SRC)?;
+    assert_eq!(keys(&env), ["FOO", "_OK_2"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_posix_strict() {
+    let err = config(builder(Dialect::JavaScriptDotenv, KeyValidation::Posix, true), SRC).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    let location = err.location().unwrap();
+    assert_eq!((location.lineno(), location.column()), (2, 1));
+
+    let err = config(builder(Dialect::JavaScriptDotenv, KeyValidation::Posix, true), "FOO=1\n  foo.baz=4\n").unwrap_err();
+    let location = err.location().unwrap();
+    assert_eq!((location.lineno(), location.column()), (2, 3));
+
+    // reported at the key, not where a multi-line value ends
+    let err = config(builder(Dialect::Punktum, KeyValidation::Posix, true), "A=\"x\ny\"\n1A=\"x\ny\"\n").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    let location = err.location().unwrap();
+    assert_eq!((location.lineno(), location.column()), (3, 1));
+}
+
+#[test]
+fn test_any() -> Result<()> {
+    let src = "'a=b'=1\n'a b'=2\n";
+    let env = config(builder(Dialect::PythonDotenv, KeyValidation::Any, false), src)?;
+    assert_eq!(keys(&env), ["a b"]);
+
+    let err = config(builder(Dialect::PythonDotenv, KeyValidation::Any, true), src).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    let location = err.location().unwrap();
+    assert_eq!((location.lineno(), location.column()), (1, 1));
+
+    Ok(())
+}
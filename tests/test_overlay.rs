@@ -0,0 +1,80 @@
+use std::{collections::HashMap, ffi::{OsStr, OsString}};
+
+use punktum::{build, env::{GetEnv, OverlayEnv}, Dialect, Env, Result};
+
+fn base() -> HashMap<OsString, OsString> {
+    let mut base = HashMap::new();
+    base.insert("A".into(), "base a".into());
+    base.insert("B".into(), "base b".into());
+    base.insert("C".into(), "base c".into());
+    base
+}
+
+fn get(env: &impl GetEnv, key: &str) -> Option<String> {
+    env.get(OsStr::new(key)).map(|value| value.to_string_lossy().into_owned())
+}
+
+#[test]
+fn test_overlay() -> Result<()> {
+    let mut base = base();
+    let mut overlay = OverlayEnv::new(&mut base);
+
+    build().
+        dialect(Dialect::Punktum).
+        override_env(true).
+        config_with_reader(&b"A=top a\nB=base b\nD=top d\nunset C E\n"[..], &mut overlay, &HashMap::<OsString, OsString>::new())?;
+
+    assert_eq!(get(&overlay, "A").as_deref(), Some("top a"));
+    assert_eq!(get(&overlay, "B").as_deref(), Some("base b"));
+    assert_eq!(get(&overlay, "C"), None);
+    assert_eq!(get(&overlay, "D").as_deref(), Some("top d"));
+    assert_eq!(get(overlay.base(), "C").as_deref(), Some("base c"));
+    assert_eq!(get(overlay.base(), "D"), None);
+
+    let mut keys = vec![];
+    overlay.for_each(&mut |key, _| keys.push(key.to_owned()));
+    keys.sort();
+    assert_eq!(keys, ["A", "B", "D"]);
+
+    assert_eq!(overlay.diff(), vec![
+        ("A".into(), Some("top a".into())),
+        ("C".into(), None),
+        ("D".into(), Some("top d".into())),
+    ]);
+
+    overlay.set("C".as_ref(), "top c".as_ref());
+    overlay.remove("D".as_ref());
+    assert_eq!(get(&overlay, "C").as_deref(), Some("top c"));
+    assert_eq!(overlay.diff(), vec![
+        ("A".into(), Some("top a".into())),
+        ("C".into(), Some("top c".into())),
+    ]);
+
+    overlay.commit();
+    assert!(overlay.diff().is_empty());
+    assert!(overlay.top().is_empty());
+
+    let mut expected = self::base();
+    expected.insert("A".into(), "top a".into());
+    expected.insert("C".into(), "top c".into());
+    assert_eq!(base, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_overlay_as_parent() -> Result<()> {
+    let mut parent = OverlayEnv::new(base());
+    parent.set("A".as_ref(), "top a".as_ref());
+    parent.remove("B".as_ref());
+
+    let env = build().
+        dialect(Dialect::Punktum).
+        config_new_with_reader(&b"A\nB\nC\n"[..], &parent)?;
+
+    assert_eq!(get(&env, "A").as_deref(), Some("top a"));
+    assert_eq!(get(&env, "B"), None);
+    assert_eq!(get(&env, "C").as_deref(), Some("base c"));
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use std::{collections::HashMap, ffi::OsString};
+
+use common::TempDir;
+use punktum::{dialects::python_dotenv::{find_dotenv_from, get_key, set_key, unset_key, QuoteMode}, Encoding, Options, Result};
+
+#[test]
+fn test_set_key() -> Result<()> {
+    let dir = TempDir::new("set-key");
+    let path = dir.join(".env");
+    let options = Options::with_path(&path);
+
+    // creates the file
+    set_key(&options, "FOO", "bar", QuoteMode::Always, false)?;
+    assert_eq!(std::fs::read_to_string(&path)?, "FOO='bar'\n");
+
+    std::fs::write(&path, "# comment\r\nFOO=bar\nexport BAZ=\"x y\" # c\nbroken line \"\nLAST=1")?;
+
+    set_key(&options, "FOO", "it's", QuoteMode::Always, false)?;
+    set_key(&options, "NEW", "abc", QuoteMode::Auto, true)?;
+    set_key(&options, "EMPTY", "", QuoteMode::Auto, false)?;
+    set_key(&options, "RAW", "a b", QuoteMode::Never, false)?;
+
+    assert_eq!(std::fs::read_to_string(&path)?,
+        "# comment\nFOO='it\\'s'\nexport BAZ=\"x y\" # c\nbroken line \"\nLAST=1\nexport NEW=abc\nEMPTY=''\nRAW=a b\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_unset_key() -> Result<()> {
+    let dir = TempDir::new("unset-key");
+    let path = dir.join(".env");
+    let options = Options::with_path(&path);
+
+    assert!(!unset_key(&options, "FOO")?);
+    assert!(!path.exists());
+
+    std::fs::write(&path, "FOO=1\nBAR=2\nFOO=3\n")?;
+    assert!(unset_key(&options, "FOO")?);
+    assert_eq!(std::fs::read_to_string(&path)?, "BAR=2\n");
+    assert!(!unset_key(&options, "FOO")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_set_key_keeps_encoding() -> Result<()> {
+    let dir = TempDir::new("set-key-encoding");
+    let path = dir.join(".env");
+    let options = Options { encoding: Encoding::Auto, ..Options::with_path(&path) };
+
+    let utf16le = |src: &str| -> Vec<u8> {
+        [0xFF, 0xFE].into_iter().chain(src.encode_utf16().flat_map(u16::to_le_bytes)).collect()
+    };
+
+    std::fs::write(&path, utf16le("FOO=1\nBAR=2\n"))?;
+    assert_eq!(get_key(&options, &HashMap::<OsString, OsString>::new(), "BAR")?.as_deref(), Some("2"));
+
+    set_key(&options, "FOO", "ä", QuoteMode::Never, false)?;
+    assert!(unset_key(&options, "BAR")?);
+    assert_eq!(std::fs::read(&path)?, utf16le("FOO=ä\n"));
+
+    // a UTF-8 BOM is kept too, but isn't part of the first key
+    std::fs::write(&path, "\u{FEFF}FOO=1\n")?;
+    set_key(&options, "FOO", "2", QuoteMode::Never, false)?;
+    assert_eq!(std::fs::read_to_string(&path)?, "\u{FEFF}FOO=2\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_get_key() -> Result<()> {
+    let dir = TempDir::new("get-key");
+    let path = dir.join(".env");
+    std::fs::write(&path, "A=${PARENT}-a\nPARENT=file\nB=${PARENT}-b\nC\nD=${C:-default}\n")?;
+
+    let mut parent = HashMap::<OsString, OsString>::new();
+    parent.insert("PARENT".into(), "parent".into());
+
+    let options = Options::with_path(&path);
+    assert_eq!(get_key(&options, &parent, "A")?.as_deref(), Some("parent-a"));
+    assert_eq!(get_key(&options, &parent, "B")?.as_deref(), Some("file-b"));
+    assert_eq!(get_key(&options, &parent, "C")?, None);
+    assert_eq!(get_key(&options, &parent, "D")?.as_deref(), Some(""));
+    assert_eq!(get_key(&options, &parent, "NOPE")?, None);
+
+    let mut options = Options::with_path(&path);
+    options.dialect_options.python.interpolate = false;
+    assert_eq!(get_key(&options, &parent, "B")?.as_deref(), Some("${PARENT}-b"));
+
+    let options = Options::with_path(dir.join("missing.env"));
+    assert_eq!(get_key(&options, &parent, "A")?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_find_dotenv() -> Result<()> {
+    let dir = TempDir::new("find-dotenv");
+    let sub = dir.join("a").join("b");
+    std::fs::create_dir_all(&sub)?;
+    std::fs::write(dir.join("a").join(".env.test"), "")?;
+
+    assert_eq!(find_dotenv_from(".env.test", &sub)?, Some(dir.join("a").join(".env.test")));
+    assert_eq!(find_dotenv_from(".env.test", sub.join("..").join("..").join("a"))?, Some(dir.join("a").join(".env.test")));
+    assert_eq!(find_dotenv_from(".env.test", dir.path())?, None);
+    assert!(find_dotenv_from(".env.test", dir.join("missing")).is_err());
+
+    Ok(())
+}
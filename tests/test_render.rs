@@ -0,0 +1,72 @@
+use std::{collections::HashMap, ffi::OsString};
+
+use punktum::{error::SourceLocation, render::{render, RenderOptions, Syntax}, ErrorKind, Result};
+
+fn env() -> HashMap<OsString, OsString> {
+    let mut env = HashMap::new();
+    env.insert(OsString::from("HOST"), OsString::from("example.com"));
+    env.insert(OsString::from("PORT"), OsString::from("8080"));
+    env.insert(OsString::from("EMPTY"), OsString::from(""));
+    env
+}
+
+fn options(syntax: Syntax, vars: Option<&[&str]>, strict: bool) -> RenderOptions {
+    RenderOptions {
+        syntax,
+        vars: vars.map(|vars| vars.iter().map(OsString::from).collect()),
+        strict,
+    }
+}
+
+#[test]
+fn test_render() -> Result<()> {
+    let env = env();
+
+    let opts = options(Syntax::Punktum, None, true);
+    assert_eq!(render("server $HOST:${PORT};\n", &env, &opts)?, "server example.com:8080;\n");
+    assert_eq!(render("${EMPTY:-\"a b\"} ${UNSET-x} $ 5$\n", &env, &opts)?, "a b x $ 5$\n");
+
+    let opts = options(Syntax::ComposeGo, None, true);
+    assert_eq!(render("listen ${PORT:-80}; cost $$5 ${UNSET:-$HOST}", &env, &opts)?, "listen 8080; cost $5 example.com");
+
+    let opts = options(Syntax::Ruby, None, true);
+    assert_eq!(render("$HOST:${PORT} \\$HOST $(whoami)", &env, &opts)?, "example.com:8080 $HOST $(whoami)");
+
+    Ok(())
+}
+
+#[test]
+fn test_render_vars() -> Result<()> {
+    let env = env();
+    let vars: &[&str] = &["HOST"];
+
+    let opts = options(Syntax::Punktum, Some(vars), true);
+    assert_eq!(render("$HOST $PORT ${PORT:-80} $UNSET\n", &env, &opts)?, "example.com $PORT ${PORT:-80} $UNSET\n");
+
+    let opts = options(Syntax::ComposeGo, Some(vars), true);
+    assert_eq!(render("$HOST $PORT ${PORT:-80} $$HOST\n", &env, &opts)?, "example.com $PORT ${PORT:-80} $HOST\n");
+
+    let opts = options(Syntax::Ruby, Some(vars), true);
+    assert_eq!(render("${HOST} $PORT \\$HOST\n", &env, &opts)?, "example.com $PORT $HOST\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_render_strict() -> Result<()> {
+    let env = env();
+
+    for syntax in [Syntax::Punktum, Syntax::ComposeGo, Syntax::Ruby] {
+        let error = render("a\nb $HOST ${UNSET}\n", &env, &options(syntax, None, true)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::SubstitutionError, "{syntax}");
+        assert_eq!(*error.location(), Some(SourceLocation::new(2, 9)), "{syntax}");
+        assert!(error.to_string().ends_with(": undefined variable UNSET"), "{syntax}: {error}");
+
+        assert_eq!(render("a\nb $HOST ${UNSET}\n", &env, &options(syntax, None, false))?, "a\nb example.com \n", "{syntax}");
+
+        // not substituted, so not checked either
+        assert_eq!(render("$UNSET", &env, &options(syntax, Some(&["HOST"]), true))?, "$UNSET", "{syntax}");
+    }
+
+    Ok(())
+}
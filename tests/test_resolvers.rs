@@ -0,0 +1,91 @@
+mod common;
+
+use std::{collections::HashMap, ffi::OsString};
+
+use common::{config, config_with_parent, get, TempDir};
+use punktum::{build, error::SourceLocation, options::Builder, resolver::{register_resolver, ResolveContext, Resolver}, Dialect, ErrorKind, Result};
+
+fn builder(resolvers: &[&str]) -> Builder {
+    build().dialect(Dialect::Punktum).resolvers(resolvers.iter().copied())
+}
+
+#[test]
+fn test_file_resolver() -> Result<()> {
+    let dir = TempDir::new("file-resolver");
+    let path = dir.write("secret", "  s3cr3t\n\n");
+    let src = format!("SECRET=${{file:{}}}\nQUOTED=\"<${{file:{}}}>\"\n", path.display(), path.display());
+    let env = config(builder(&["file"]), src)?;
+
+    assert_eq!(get(&env, "SECRET"), Some("s3cr3t"));
+    assert_eq!(get(&env, "QUOTED"), Some("<s3cr3t>"));
+
+    Ok(())
+}
+
+#[test]
+fn test_env_resolver() -> Result<()> {
+    let parent = HashMap::from([(OsString::from("HOME"), OsString::from("/home/user"))]);
+
+    let env = config_with_parent(builder(&["env"]), "A=${env:HOME}\nB=${env:UNSET}\nNAME=HOME\nC=${env:$NAME}\n", &parent)?;
+
+    assert_eq!(get(&env, "A"), Some("/home/user"));
+    assert_eq!(get(&env, "B"), Some(""));
+    assert_eq!(get(&env, "C"), Some("/home/user"));
+    // not imported
+    assert_eq!(get(&env, "HOME"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_disabled_resolver() -> Result<()> {
+    let env = config(builder(&[]), "file=archive.tar.gz\nA=${file:8}\n")?;
+
+    assert_eq!(get(&env, "A"), Some("tar.gz"));
+
+    // numeric arguments are substrings even with the resolver enabled
+    let env = config(builder(&["file"]), "file=archive.tar.gz\nA=${file:8}\nB=${file: -2:1}\nC=${file:1 }\n")?;
+    assert_eq!(get(&env, "A"), Some("tar.gz"));
+    assert_eq!(get(&env, "B"), Some("g"));
+    assert_eq!(get(&env, "C"), Some("rchive.tar.gz"));
+
+    Ok(())
+}
+
+struct Upper;
+
+impl Resolver for Upper {
+    fn resolve(&self, argument: &str, _context: &ResolveContext<'_>) -> std::result::Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        if argument.is_empty() {
+            return Err("empty argument".into());
+        }
+        Ok(argument.to_uppercase())
+    }
+}
+
+#[test]
+fn test_custom_resolver() -> Result<()> {
+    register_resolver("upper", Upper).unwrap();
+    assert!(register_resolver("file", Upper).is_err());
+    assert!(register_resolver("no-scheme", Upper).is_err());
+
+    let env = config(builder(&["upper"]), "NAME=world\nA=${upper:hello $NAME}\n")?;
+    assert_eq!(get(&env, "A"), Some("HELLO WORLD"));
+
+    let error = config(builder(&["upper"]), "A=x\nB=\"-${upper:}\"\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(2, 5)));
+    assert!(error.to_string().ends_with(": upper: empty argument"), "{error}");
+
+    Ok(())
+}
+
+#[test]
+fn test_resolver_errors() {
+    let error = config(builder(&["file"]), "A=1\nB=${file:/nonexistent/punktum/secret}\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(2, 3)));
+
+    let error = config(builder(&["nonexistent"]), "A=1\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::OptionsParseError);
+}
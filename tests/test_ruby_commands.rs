@@ -0,0 +1,95 @@
+mod common;
+
+use std::{collections::HashMap, ffi::OsString, time::Duration};
+
+use common::{config, get, TempDir};
+use punktum::{build, build_from, Dialect, ErrorKind, Result};
+
+#[test]
+fn test_commands_disabled_by_default() -> Result<()> {
+    let env = config(build().dialect(Dialect::RubyDotenv), "FOO=$(echo foo)\n")?;
+    assert_eq!(get(&env, "FOO"), Some("$(echo foo)"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_allowed_commands() -> Result<()> {
+    let src = concat!(
+        "FOO=$(echo foo  'bar  baz')\n",
+        "BAR=\"<$(printf 'a\\nb\\n\\n')>\"\n",
+        "ESCAPED=\\$(echo foo)\n",
+        "CMD='$(echo foo)'\n",
+        "INDIRECT=\"$CMD\"\n",
+        "ENV=$(env)\n",
+    );
+
+    let env = config(build().dialect(Dialect::RubyDotenv).ruby_allow_commands(["echo", "printf", "env"]), src)?;
+    assert_eq!(get(&env, "FOO"), Some("foo bar  baz"));
+    assert_eq!(get(&env, "BAR"), Some("<a\nb\n>"));
+    assert_eq!(get(&env, "ESCAPED"), Some("$(echo foo)"));
+    // values of variables are never executed
+    assert_eq!(get(&env, "INDIRECT"), Some("$(echo foo)"));
+    // the environment is cleared
+    assert_eq!(get(&env, "ENV"), Some(""));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_refused_commands() -> Result<()> {
+    let builder = || build().dialect(Dialect::RubyDotenv).ruby_allow_commands(["echo", "sleep"]).ruby_command_timeout(Duration::from_millis(200));
+
+    for src in ["FOO=$(cat /etc/passwd)\n", "FOO=$(echo foo | cat)\n", "FOO=$(echo $HOME)\n", "FOO=$(sleep 5)\n"] {
+        let env = config(builder().strict(false), src)?;
+        assert_eq!(get(&env, "FOO"), Some(""), "{src:?}");
+
+        let err = config(builder().strict(true), src).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ExecError, "{src:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_command_path() -> Result<()> {
+    let dir = TempDir::new("command-path");
+    std::os::unix::fs::symlink("/bin/echo", dir.join("shout"))?;
+
+    let builder = || build().dialect(Dialect::RubyDotenv).ruby_allow_commands(["shout", "echo"]).strict(true);
+
+    // a PATH defined by the .env file itself is not used to find programs
+    let err = config(builder(), format!("PATH={}\nFOO=$(shout hi)\n", dir.path().display())).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ExecError);
+
+    let env = config(builder().ruby_command_path(dir.path()), "PATH=/nonexistent\nFOO=$(shout hi)\n")?;
+    assert_eq!(get(&env, "FOO"), Some("hi"));
+
+    let err = config(builder().ruby_command_path(dir.path()), "FOO=$(echo hi)\n").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ExecError);
+
+    Ok(())
+}
+
+#[test]
+fn test_command_options_from_env() -> Result<()> {
+    let mut config_env = HashMap::<OsString, OsString>::new();
+    config_env.insert("DOTENV_CONFIG_RUBY_ALLOW_COMMANDS".into(), "echo, /usr/bin/date".into());
+    config_env.insert("DOTENV_CONFIG_RUBY_COMMAND_TIMEOUT".into(), "1.5".into());
+    config_env.insert("PATH".into(), "/opt/punktum/bin".into());
+
+    let builder = build_from(&config_env)?;
+    let ruby = &builder.options().dialect_options.ruby;
+    assert_eq!(ruby.allow_commands, [OsString::from("echo"), OsString::from("/usr/bin/date")]);
+    assert_eq!(ruby.command_timeout, Duration::from_millis(1500));
+    assert_eq!(ruby.command_path.as_deref(), Some("/opt/punktum/bin".as_ref()));
+
+    config_env.insert("DOTENV_CONFIG_RUBY_COMMAND_TIMEOUT".into(), "-1".into());
+    let err = build_from(&config_env).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OptionsParseError);
+
+    Ok(())
+}
@@ -1 +1,206 @@
-// TODO
+mod common;
+
+use common::{config, get};
+use punktum::{build, error::SourceLocation, options::KeyValidation, Dialect, ErrorKind, Result};
+
+const BASE: &str = "\
+FILE=archive.tar.gz
+PATH_VAR=/usr/local/bin/punktum
+WORDS=\"hello world\"
+EMPTY=
+";
+
+#[test]
+fn test_assign_default() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), format!("{BASE}\
+A=${{UNSET1:=first}}-$UNSET1
+B=${{EMPTY:=not empty}}
+C=${{EMPTY2=}}${{EMPTY2=x}}
+D=${{FILE:=other}}
+E=${{UNSET3=\"a b\"}}
+"))?;
+
+    assert_eq!(get(&env, "A"), Some("first-first"));
+    assert_eq!(get(&env, "UNSET1"), Some("first"));
+    assert_eq!(get(&env, "B"), Some("not empty"));
+    assert_eq!(get(&env, "C"), Some(""));
+    assert_eq!(get(&env, "EMPTY2"), Some(""));
+    assert_eq!(get(&env, "D"), Some("archive.tar.gz"));
+    assert_eq!(get(&env, "E"), Some("a b"));
+    assert_eq!(get(&env, "UNSET3"), Some("a b"));
+
+    // not assigned when skipped
+    let env = config(build().dialect(Dialect::Punktum), format!("{BASE}X=${{FILE:-${{UNSET4:=foo}}}}\n"))?;
+    assert_eq!(get(&env, "X"), Some("archive.tar.gz"));
+    assert_eq!(get(&env, "UNSET4"), None);
+
+    // assigned variables are subject to the key validation, too
+    let src = "A=x${1B:=b}\n";
+    let env = config(build().dialect(Dialect::Punktum).key_validation(KeyValidation::Posix).strict(false), src)?;
+    assert_eq!(get(&env, "A"), Some("xb"));
+    assert_eq!(get(&env, "1B"), None);
+
+    let err = config(build().dialect(Dialect::Punktum).key_validation(KeyValidation::Posix).strict(true), src).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert_eq!(*err.location(), Some(SourceLocation::new(1, 6)));
+
+    Ok(())
+}
+
+#[test]
+fn test_length() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), format!("{BASE}\
+A=${{#FILE}}
+B=${{#EMPTY}}
+C=${{#UNSET}}
+D=\"ä\"
+E=${{#D}}
+"))?;
+
+    assert_eq!(get(&env, "A"), Some("14"));
+    assert_eq!(get(&env, "B"), Some("0"));
+    assert_eq!(get(&env, "C"), Some("0"));
+    assert_eq!(get(&env, "E"), Some("1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_strip_pattern() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), format!("{BASE}\
+A=${{FILE#*.}}
+B=${{FILE##*.}}
+C=${{FILE%.*}}
+D=${{FILE%%.*}}
+E=${{PATH_VAR##*/}}
+F=${{PATH_VAR%/*}}
+G=${{FILE#nomatch}}
+H=${{FILE#[a-c]?}}
+I=${{FILE%[![:alpha:]]gz}}
+J=${{UNSET#*}}
+K=${{FILE#\"archive\"}}
+"))?;
+
+    assert_eq!(get(&env, "A"), Some("tar.gz"));
+    assert_eq!(get(&env, "B"), Some("gz"));
+    assert_eq!(get(&env, "C"), Some("archive.tar"));
+    assert_eq!(get(&env, "D"), Some("archive"));
+    assert_eq!(get(&env, "E"), Some("punktum"));
+    assert_eq!(get(&env, "F"), Some("/usr/local/bin"));
+    assert_eq!(get(&env, "G"), Some("archive.tar.gz"));
+    assert_eq!(get(&env, "H"), Some("chive.tar.gz"));
+    assert_eq!(get(&env, "I"), Some("archive.tar"));
+    assert_eq!(get(&env, "J"), Some(""));
+    assert_eq!(get(&env, "K"), Some(".tar.gz"));
+
+    Ok(())
+}
+
+#[test]
+fn test_replace_pattern() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), format!("{BASE}\
+A=${{WORDS/o/0}}
+B=${{WORDS//o/0}}
+C=${{WORDS/#hello/bye}}
+D=${{WORDS/%world/\"you all\"}}
+E=${{WORDS/l*o}}
+F=${{WORDS//[lo]}}
+G=${{WORDS/#/>}}
+H=${{WORDS/%/<}}
+I=${{WORDS//}}
+J=${{PATH_VAR//\"/\"/:}}
+K=${{WORDS/xyz/abc}}
+L=${{WORDS//o/$FILE}}
+"))?;
+
+    assert_eq!(get(&env, "A"), Some("hell0 world"));
+    assert_eq!(get(&env, "B"), Some("hell0 w0rld"));
+    assert_eq!(get(&env, "C"), Some("bye world"));
+    assert_eq!(get(&env, "D"), Some("hello you all"));
+    assert_eq!(get(&env, "E"), Some("herld"));
+    assert_eq!(get(&env, "F"), Some("he wrd"));
+    assert_eq!(get(&env, "G"), Some(">hello world"));
+    assert_eq!(get(&env, "H"), Some("hello world<"));
+    assert_eq!(get(&env, "I"), Some("hello world"));
+    assert_eq!(get(&env, "J"), Some(":usr:local:bin:punktum"));
+    assert_eq!(get(&env, "K"), Some("hello world"));
+    assert_eq!(get(&env, "L"), Some("hellarchive.tar.gz warchive.tar.gzrld"));
+
+    Ok(())
+}
+
+#[test]
+fn test_substring() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), format!("{BASE}\
+A=${{FILE:8}}
+B=${{FILE:8:3}}
+C=${{FILE: -2}}
+D=${{FILE: -6:3}}
+E=${{FILE:0:-3}}
+F=${{FILE:20}}
+G=${{FILE: -20}}
+H=${{FILE:2:100}}
+N=3
+I=${{FILE:$N:$N}}
+"))?;
+
+    assert_eq!(get(&env, "A"), Some("tar.gz"));
+    assert_eq!(get(&env, "B"), Some("tar"));
+    assert_eq!(get(&env, "C"), Some("gz"));
+    assert_eq!(get(&env, "D"), Some("tar"));
+    assert_eq!(get(&env, "E"), Some("archive.tar"));
+    assert_eq!(get(&env, "F"), Some(""));
+    assert_eq!(get(&env, "G"), Some(""));
+    assert_eq!(get(&env, "H"), Some("chive.tar.gz"));
+    assert_eq!(get(&env, "I"), Some("hiv"));
+
+    Ok(())
+}
+
+#[test]
+fn test_substring_errors() {
+    let error = config(build().dialect(Dialect::Punktum), format!("{BASE}A=${{FILE:x}}\n")).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(5, 10)));
+
+    let error = config(build().dialect(Dialect::Punktum), format!("{BASE}A=${{FILE:10:-10}}\n")).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert!(error.to_string().ends_with(": substring expression < 0: -10"), "{error}");
+}
+
+#[test]
+fn test_case_conversion() -> Result<()> {
+    let env = config(build().dialect(Dialect::Punktum), format!("{BASE}\
+A=${{WORDS^}}
+B=${{WORDS^^}}
+C=${{B,}}
+D=${{B,,}}
+E=${{WORDS^^[lo]}}
+F=${{WORDS^[!h]}}
+G=\"straße\"
+H=${{G^^}}
+"))?;
+
+    assert_eq!(get(&env, "A"), Some("Hello world"));
+    assert_eq!(get(&env, "B"), Some("HELLO WORLD"));
+    assert_eq!(get(&env, "C"), Some("hELLO WORLD"));
+    assert_eq!(get(&env, "D"), Some("hello world"));
+    assert_eq!(get(&env, "E"), Some("heLLO wOrLd"));
+    assert_eq!(get(&env, "F"), Some("hello world"));
+    assert_eq!(get(&env, "H"), Some("STRASSE"));
+
+    Ok(())
+}
+
+#[test]
+fn test_unterminated_default() -> Result<()> {
+    // the quoted default value swallows the rest of the file
+    let env = config(build().dialect(Dialect::Punktum).strict(false), "A=${X:-\"a}\nB=1\n")?;
+    assert_eq!(get(&env, "A"), Some("a}\nB=1\n"));
+    assert_eq!(get(&env, "B"), None);
+
+    let error = config(build().dialect(Dialect::Punktum).strict(true), "A=${X:-\"a}\nB=1\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::{collections::HashMap, ffi::OsString};
+
+use common::{get, TempDir};
+use punktum::{build, Dialect, Env, ErrorKind, Result};
+
+fn temp_dir(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    dir.write("a.env", "A=a\nKEEP=new\n");
+    dir.write("b.env", "B=$A-b\n");
+    dir.write("broken.env", "C=c\nD=\"unterminated\n");
+    dir
+}
+
+fn env() -> HashMap<OsString, OsString> {
+    let mut env = HashMap::new();
+    env.insert("KEEP".into(), "old".into());
+    env
+}
+
+#[test]
+fn test_transaction() -> Result<()> {
+    let dir = temp_dir("transaction");
+    let parent = HashMap::<OsString, OsString>::new();
+    let builder = build().dialect(Dialect::Punktum).override_env(true);
+
+    let mut committed = env();
+    let ok = builder.clone().config_transaction(&mut committed, &parent, &[dir.join("a.env"), dir.join("b.env")]);
+
+    let mut aborted = env();
+    let error = builder.config_transaction(&mut aborted, &parent, &[dir.join("a.env"), dir.join("broken.env"), dir.join("b.env")]);
+
+    ok?;
+    assert_eq!(get(&committed, "A"), Some("a"));
+    assert_eq!(get(&committed, "B"), Some("a-b"));
+    assert_eq!(get(&committed, "KEEP"), Some("new"));
+
+    let error = error.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+    assert_eq!(error.path(), Some(dir.join("broken.env").as_path()));
+    assert!(error.to_string().starts_with(&format!("SyntaxError in {}", dir.join("broken.env").to_string_lossy())), "{error}");
+    assert_eq!(aborted, env());
+
+    Ok(())
+}
+
+#[test]
+fn test_transaction_closure() {
+    let mut env = env();
+    let result: Result<()> = punktum::transaction(&mut env, |env| {
+        env.set("A".as_ref(), "a".as_ref());
+        env.remove("KEEP".as_ref());
+        Err(ErrorKind::IllegalArgument.into())
+    });
+
+    assert!(result.is_err());
+    assert_eq!(env, self::env());
+
+    punktum::transaction(&mut env, |env| {
+        env.remove("KEEP".as_ref());
+        Ok(())
+    }).unwrap();
+
+    assert!(env.is_empty());
+}
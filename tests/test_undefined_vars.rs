@@ -0,0 +1,73 @@
+mod common;
+
+use common::{config, get};
+use punktum::{build, error::SourceLocation, options::{Builder, UndefinedVars}, Dialect, ErrorKind, Result};
+
+fn builder(dialect: Dialect, undefined_vars: UndefinedVars, strict: bool) -> Builder {
+    build().dialect(dialect).undefined_vars(undefined_vars).strict(strict)
+}
+
+fn assert_undefined(src: &str, dialect: Dialect, name: &str, location: SourceLocation) {
+    let error = config(builder(dialect, UndefinedVars::Error, true), src).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError, "{dialect}: {error}");
+    assert_eq!(*error.location(), Some(location), "{dialect}: {error}");
+    assert!(error.to_string().ends_with(&format!(": undefined variable {name}")), "{dialect}: {error}");
+}
+
+#[test]
+fn test_undefined_vars() {
+    for dialect in [Dialect::Punktum, Dialect::ComposeGo, Dialect::RubyDotenv] {
+        assert_undefined("A=1\nB=\"x $A $UNDEF\"\n", dialect, "UNDEF", SourceLocation::new(2, 9));
+        assert_undefined("A=1\nB=${A}${UNDEF}\n", dialect, "UNDEF", SourceLocation::new(2, 7));
+        assert_undefined("A1=1\nB=\"$A1 $A\"\n", dialect, "A", SourceLocation::new(2, 8));
+    }
+
+    assert_undefined("A=1\nB=\"${A}\n${UNDEF}\"\n", Dialect::PythonDotenv, "UNDEF", SourceLocation::new(3, 1));
+    assert_undefined("A=${UNDEF:-x} ${UNDEF}\n", Dialect::PythonDotenv, "UNDEF", SourceLocation::new(1, 15));
+    assert_undefined("A=${UNDEF:-x} ${UNDEF}\n", Dialect::ComposeGo, "UNDEF", SourceLocation::new(1, 15));
+    assert_undefined("A=\"\\t${X:-${UNDEF}} $UNDEF\"\n", Dialect::ComposeGo, "UNDEF", SourceLocation::new(1, 11));
+    assert_undefined("A=${X-$X}\n", Dialect::ComposeGo, "X", SourceLocation::new(1, 7));
+    assert_undefined("A=${#UNDEF}\n", Dialect::Punktum, "UNDEF", SourceLocation::new(1, 3));
+}
+
+#[test]
+fn test_defaults_are_not_undefined() -> Result<()> {
+    let src = "A=${UNDEF:-x}${UNDEF-y}${UNDEF:+z}${UNDEF+z}\n";
+    for dialect in [Dialect::Punktum, Dialect::ComposeGo] {
+        let env = config(builder(dialect, UndefinedVars::Error, true), src)?;
+        assert_eq!(get(&env, "A"), Some("xy"), "{dialect}");
+    }
+
+    let env = config(builder(Dialect::PythonDotenv, UndefinedVars::Error, true), "A=${UNDEF:-x}\n")?;
+    assert_eq!(get(&env, "A"), Some("x"));
+
+    Ok(())
+}
+
+#[test]
+fn test_undefined_vars_non_strict() -> Result<()> {
+    let src = "A=\"<$UNDEF>\"\n";
+    for mode in [UndefinedVars::Ignore, UndefinedVars::Warn, UndefinedVars::Error] {
+        let env = config(builder(Dialect::Punktum, mode, false), src)?;
+        assert_eq!(get(&env, "A"), Some("<>"), "{mode}");
+    }
+
+    let env = config(builder(Dialect::Punktum, UndefinedVars::Warn, true), src)?;
+    assert_eq!(get(&env, "A"), Some("<>"));
+
+    Ok(())
+}
+
+#[test]
+fn test_undefined_vars_deferred() -> Result<()> {
+    let builder = build().undefined_vars(UndefinedVars::Error).deferred_substitution(true);
+
+    let env = config(builder.clone(), "A=$B\nB=1\n")?;
+    assert_eq!(get(&env, "A"), Some("1"));
+
+    let error = config(builder, "A=$B\nB=1\nC=$UNDEF\n").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SubstitutionError);
+    assert_eq!(*error.location(), Some(SourceLocation::new(3, 3)));
+
+    Ok(())
+}